@@ -42,6 +42,10 @@ impl Component for Button {
             Event::Focused => {}
             Event::Unfocused => {}
             Event::Resize(_) => {}
+            Event::SaveSession
+            | Event::RequestClose
+            | Event::CloseCancelled
+            | Event::FileChanged(_) => {}
             Event::Close => panic!("Event::Close should not have been forwarded"),
         }
     }
@@ -82,6 +86,10 @@ impl Component for ToggleButton {
             Event::Focused => {}
             Event::Unfocused => {}
             Event::Resize(_) => {}
+            Event::SaveSession
+            | Event::RequestClose
+            | Event::CloseCancelled
+            | Event::FileChanged(_) => {}
             Event::Close => panic!("Event::Close should not have been forwarded"),
         }
     }
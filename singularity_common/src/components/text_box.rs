@@ -23,6 +23,23 @@ impl TextBox {
         self.text.get_text_as_string()
     }
 
+    /// puts the cursor after the last character
+    pub fn move_cursor_to_end(&mut self) {
+        self.cursor_logical_position.1 = self.text.content.len() - 1;
+        self.cursor_logical_position.0 = self.text.content[self.cursor_logical_position.1].len();
+    }
+
+    /// (x, y) or (col, row)
+    pub fn get_cursor(&self) -> (usize, usize) {
+        self.cursor_logical_position
+    }
+
+    /// Clamped to the text, so it is fine if the text changed since the cursor was saved
+    pub fn set_cursor(&mut self, cursor_logical_position: (usize, usize)) {
+        self.cursor_logical_position = cursor_logical_position;
+        self.clamp_everything();
+    }
+
     fn clamp_everything(&mut self) {
         {
             // clamp cursor
@@ -184,6 +201,10 @@ impl Component for TextBox {
             Event::Focused => {}
             Event::Unfocused => {}
             Event::Resize(_) => {}
+            Event::SaveSession
+            | Event::RequestClose
+            | Event::CloseCancelled
+            | Event::FileChanged(_) => {}
             Event::Close => panic!("Event::Close should not have been forwarded"),
         }
 
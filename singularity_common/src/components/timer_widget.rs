@@ -110,6 +110,10 @@ impl Component for TimerWidget {
             Event::Focused => {}
             Event::Unfocused => {}
             Event::Resize(_) => {}
+            Event::SaveSession
+            | Event::RequestClose
+            | Event::CloseCancelled
+            | Event::FileChanged(_) => {}
             Event::Close => panic!("Event::Close should not have been forwarded"),
         }
 
@@ -0,0 +1,444 @@
+//! Keyboard shortcuts as named actions, so that nothing has to match on raw keycodes.
+//!
+//! Shortcuts are written like `"Alt+Shift+P"`, `"Ctrl+S"`, `"Escape"`, or just `"w"`.
+//! Letters are matched by the character they type (not where they are on the keyboard),
+//! so the shortcuts follow the layout.
+//!
+//! Overrides replace all the default shortcuts of an action, and are read from (later overrides earlier):
+//! 1. the user config: `$XDG_CONFIG_HOME/singularity/keymap.json` (or `~/.config/singularity/keymap.json`)
+//! 2. the project: `keymap` in `.project/core.json`
+//!
+//! Both look like `{ "manager.quit": "Ctrl+Q", "tree.parent": ["h", "0"] }`, and `[]` unbinds an action.
+
+use crate::utils::tree::tree_node_path::TreeTraversal;
+use serde::{Deserialize, Serialize};
+use singularity_ui::ui_event::{Key, KeyModifiers, KeyTrait, UIEvent};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// The default shortcuts for every action that singularity itself uses.
+///
+/// NOTE: the `tree.*` actions are also used by the manager with modifiers on top (see `get_tree_traversal`)
+const DEFAULT_BINDINGS: &[(&str, &[&str])] = &[
+    // manager
+    ("manager.quit", &["Ctrl+Q"]),
+    ("manager.toggle_focuser", &["Alt+Enter"]),
+    ("manager.pluck_or_place", &["Alt+Shift+P"]),
+    ("manager.swap_focused_and_focusing", &["Alt+Shift+S"]),
+    ("manager.close_tab", &["Ctrl+W"]),
+    ("manager.close_tab.reattach_children", &["Ctrl+Shift+W"]),
+    ("manager.close_tab.promote_first_child", &["Ctrl+Alt+W"]),
+    ("manager.tile.transpose", &["Logo+T"]),
+    ("manager.tile.swap_siblings", &["Logo+S"]),
+    ("manager.tile.maximize", &["Logo+F"]),
+    ("manager.tile.toggle_floating", &["Logo+Shift+Space"]),
+    ("manager.tile.minimize", &["Logo+M"]),
+    ("manager.tile.restore_minimized", &["Logo+Shift+M"]),
+    // by where tiles are on screen, like tiling window managers
+    ("manager.tile.focus_left", &["Logo+Left"]),
+    ("manager.tile.focus_right", &["Logo+Right"]),
+    ("manager.tile.focus_up", &["Logo+Up"]),
+    ("manager.tile.focus_down", &["Logo+Down"]),
+    ("manager.tile.move_left", &["Logo+Shift+Left"]),
+    ("manager.tile.move_right", &["Logo+Shift+Right"]),
+    ("manager.tile.move_up", &["Logo+Shift+Up"]),
+    ("manager.tile.move_down", &["Logo+Shift+Down"]),
+    ("manager.tile.increment_split", &["Logo+="]),
+    ("manager.tile.decrement_split", &["Logo+-"]),
+    ("manager.tile.toggle_tabbed", &["Logo+W"]),
+    ("manager.tile.next_tab", &["Logo+Tab"]),
+    ("manager.tile.previous_tab", &["Logo+Shift+Tab"]),
+    ("manager.tile.cycle_layout", &["Logo+L"]),
+    ("manager.open_launcher", &["Ctrl+T"]),
+    // workspaces (the new one gets the focused tab)
+    ("manager.workspace.next", &["Logo+PageDown"]),
+    ("manager.workspace.previous", &["Logo+PageUp"]),
+    ("manager.workspace.create", &["Logo+N"]),
+    ("manager.workspace.rename", &["Logo+R"]),
+    ("manager.workspace.delete", &["Logo+Delete"]),
+    // tab launcher (only while it is open)
+    ("launcher.previous_type", &["Up"]),
+    ("launcher.next_type", &["Down"]),
+    ("launcher.launch", &["Enter"]),
+    ("launcher.cancel", &["Escape"]),
+    // workspace name prompt (only while it is open)
+    ("workspace_prompt.confirm", &["Enter"]),
+    ("workspace_prompt.cancel", &["Escape"]),
+    // a tab using files it doesn't have permission for (only while it is open)
+    ("permission_prompt.allow_once", &["y"]),
+    ("permission_prompt.allow_always", &["a"]),
+    ("permission_prompt.deny", &["n", "Escape"]),
+    // crashed tab tile (only while the focused tab is crashed)
+    ("crashed_tab.restart", &["r", "Enter"]),
+    ("crashed_tab.close", &["c"]),
+    // "save unsaved changes?" prompt when closing a tab
+    ("close_prompt.save", &["y"]),
+    ("close_prompt.discard", &["n"]),
+    ("close_prompt.cancel", &["Escape"]),
+    // common to tabs
+    ("tab.save", &["Ctrl+S"]),
+    // tree traversal, see `TreeNodePath::checked_traverse_based_on_wasd` for the reasoning
+    ("tree.parent", &["a", "0"]),
+    ("tree.first_child", &["d"]),
+    ("tree.previous_sibling", &["w"]),
+    ("tree.next_sibling", &["s"]),
+    ("tree.dfs_previous", &["q"]),
+    ("tree.dfs_next", &["e"]),
+    ("tree.child_1", &["1"]),
+    ("tree.child_2", &["2"]),
+    ("tree.child_3", &["3"]),
+    ("tree.child_4", &["4"]),
+    ("tree.child_5", &["5"]),
+    ("tree.child_6", &["6"]),
+    ("tree.child_7", &["7"]),
+    ("tree.child_8", &["8"]),
+    ("tree.last_child", &["9"]),
+    // specific tabs
+    ("file_manager.open_file", &["f"]),
+    ("task_organizer.add_task", &["+"]),
+    ("task_organizer.start_editing", &["Enter"]),
+    ("task_organizer.stop_editing", &["Escape"]),
+    ("time_manager.toggle_timer", &["Space"]),
+];
+
+/// Named keys that don't type a character, and their linux keycode (see `Key::raw_code`)
+const NAMED_KEYS: &[(&str, u32)] = &[
+    ("Escape", 1),
+    ("Backspace", 14),
+    ("Tab", 15),
+    ("Enter", 28),
+    ("Home", 102),
+    ("Up", 103),
+    ("PageUp", 104),
+    ("Left", 105),
+    ("Right", 106),
+    ("End", 107),
+    ("Down", 108),
+    ("PageDown", 109),
+    ("Insert", 110),
+    ("Delete", 111),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShortcutKey {
+    /// Matched by what the key types
+    Char(char),
+    /// Keys that don't type anything (like escape) are matched by keycode
+    RawCode(u32),
+}
+
+/// Serialized as its string form (see the module docs)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Shortcut {
+    /// only ctrl, alt, shift, and logo matter
+    pub modifiers: KeyModifiers,
+    pub key: ShortcutKey,
+}
+impl Shortcut {
+    pub fn matches(&self, key: &Key, modifiers: KeyModifiers) -> bool {
+        let key_matches = match self.key {
+            ShortcutKey::Char(shortcut_char) => key
+                .to_char()
+                .is_some_and(|c| c.to_lowercase().eq(shortcut_char.to_lowercase())),
+            ShortcutKey::RawCode(raw_code) => key.raw_code == raw_code,
+        };
+
+        // characters that aren't letters already include shift (like "+"),
+        // and whether shift is needed depends on the layout
+        let ignore_shift = matches!(self.key, ShortcutKey::Char(c) if !c.is_alphabetic());
+
+        key_matches
+            && self.modifiers.ctrl == modifiers.ctrl
+            && self.modifiers.alt == modifiers.alt
+            && self.modifiers.logo == modifiers.logo
+            && (ignore_shift || self.modifiers.shift == modifiers.shift)
+    }
+}
+impl FromStr for Shortcut {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // the key is after the last "+", unless the key itself is "+"
+        let (modifiers_str, key_str) = if s == "+" {
+            (None, s)
+        } else if let Some(modifiers_str) = s.strip_suffix("++") {
+            (Some(modifiers_str), "+")
+        } else {
+            match s.rsplit_once('+') {
+                Some((modifiers_str, key_str)) => (Some(modifiers_str), key_str),
+                None => (None, s),
+            }
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in modifiers_str.into_iter().flat_map(|m| m.split('+')) {
+            modifiers = modifiers
+                | match modifier.to_lowercase().as_str() {
+                    "ctrl" | "control" => KeyModifiers::CTRL,
+                    "alt" => KeyModifiers::ALT,
+                    "shift" => KeyModifiers::SHIFT,
+                    "logo" | "super" | "win" | "meta" => KeyModifiers::LOGO,
+                    _ => return Err(format!("unknown modifier `{modifier}` in `{s}`")),
+                };
+        }
+
+        let key = if let Some(&(_, raw_code)) = NAMED_KEYS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key_str))
+        {
+            ShortcutKey::RawCode(raw_code)
+        } else if key_str.eq_ignore_ascii_case("Space") {
+            ShortcutKey::Char(' ')
+        } else if key_str.eq_ignore_ascii_case("Esc") {
+            ShortcutKey::RawCode(1)
+        } else if key_str.eq_ignore_ascii_case("Return") {
+            ShortcutKey::RawCode(28)
+        } else if let Some(raw_code) = key_str
+            .strip_prefix('<')
+            .and_then(|key_str| key_str.strip_suffix('>'))
+        {
+            // unnamed keys are displayed as `<raw_code>`
+            ShortcutKey::RawCode(
+                raw_code
+                    .parse()
+                    .map_err(|_| format!("invalid keycode `{raw_code}` in `{s}`"))?,
+            )
+        } else {
+            let mut chars = key_str.chars();
+            match (chars.next(), chars.next()) {
+                // lowercase, so that "Q" and "q" are the same shortcut
+                (Some(c), None) => ShortcutKey::Char(c.to_ascii_lowercase()),
+                _ => return Err(format!("unknown key `{key_str}` in `{s}`")),
+            }
+        };
+
+        Ok(Self { modifiers, key })
+    }
+}
+impl std::fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (is_held, name) in [
+            (self.modifiers.ctrl, "Ctrl"),
+            (self.modifiers.alt, "Alt"),
+            (self.modifiers.shift, "Shift"),
+            (self.modifiers.logo, "Logo"),
+        ] {
+            if is_held {
+                write!(f, "{name}+")?;
+            }
+        }
+
+        match self.key {
+            ShortcutKey::Char(' ') => write!(f, "Space"),
+            ShortcutKey::Char(c) => write!(f, "{}", c.to_uppercase()),
+            ShortcutKey::RawCode(raw_code) => {
+                match NAMED_KEYS.iter().find(|(_, code)| *code == raw_code) {
+                    Some((name, _)) => write!(f, "{name}"),
+                    None => write!(f, "<{raw_code}>"),
+                }
+            }
+        }
+    }
+}
+
+impl From<Shortcut> for String {
+    fn from(shortcut: Shortcut) -> Self {
+        shortcut.to_string()
+    }
+}
+impl TryFrom<String> for Shortcut {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// What is written in the config files, see the module docs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KeymapOverride {
+    Single(String),
+    Multiple(Vec<String>),
+}
+pub type KeymapOverrides = BTreeMap<String, KeymapOverride>;
+
+/// NOTE: serializable so it can be sent to tabs in other processes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keymap {
+    /// action name to its shortcuts
+    bindings: BTreeMap<String, Vec<Shortcut>>,
+}
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            bindings: DEFAULT_BINDINGS
+                .iter()
+                .map(|(action, shortcuts)| {
+                    (
+                        action.to_string(),
+                        shortcuts
+                            .iter()
+                            .map(|shortcut| shortcut.parse().expect("default shortcuts are valid"))
+                            .collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+impl Keymap {
+    /// Defaults, then the user config, then the project's overrides
+    pub fn load(project_overrides: &KeymapOverrides) -> Self {
+        Self::load_with_user_config(Self::user_config_path().as_deref(), project_overrides)
+    }
+
+    /// `load`, but with the user config at `user_config_path` instead (None for no user config)
+    pub fn load_with_user_config(
+        user_config_path: Option<&Path>,
+        project_overrides: &KeymapOverrides,
+    ) -> Self {
+        let mut keymap = Self::default();
+
+        if let Some(user_overrides) = user_config_path.and_then(Self::read_user_overrides) {
+            keymap.apply_overrides(&user_overrides);
+        }
+        keymap.apply_overrides(project_overrides);
+
+        keymap
+    }
+
+    pub fn user_config_path() -> Option<PathBuf> {
+        Some(crate::project::user_settings::user_config_directory()?.join("keymap.json"))
+    }
+
+    fn read_user_overrides(user_config_path: &Path) -> Option<KeymapOverrides> {
+        let user_config = std::fs::read_to_string(user_config_path).ok()?;
+
+        match serde_json::from_str(&user_config) {
+            Ok(user_overrides) => Some(user_overrides),
+            Err(err) => {
+                println!("Ignoring `{}`: {err}", user_config_path.display());
+                None
+            }
+        }
+    }
+
+    /// Shortcuts that can't be parsed are skipped (with a message), the rest of the action still gets overriden
+    pub fn apply_overrides(&mut self, overrides: &KeymapOverrides) {
+        for (action, keymap_override) in overrides {
+            let shortcut_strs = match keymap_override {
+                KeymapOverride::Single(shortcut_str) => std::slice::from_ref(shortcut_str),
+                KeymapOverride::Multiple(shortcut_strs) => shortcut_strs.as_slice(),
+            };
+
+            let shortcuts = shortcut_strs
+                .iter()
+                .filter_map(|shortcut_str| match shortcut_str.parse() {
+                    Ok(shortcut) => Some(shortcut),
+                    Err(err) => {
+                        println!("Ignoring shortcut for `{action}`: {err}");
+                        None
+                    }
+                })
+                .collect();
+
+            self.bindings.insert(action.clone(), shortcuts);
+        }
+    }
+
+    pub fn get_shortcuts(&self, action: &str) -> &[Shortcut] {
+        self.bindings.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    /// For showing the user, like `"r/Enter"`
+    pub fn describe_shortcuts(&self, action: &str) -> String {
+        self.get_shortcuts(action)
+            .iter()
+            .map(|shortcut| shortcut.to_string())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    pub fn is_action(&self, action: &str, key: &Key, modifiers: KeyModifiers) -> bool {
+        self.get_shortcuts(action)
+            .iter()
+            .any(|shortcut| shortcut.matches(key, modifiers))
+    }
+
+    /// Finds the action that the key press is bound to,
+    /// only looking at actions in the namespaces (eg: `"tab"` has `"tab.save"`).
+    ///
+    /// If multiple actions match, the first alphabetically wins.
+    pub fn get_action(
+        &self,
+        namespaces: &[&str],
+        key: &Key,
+        modifiers: KeyModifiers,
+    ) -> Option<&str> {
+        self.bindings
+            .iter()
+            .filter(|(action, _)| {
+                namespaces.iter().any(|namespace| {
+                    action
+                        .strip_prefix(namespace)
+                        .is_some_and(|rest| rest.starts_with('.'))
+                })
+            })
+            .find(|(_, shortcuts)| {
+                shortcuts
+                    .iter()
+                    .any(|shortcut| shortcut.matches(key, modifiers))
+            })
+            .map(|(action, _)| action.as_str())
+    }
+
+    /// `get_action` but takes the event, and is None if it isn't a key press
+    pub fn get_action_from_event(&self, namespaces: &[&str], ui_event: &UIEvent) -> Option<&str> {
+        match ui_event {
+            UIEvent::KeyPress(key, modifiers) => self.get_action(namespaces, key, *modifiers),
+            _ => None,
+        }
+    }
+
+    /// The `tree.*` action as a traversal.
+    ///
+    /// The manager uses the same keys but with modifiers held,
+    /// so the modifiers in `extra_modifiers` are removed before matching.
+    pub fn get_tree_traversal(
+        &self,
+        key: &Key,
+        modifiers: KeyModifiers,
+        extra_modifiers: KeyModifiers,
+    ) -> Option<TreeTraversal> {
+        let held_extra_modifiers = modifiers & extra_modifiers;
+        if held_extra_modifiers != extra_modifiers {
+            return None;
+        }
+        let modifiers = KeyModifiers {
+            ctrl: modifiers.ctrl && !extra_modifiers.ctrl,
+            alt: modifiers.alt && !extra_modifiers.alt,
+            shift: modifiers.shift && !extra_modifiers.shift,
+            logo: modifiers.logo && !extra_modifiers.logo,
+            ..modifiers
+        };
+
+        match self.get_action(&["tree"], key, modifiers)? {
+            "tree.parent" => Some(TreeTraversal::Parent),
+            "tree.first_child" => Some(TreeTraversal::FirstChild),
+            "tree.previous_sibling" => Some(TreeTraversal::PreviousSibling),
+            "tree.next_sibling" => Some(TreeTraversal::NextSibling),
+            "tree.dfs_previous" => Some(TreeTraversal::DfsPrevious),
+            "tree.dfs_next" => Some(TreeTraversal::DfsNext),
+            "tree.last_child" => Some(TreeTraversal::LastChild),
+            action => {
+                let child_number: usize = action.strip_prefix("tree.child_")?.parse().ok()?;
+                Some(TreeTraversal::Child(child_number.checked_sub(1)?))
+            }
+        }
+    }
+}
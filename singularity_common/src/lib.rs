@@ -1,6 +1,7 @@
 //! For things that are used by both the manager and subapps.
 
 pub mod components;
+pub mod keymap;
 pub mod project;
 pub mod tab;
 pub mod utils;
@@ -1,36 +1,285 @@
-use project_settings::ProjectSettings;
-use std::path::PathBuf;
+use project_settings::{MergedSubappSettings, OpenTabs, ProjectSettings, SettingsLayer};
+use schema::{SchemaError, BLOCKS_SCHEMA, CORE_SCHEMA, SESSION_SCHEMA, TASKS_SCHEMA};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+use user_settings::UserSettings;
 
 pub mod project_settings;
+pub mod schema;
+pub mod user_settings;
+
+/// What a new project's task organizer starts with (a `RecursiveTreeNode` of its tasks)
+const EMPTY_TASKS: &str = r#"{
+  "value": {
+    "title": "Root",
+    "body": "",
+    "is_complete": false,
+    "timer": null
+  },
+  "children": []
+}"#;
+/// What a new project's settings start with: the standard tabs get what they need,
+/// and any other file is asked about (see `project_settings::SubappFileSystemPermissions`)
+const DEFAULT_CORE: &str = r#"{
+  "subapps": {
+    "editor": {
+      "subapp_standard_settings": {
+        "subapp_file_system_permissions": {
+          "property": { "location": "[$project_directory]", "read": true, "write": true }
+        }
+      }
+    },
+    "file_manager": {
+      "subapp_standard_settings": {
+        "subapp_file_system_permissions": {
+          "property": { "location": "[$project_directory]", "read": true }
+        }
+      }
+    },
+    "task_organizer": {
+      "subapp_standard_settings": {
+        "subapp_file_system_permissions": {
+          "property": { "location": "[$project_directory]/.project/tasks.json", "read": true, "write": true }
+        }
+      }
+    },
+    "time_manager": {
+      "subapp_standard_settings": {
+        "subapp_file_system_permissions": {
+          "property": { "location": "[$project_directory]/.project/blocks.json", "read": true, "write": true }
+        }
+      }
+    }
+  }
+}"#;
+/// The session changes all the time and has ids and areas in it, so it's not for git
+const GITIGNORE: &str = "session.json\n*.backup\n";
 
 pub struct Project {
     project_directory: PathBuf,
     /// REVIEW: dangerous to expose this?
     pub project_settings: ProjectSettings,
+    /// The open tabs, workspaces and focus, from `.project/session.json`
+    /// (or `.project/session.template.json` until there is one).
+    /// Kept apart from the settings so `core.json` only changes when the settings do.
+    pub session: Option<OpenTabs>,
+    /// the user's settings with the project's on top, by subapp name
+    subapp_settings: HashMap<String, MergedSubappSettings>,
 }
 impl Project {
+    /// Panics if it can't be opened, see `Self::try_new`
     pub fn new<P>(project_directory: P) -> Self
     where
         P: AsRef<std::path::Path>,
         PathBuf: std::convert::From<P>,
     {
-        Self {
-            project_settings: Self::parse_project_settings(&project_directory),
+        Self::try_new(project_directory).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// `new`, but with these instead of the user's settings file
+    pub fn new_with_user_settings<P>(project_directory: P, user_settings: &UserSettings) -> Self
+    where
+        P: AsRef<std::path::Path>,
+        PathBuf: std::convert::From<P>,
+    {
+        Self::try_new_with_user_settings(project_directory, user_settings)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Initializes it first if it's a directory that isn't a project yet (see `Self::init_if_new`)
+    pub fn try_new<P>(project_directory: P) -> Result<Self, ProjectError>
+    where
+        P: AsRef<std::path::Path>,
+        PathBuf: std::convert::From<P>,
+    {
+        Self::try_new_with_user_settings(project_directory, &UserSettings::load())
+    }
+
+    pub fn try_new_with_user_settings<P>(
+        project_directory: P,
+        user_settings: &UserSettings,
+    ) -> Result<Self, ProjectError>
+    where
+        P: AsRef<std::path::Path>,
+        PathBuf: std::convert::From<P>,
+    {
+        Self::init_if_new(&project_directory).map_err(ProjectError::Init)?;
+        let mut project_settings =
+            Self::parse_project_settings(&project_directory).map_err(ProjectError::Settings)?;
+        let session = Self::parse_session(&project_directory, "session.json")
+            .map_err(ProjectError::Session)?
+            // from before `session.json`
+            .or_else(|| project_settings.open_tabs.take());
+        // what the project starts with, since `session.json` isn't for git (it's never saved over)
+        let session = match session {
+            Some(session) => Some(session),
+            None => Self::parse_session(&project_directory, "session.template.json")
+                .map_err(ProjectError::Session)?,
+        };
+
+        let subapp_settings = user_settings
+            .subapps
+            .keys()
+            .chain(project_settings.subapps.keys())
+            .map(|subapp_name| {
+                let layers = [
+                    (SettingsLayer::User, user_settings.subapps.get(subapp_name)),
+                    (
+                        SettingsLayer::Project,
+                        project_settings.subapps.get(subapp_name),
+                    ),
+                ];
+                let merged = MergedSubappSettings::merge(
+                    layers
+                        .into_iter()
+                        .filter_map(|(layer, settings)| Some((layer, settings?))),
+                );
+
+                (subapp_name.clone(), merged)
+            })
+            .collect();
+
+        Ok(Self {
+            project_settings,
+            session,
+            subapp_settings,
             project_directory: PathBuf::from(project_directory),
+        })
+    }
+
+    /// If it has a `.project/core.json`
+    pub fn is_project<P: AsRef<Path>>(project_directory: P) -> bool {
+        project_directory
+            .as_ref()
+            .join(".project/core.json")
+            .is_file()
+    }
+
+    /// Makes an existing directory a project the first time it's opened.
+    ///
+    /// Errors if the directory doesn't exist, since that's more likely a typo than a new project
+    /// (`init` is for making new ones).
+    pub fn init_if_new<P: AsRef<Path>>(project_directory: P) -> std::io::Result<()> {
+        let project_directory = project_directory.as_ref();
+        if Self::is_project(project_directory) {
+            return Ok(());
         }
+        if !project_directory.is_dir() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!(
+                    "`{0}` isn't a directory, use `singularity_manager init {0}` to make a project there",
+                    project_directory.display()
+                ),
+            ));
+        }
+
+        // first run in this directory
+        println!(
+            "Initializing a new project in `{}`",
+            project_directory.display()
+        );
+        Self::init(project_directory, None)
+    }
+
+    /// `<user config>/templates`, where each directory is a project template
+    pub fn templates_directory() -> Option<PathBuf> {
+        Some(user_settings::user_config_directory()?.join("templates"))
     }
 
-    fn parse_project_settings<P>(project_directory: P) -> ProjectSettings
+    /// Makes `project_directory` a project, optionally from the template with that name
+    /// (see `Self::templates_directory`).
+    ///
+    /// Only creates what's missing, so this is safe to run on an existing project.
+    pub fn init<P: AsRef<Path>>(
+        project_directory: P,
+        template: Option<&str>,
+    ) -> std::io::Result<()> {
+        let template_directory = match template {
+            Some(template) => {
+                let template_directory = Self::templates_directory()
+                    .map(|templates_directory| templates_directory.join(template))
+                    .filter(|template_directory| template_directory.is_dir())
+                    .ok_or_else(|| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::NotFound,
+                            format!("no project template named `{template}`"),
+                        )
+                    })?;
+                Some(template_directory)
+            }
+            None => None,
+        };
+
+        Self::init_with_template_directory(project_directory, template_directory.as_deref())
+    }
+
+    /// `init`, with a template's directory instead of its name.
+    /// The template's files are copied over, and then the default project files are added if the template doesn't have them.
+    pub fn init_with_template_directory<P: AsRef<Path>>(
+        project_directory: P,
+        template_directory: Option<&Path>,
+    ) -> std::io::Result<()> {
+        let project_directory = project_directory.as_ref();
+        std::fs::create_dir_all(project_directory.join(".project"))?;
+
+        if let Some(template_directory) = template_directory {
+            copy_missing(template_directory, project_directory)?;
+        }
+
+        for (file_name, default_content) in [
+            (
+                "core.json",
+                CORE_SCHEMA.to_string(&serde_json::from_str::<ProjectSettings>(DEFAULT_CORE)?),
+            ),
+            (
+                "tasks.json",
+                TASKS_SCHEMA.to_string(&serde_json::from_str::<serde_json::Value>(EMPTY_TASKS)?),
+            ),
+            (
+                "blocks.json",
+                BLOCKS_SCHEMA.to_string(&schema::BlocksFile::<()>::default()),
+            ),
+            (".gitignore", GITIGNORE.to_string()),
+        ] {
+            let file_path = project_directory.join(".project").join(file_name);
+            if !file_path.exists() {
+                std::fs::write(file_path, default_content)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_project_settings<P>(project_directory: P) -> Result<ProjectSettings, SchemaError>
     where
         P: AsRef<std::path::Path>,
     {
         let core_project_settings_path = project_directory.as_ref().join(".project/core.json");
-        serde_json::from_str(
-            &std::fs::read_to_string(&core_project_settings_path).expect(
-                "project directories should have a core project file in `.project/core.json`",
-            ),
-        )
-        .expect("core project file should be formatted correctly")
+        CORE_SCHEMA.load(&core_project_settings_path)
+    }
+
+    /// None if there's no session yet (or it's broken, so the project still opens).
+    /// Only errors if it's from a newer build, since starting over would overwrite it.
+    fn parse_session<P>(
+        project_directory: P,
+        file_name: &str,
+    ) -> Result<Option<OpenTabs>, SchemaError>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let session_path = project_directory.as_ref().join(".project").join(file_name);
+        match SESSION_SCHEMA.load(&session_path) {
+            Ok(session) => Ok(Some(session)),
+            Err(SchemaError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err @ SchemaError::TooNew { .. }) => Err(err),
+            Err(err) => {
+                println!("Ignoring the session in `{}`: {err}", session_path.display());
+                Ok(None)
+            }
+        }
     }
 
     pub fn get_project_directory(&self) -> &PathBuf {
@@ -41,10 +290,67 @@ impl Project {
         &self.project_settings
     }
 
+    /// The merged settings of a subapp (empty if neither the user nor the project have any)
+    pub fn get_subapp_settings(&self, subapp_name: &str) -> MergedSubappSettings {
+        self.subapp_settings
+            .get(subapp_name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Only the session, see `Self::save_to_file` for the settings
+    pub fn save_session(&mut self, session: OpenTabs) {
+        let session_path = self.project_directory.join(".project/session.json");
+        SESSION_SCHEMA
+            .save(&session_path, &session)
+            .expect("failed to write the session to `.project/session.json`");
+        self.session = Some(session);
+    }
+
+    /// Only the settings, see `Self::save_session` for the session
     pub fn save_to_file(&self) {
         let core_project_settings_path = self.project_directory.join(".project/core.json");
-        let serialized_project = serde_json::to_string_pretty(&self.project_settings).unwrap();
-        std::fs::write(core_project_settings_path, serialized_project)
+        CORE_SCHEMA
+            .save(&core_project_settings_path, &self.project_settings)
             .expect("failed to write serialized project to `.project/core.json`");
     }
 }
+
+/// Why a project couldn't be opened
+#[derive(Debug)]
+pub enum ProjectError {
+    /// from `Project::init_if_new`
+    Init(std::io::Error),
+    /// `.project/core.json` is missing or broken
+    Settings(SchemaError),
+    /// `.project/session.json` (or the template) is from a newer build (a broken one is just ignored)
+    Session(SchemaError),
+}
+impl std::fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProjectError::Init(err) => write!(f, "couldn't initialize the project: {err}"),
+            ProjectError::Settings(err) => {
+                write!(f, "couldn't load the core project file: {err}")
+            }
+            ProjectError::Session(err) => write!(f, "couldn't load the session: {err}"),
+        }
+    }
+}
+impl std::error::Error for ProjectError {}
+
+/// Recursively copies `from` into `to`, without overwriting anything
+fn copy_missing(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let destination = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_missing(&entry.path(), &destination)?;
+        } else if !destination.exists() {
+            std::fs::copy(entry.path(), destination)?;
+        }
+    }
+
+    Ok(())
+}
@@ -1,7 +1,21 @@
-use crate::{tab::tile::Tiles, utils::id_map::IdMap};
+use crate::{
+    keymap::KeymapOverrides,
+    tab::{
+        file_system::{resolve_path, FileSystemAccess},
+        overlay::Overlay,
+        tile::{LayoutPolicy, Tiles},
+    },
+    utils::id_map::IdMap,
+};
 use serde::{Deserialize, Serialize};
 use singularity_ui::display_units::DisplayArea;
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+/// Filled in with the manager's project directory in `SubappFileSystemPermission::location`
+pub const PROJECT_DIRECTORY_PLACEHOLDER: &str = "[$project_directory]";
 
 #[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
 pub struct SubappFileSystemPermission {
@@ -17,10 +31,58 @@ pub struct SubappFileSystemPermission {
     execute: bool,
 }
 
+impl SubappFileSystemPermission {
+    pub fn new(location: impl Into<String>, read: bool, write: bool, execute: bool) -> Self {
+        Self {
+            location: location.into(),
+            read,
+            write,
+            execute,
+        }
+    }
+
+    /// `location` with the placeholders filled in (see `PROJECT_DIRECTORY_PLACEHOLDER`)
+    pub fn expand_location(&self, project_directory: &Path) -> PathBuf {
+        resolve_path(Path::new(&self.location.replace(
+            PROJECT_DIRECTORY_PLACEHOLDER,
+            &project_directory.to_string_lossy(),
+        )))
+    }
+
+    /// If `path` is the location (or inside it, for directories)
+    pub fn allows(&self, path: &Path, access: FileSystemAccess, project_directory: &Path) -> bool {
+        let has_access = match access {
+            FileSystemAccess::Read => self.read,
+            FileSystemAccess::Write => self.write,
+            FileSystemAccess::Execute => self.execute,
+        };
+
+        has_access && resolve_path(path).starts_with(self.expand_location(project_directory))
+    }
+}
+
+/// Checked by the manager whenever a tab uses files, see `crate::tab::file_system`
 #[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
 pub struct SubappFileSystemPermissions {
     property: Option<SubappFileSystemPermission>,
 }
+impl SubappFileSystemPermissions {
+    pub fn new(property: Option<SubappFileSystemPermission>) -> Self {
+        Self { property }
+    }
+
+    pub fn allows(&self, path: &Path, access: FileSystemAccess, project_directory: &Path) -> bool {
+        self.property
+            .as_ref()
+            .is_some_and(|permission| permission.allows(path, access, project_directory))
+    }
+}
+/// For subapps without any: nothing, so the user is asked about every file
+impl Default for SubappFileSystemPermissions {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
 
 #[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
 pub struct SubappStandardSettings {
@@ -33,6 +95,73 @@ pub struct SubappSettings {
     subapp_specific_settings: Option<HashMap<String, serde_json::Value>>,
 }
 
+/// Where a setting came from. Later layers override earlier ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum SettingsLayer {
+    /// see `crate::project::user_settings`
+    User,
+    /// `.project/core.json`
+    Project,
+}
+
+/// A setting, and the layer it came from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Layered<T> {
+    pub value: T,
+    pub layer: SettingsLayer,
+}
+
+/// `SubappSettings` from every layer, merged. This is what tabs see.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MergedSubappSettings {
+    /// comes from a single layer, as a whole
+    pub subapp_file_system_permissions: Option<Layered<SubappFileSystemPermissions>>,
+    /// merged key by key
+    pub subapp_specific_settings: BTreeMap<String, Layered<serde_json::Value>>,
+}
+impl MergedSubappSettings {
+    /// `layers` from lowest to highest precedence
+    pub fn merge<'a>(
+        layers: impl IntoIterator<Item = (SettingsLayer, &'a SubappSettings)>,
+    ) -> Self {
+        let mut merged = Self::default();
+
+        for (layer, subapp_settings) in layers {
+            if let Some(subapp_file_system_permissions) = subapp_settings
+                .subapp_standard_settings
+                .as_ref()
+                .and_then(|standard_settings| {
+                    standard_settings.subapp_file_system_permissions.clone()
+                })
+            {
+                merged.subapp_file_system_permissions = Some(Layered {
+                    value: subapp_file_system_permissions,
+                    layer,
+                });
+            }
+
+            for (key, value) in subapp_settings.subapp_specific_settings.iter().flatten() {
+                merged.subapp_specific_settings.insert(
+                    key.clone(),
+                    Layered {
+                        value: value.clone(),
+                        layer,
+                    },
+                );
+            }
+        }
+
+        merged
+    }
+
+    /// Without where it came from
+    pub fn get_specific_setting(&self, key: &str) -> Option<&serde_json::Value> {
+        self.subapp_specific_settings
+            .get(key)
+            .map(|layered| &layered.value)
+    }
+}
+
 /// NOTE: Read devlog ~2024/10/29 for description; this is like SessionStorage for webdev
 /// REVIEW: rename?
 /// REVIEW: include Area and UIElement and TabType into this?
@@ -52,6 +181,9 @@ pub struct OpenTab {
     pub tab_data: TabData,
 }
 /// REVIEW: alternative name for open tab: tab session
+///
+/// `focused_tab`, `display_tiles`, and `overlay` are the active workspace's,
+/// so sessions from before workspaces are just one workspace.
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct OpenTabs {
     pub tabs: IdMap<OpenTab>,
@@ -63,11 +195,67 @@ pub struct OpenTabs {
     // /// currently, last in vec is "top" in gui
     // pub display_order: Vec<Uuid>,
     pub display_tiles: Tiles,
+    /// Some if a different layout was chosen for this session, instead of the project's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout_policy: Option<LayoutPolicy>,
+    /// floating, minimized, and maximized tabs (only maximized ones can also be in `display_tiles`)
+    #[serde(default, skip_serializing_if = "Overlay::is_empty")]
+    pub overlay: Overlay,
+
+    /// the active workspace's name
+    #[serde(default = "default_workspace_name")]
+    pub workspace_name: String,
+    /// the other workspaces, in order (the active one goes at `active_workspace_index` among them)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inactive_workspaces: Vec<Workspace>,
+    #[serde(default)]
+    pub active_workspace_index: usize,
+}
+
+pub fn default_workspace_name() -> String {
+    "main".to_string()
 }
 
+/// An arrangement of some of the tabs, with its own tiles and focus.
+/// Every tab is in exactly one workspace, but they all share the org tree.
 #[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Workspace {
+    pub name: String,
+    pub focused_tab: crate::utils::id_map::Id<crate::tab::TabHandler>,
+    pub display_tiles: Tiles,
+    #[serde(default, skip_serializing_if = "Overlay::is_empty")]
+    pub overlay: Overlay,
+}
+
+/// A tab type that runs as its own process, see `crate::tab::external`
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct ExternalTabSettings {
+    /// defaults to the tab type
+    pub display_name: Option<String>,
+    /// Relative paths (like `"./tools/my_tab"`) are from the project directory,
+    /// plain names (like `"my_tab"`) are looked up in PATH
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// `.project/core.json`, see `crate::project::Project::init` for what a new project starts with
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
 pub struct ProjectSettings {
+    /// subapp name (the tab type in lowercase) to its settings, on top of the user's.
+    /// See `crate::project::Project::get_subapp_settings`
     pub subapps: HashMap<String, SubappSettings>,
-    /// TODO: move this out of settings
-    pub open_tabs: Option<OpenTabs>,
+    /// tab type to how to run it
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub external_tabs: BTreeMap<String, ExternalTabSettings>,
+    /// shortcut overrides for this project, see `crate::keymap`
+    #[serde(default, skip_serializing_if = "KeymapOverrides::is_empty")]
+    pub keymap: KeymapOverrides,
+    /// how new tabs are placed, defaults to `LayoutPolicy::Spiral`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout_policy: Option<LayoutPolicy>,
+    /// From before the session had its own file, so it's only read (when there's no `session.json` yet).
+    /// See `crate::project::Project::session`
+    #[serde(default, skip_serializing)]
+    pub(crate) open_tabs: Option<OpenTabs>,
 }
@@ -0,0 +1,208 @@
+//! Versions of the `.project` files, and upgrading old files when they're loaded.
+//!
+//! Every file is a json object with a `schema_version` (a missing one is version 0, from before this existed).
+//! Old files are upgraded one version at a time with their schema's `migrations`,
+//! and the original is kept next to it as `<file>.v<version>.backup`.
+//!
+//! NOTE: changing the format of a file means adding a migration to its schema here
+
+use crate::tab::{file_system::FsError, ManagerHandler};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+const VERSION_KEY: &str = "schema_version";
+
+/// `.project/core.json`, see `super::project_settings::ProjectSettings`
+pub const CORE_SCHEMA: Schema = Schema {
+    file_name: "core.json",
+    // 1: added the version
+    migrations: &[|core| core],
+};
+
+/// `.project/session.json`, see `super::project_settings::OpenTabs`
+pub const SESSION_SCHEMA: Schema = Schema {
+    file_name: "session.json",
+    // 1: moved out of `core.json` (where it was `open_tabs`)
+    migrations: &[|session| session],
+};
+
+/// `.project/tasks.json`, the task organizer's `RecursiveTreeNode` of tasks
+pub const TASKS_SCHEMA: Schema = Schema {
+    file_name: "tasks.json",
+    // 1: added the version
+    migrations: &[|tasks| tasks],
+};
+
+/// `.project/blocks.json`, the time manager's blocks
+pub const BLOCKS_SCHEMA: Schema = Schema {
+    file_name: "blocks.json",
+    // 1: the list of blocks moved into `blocks`, since the file has to be an object
+    migrations: &[|blocks| serde_json::json!({ "blocks": blocks })],
+};
+
+pub struct Schema {
+    /// just for messages
+    pub file_name: &'static str,
+    /// `migrations[n]` upgrades version `n` to `n + 1`, so the current version is the number of migrations.
+    /// They get and return the file without its `schema_version`.
+    pub migrations: &'static [fn(Value) -> Value],
+}
+impl Schema {
+    pub fn current_version(&self) -> u32 {
+        self.migrations.len() as u32
+    }
+
+    /// Upgrades the file first if it's old (and writes it back, keeping a backup)
+    pub fn load<T: DeserializeOwned>(&self, path: &Path) -> Result<T, SchemaError> {
+        let serialized = std::fs::read_to_string(path)?;
+        let (content, version) = self.upgrade(&serialized, path)?;
+
+        if version < self.current_version() {
+            let backup_path = self.backup_path(path, version);
+            // don't replace an older backup
+            if !backup_path.exists() {
+                std::fs::write(&backup_path, &serialized)?;
+            }
+            std::fs::write(path, self.to_string(&content))?;
+            self.print_upgrade(path, version, &backup_path);
+        }
+
+        Ok(serde_json::from_value(content)?)
+    }
+
+    /// `load`, but through the manager, for tabs (see `crate::tab::file_system`)
+    pub fn load_through<T: DeserializeOwned>(
+        &self,
+        manager_handler: &ManagerHandler,
+        path: &Path,
+    ) -> Result<T, SchemaError> {
+        let serialized = manager_handler.read_file_to_string(path)?;
+        let (content, version) = self.upgrade(&serialized, path)?;
+
+        if version < self.current_version() {
+            let backup_path = self.backup_path(path, version);
+            if let Err(FsError::NotFound(_)) = manager_handler.read_file(&backup_path) {
+                manager_handler.write_file(&backup_path, serialized)?;
+            }
+            manager_handler.write_file(path, self.to_string(&content))?;
+            self.print_upgrade(path, version, &backup_path);
+        }
+
+        Ok(serde_json::from_value(content)?)
+    }
+
+    /// The content at the current version (without `schema_version`), and the version it was at
+    fn upgrade(&self, serialized: &str, path: &Path) -> Result<(Value, u32), SchemaError> {
+        let mut content: Value = serde_json::from_str(serialized)?;
+
+        let version = match &mut content {
+            Value::Object(object) => match object.remove(VERSION_KEY) {
+                Some(version) => serde_json::from_value(version)?,
+                None => 0,
+            },
+            _ => 0,
+        };
+
+        if version > self.current_version() {
+            return Err(SchemaError::TooNew {
+                path: path.to_path_buf(),
+                version,
+                supported_version: self.current_version(),
+            });
+        }
+
+        for migration in &self.migrations[version as usize..] {
+            content = migration(content);
+        }
+
+        Ok((content, version))
+    }
+
+    fn backup_path(&self, path: &Path, version: u32) -> PathBuf {
+        path.with_file_name(format!("{}.v{version}.backup", self.file_name))
+    }
+
+    fn print_upgrade(&self, path: &Path, version: u32, backup_path: &Path) {
+        println!(
+            "Upgraded `{}` from version {version} to {} (the original is in `{}`)",
+            path.display(),
+            self.current_version(),
+            backup_path.display()
+        );
+    }
+
+    /// With the current version
+    pub fn to_string<T: Serialize>(&self, content: &T) -> String {
+        #[derive(Serialize)]
+        struct Versioned<'a, T> {
+            schema_version: u32,
+            #[serde(flatten)]
+            content: &'a T,
+        }
+
+        serde_json::to_string_pretty(&Versioned {
+            schema_version: self.current_version(),
+            content,
+        })
+        .unwrap()
+    }
+
+    pub fn save<T: Serialize>(&self, path: &Path, content: &T) -> std::io::Result<()> {
+        std::fs::write(path, self.to_string(content))
+    }
+}
+
+/// The content of `blocks.json`, since it's not an object on its own
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct BlocksFile<B> {
+    pub blocks: Vec<B>,
+}
+
+#[derive(Debug)]
+pub enum SchemaError {
+    Io(std::io::Error),
+    /// from `Schema::load_through`
+    Fs(FsError),
+    Json(serde_json::Error),
+    /// from a newer build, so this one can't know what changed
+    TooNew {
+        path: PathBuf,
+        version: u32,
+        supported_version: u32,
+    },
+}
+impl std::fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaError::Io(err) => write!(f, "{err}"),
+            SchemaError::Fs(err) => write!(f, "{err}"),
+            SchemaError::Json(err) => write!(f, "badly formatted: {err}"),
+            SchemaError::TooNew {
+                path,
+                version,
+                supported_version,
+            } => write!(
+                f,
+                "`{}` is from a newer version of singularity (schema version {version}, but this build only knows up to {supported_version}). Please update singularity to open it.",
+                path.display()
+            ),
+        }
+    }
+}
+impl std::error::Error for SchemaError {}
+impl From<std::io::Error> for SchemaError {
+    fn from(err: std::io::Error) -> Self {
+        SchemaError::Io(err)
+    }
+}
+impl From<FsError> for SchemaError {
+    fn from(err: FsError) -> Self {
+        SchemaError::Fs(err)
+    }
+}
+impl From<serde_json::Error> for SchemaError {
+    fn from(err: serde_json::Error) -> Self {
+        SchemaError::Json(err)
+    }
+}
@@ -0,0 +1,45 @@
+//! Settings for all of the user's projects, in `$XDG_CONFIG_HOME/singularity/settings.json`
+//! (or `~/.config/singularity/settings.json`).
+//!
+//! The project's settings (`.project/core.json`) go on top, like VSCode's user and workspace settings
+//! (see the DEVLOG's Organization section).
+
+use super::project_settings::SubappSettings;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::PathBuf};
+
+/// Same structure as the project's subapp settings, see `ProjectSettings::subapps`
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct UserSettings {
+    #[serde(default)]
+    pub subapps: HashMap<String, SubappSettings>,
+}
+impl UserSettings {
+    pub fn path() -> Option<PathBuf> {
+        Some(user_config_directory()?.join("settings.json"))
+    }
+
+    /// A missing file is the same as no settings, and a broken one is ignored (with a message)
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        let Ok(user_settings) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        serde_json::from_str(&user_settings).unwrap_or_else(|err| {
+            println!("Ignoring `{}`: {err}", path.display());
+            Self::default()
+        })
+    }
+}
+
+/// `$XDG_CONFIG_HOME/singularity` (or `~/.config/singularity`), for everything that is per user
+pub fn user_config_directory() -> Option<PathBuf> {
+    let config_directory = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config_directory.join("singularity"))
+}
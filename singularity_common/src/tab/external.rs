@@ -0,0 +1,452 @@
+//! Tabs that run as their own process, talking to the manager over the process's stdin/stdout.
+//!
+//! The manager side is `ExternalTab`, a `TabCreator` that spawns the executable.
+//! The process side is `run_tab_over_stdio`, which runs any `TabCreator` as if it were in the manager.
+//!
+//! Both sides use `ObjectInputStream`/`ObjectOutputStream`: the manager writes `ToTab`s and the process writes `ToManager`s.
+//! So, tabs don't have to be written in rust, they just need to send the same json.
+//!
+//! The process starts by writing a `Handshake` with the encoding it wants,
+//! and the manager answers with its own handshake, then writes in that encoding too.
+//! (`run_tab_over_stdio` asks for MessagePack, other languages can stick to JSON)
+//!
+//! NOTE: stdout belongs to the protocol, so external tabs should print to stderr (`eprintln!`/`dbg!`)
+
+use super::{
+    create_channels,
+    file_system::{FsRequest, FsResult},
+    packets::{CloseResponse, Event, QueryId, QueryResponse, QueryTypes, Request},
+    ManagerHandler, TabChannels, TabCreator,
+};
+use crate::{
+    project::project_settings::TabData,
+    utils::object_stream::{Encoding, ObjectInputStream, ObjectOutputStream, ObjectStreamError},
+};
+use serde::{Deserialize, Serialize};
+use singularity_ui::{
+    color::Color,
+    display_units::DisplayArea,
+    ui_element::{CharGrid, UIElement},
+    wakeup::Wakeup,
+};
+use std::{
+    io::{self, BufReader},
+    path::PathBuf,
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long a process gets to exit by itself after `Event::Close`, before it is killed
+const EXIT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Manager to tab
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ToTab {
+    Event(Event),
+    /// The answer to the `ToManager::Query` with the same id
+    QueryResponse(QueryId, QueryResponse),
+    /// The answer to the last `ExternalRequest::FileSystem`
+    FsResponse(FsResult),
+}
+
+/// Tab to manager
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ToManager {
+    /// replaces what the tab displays
+    UIElement(UIElement),
+    Request(ExternalRequest),
+    /// Answered with a `ToTab::QueryResponse` with the same id (the process picks the ids).
+    /// Several can be waiting at once.
+    Query(QueryId, QueryTypes),
+}
+
+/// The `Request`s that can be sent between processes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ExternalRequest {
+    ChangeName(String),
+    /// The manager finds the tab creator from `TabData.tab_type`
+    SpawnChildTab(TabData),
+    UpdateSessionData(serde_json::Value),
+    SessionDataUpToDate,
+    CloseResponse(CloseResponse),
+    /// Must be answered (with `ToTab::FsResponse`) before the next one is sent
+    FileSystem(FsRequest),
+}
+impl From<ExternalRequest> for Request {
+    fn from(external_request: ExternalRequest) -> Self {
+        match external_request {
+            ExternalRequest::ChangeName(new_name) => Request::ChangeName(new_name),
+            ExternalRequest::SpawnChildTab(tab_data) => Request::SpawnRegisteredChildTab(tab_data),
+            ExternalRequest::UpdateSessionData(session_data) => {
+                Request::UpdateSessionData(session_data)
+            }
+            ExternalRequest::SessionDataUpToDate => Request::SessionDataUpToDate,
+            ExternalRequest::CloseResponse(close_response) => {
+                Request::CloseResponse(close_response)
+            }
+            ExternalRequest::FileSystem(fs_request) => Request::FileSystem(fs_request),
+        }
+    }
+}
+impl From<Request> for ExternalRequest {
+    fn from(request: Request) -> Self {
+        match request {
+            Request::ChangeName(new_name) => ExternalRequest::ChangeName(new_name),
+            // the tab creator can't leave the process, so the manager has to find its own
+            Request::SpawnChildTab(_, tab_data) | Request::SpawnRegisteredChildTab(tab_data) => {
+                ExternalRequest::SpawnChildTab(tab_data)
+            }
+            Request::UpdateSessionData(session_data) => {
+                ExternalRequest::UpdateSessionData(session_data)
+            }
+            Request::SessionDataUpToDate => ExternalRequest::SessionDataUpToDate,
+            Request::CloseResponse(close_response) => {
+                ExternalRequest::CloseResponse(close_response)
+            }
+            Request::FileSystem(fs_request) => ExternalRequest::FileSystem(fs_request),
+        }
+    }
+}
+
+/// Runs the tab as a separate process (manager side).
+///
+/// If the process exits, crashes or breaks the protocol, the tab shows that it did, and the manager doesn't notice.
+///
+/// NOTE: the process is only stopped on `Event::Close`, otherwise it runs until the manager exits
+/// (which closes its stdin)
+pub struct ExternalTab {
+    program: PathBuf,
+    args: Vec<String>,
+}
+impl ExternalTab {
+    pub fn new(program: impl Into<PathBuf>, args: Vec<String>) -> Self {
+        Self {
+            program: program.into(),
+            args,
+        }
+    }
+
+    fn message_element(message: String) -> UIElement {
+        UIElement::CharGrid(CharGrid::from(message))
+            .fill_bg(Color::DARK_GRAY)
+            .bordered(Color::RED)
+    }
+}
+impl TabCreator for ExternalTab {
+    fn create_tab(&mut self, mut manager_handler: ManagerHandler) {
+        let mut child = match Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) => {
+                manager_handler.update_ui_element(Self::message_element(format!(
+                    "Failed to start `{}`:\n{err}",
+                    self.program.display()
+                )));
+
+                // nothing else to do, and nothing to lose by closing
+                loop {
+                    manager_handler.wait_for_events(None);
+
+                    for event in manager_handler.collect_events() {
+                        match event {
+                            Event::RequestClose => manager_handler
+                                .send_request(Request::CloseResponse(CloseResponse::Accept)),
+                            Event::Close => return,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        };
+
+        // writing is on its own thread, so a process that doesn't read can't block the tab
+        // NOTE: it only starts once the reader knows which encoding the process wants
+        let (encoding_tx, encoding_rx) = mpsc::channel::<Encoding>();
+        let (to_tab_tx, to_tab_rx) = mpsc::channel::<ToTab>();
+        let mut child_stdin = child.stdin.take().unwrap();
+        let writer_thread = thread::spawn(move || {
+            let Ok(encoding) = encoding_rx.recv() else {
+                return;
+            };
+            if child_stdin.write_handshake(encoding).is_err() {
+                return;
+            }
+
+            for message in to_tab_rx {
+                // the process is gone (the reader finds out why)
+                if child_stdin
+                    .write_encoded_object(&message, encoding)
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        // reading blocks, so it also needs its own thread, which wakes the tab up
+        let (to_manager_tx, to_manager_rx) =
+            mpsc::channel::<Result<ToManager, ObjectStreamError>>();
+        let mut child_stdout = BufReader::new(child.stdout.take().unwrap());
+        let tab_wakeup = manager_handler.get_tab_wakeup();
+        thread::spawn(move || {
+            let result = child_stdout.read_handshake().and_then(|handshake| {
+                let _ = encoding_tx.send(handshake.encoding);
+
+                loop {
+                    let message = child_stdout.read_encoded_object(handshake.encoding)?;
+                    if to_manager_tx.send(Ok(message)).is_err() {
+                        return Ok(());
+                    }
+                    tab_wakeup.notify();
+                }
+            });
+
+            // the process closing its stdout (usually by exiting) is the normal way to stop
+            if let Err(err) = result {
+                if !matches!(err, ObjectStreamError::Disconnected) {
+                    let _ = to_manager_tx.send(Err(err));
+                }
+            }
+
+            drop(to_manager_tx);
+            tab_wakeup.notify();
+        });
+
+        // the process doesn't know where it is yet
+        // NOTE: if the process is gone, no one needs the messages, so send errors are ignored
+        let _ = to_tab_tx.send(ToTab::Event(Event::Resize(manager_handler.inner_area)));
+        if manager_handler.focus {
+            let _ = to_tab_tx.send(ToTab::Event(Event::Focused));
+        }
+
+        let mut has_exited = false;
+        // (this tab's id, the process's id)
+        let mut pending_queries: Vec<(QueryId, QueryId)> = Vec::new();
+        'mainloop: loop {
+            manager_handler.wait_for_events(None);
+
+            // the answers wake the tab up too
+            pending_queries.retain(|(query_id, process_query_id)| {
+                match manager_handler
+                    .get_query_channels()
+                    .try_take_response(*query_id)
+                {
+                    Some(response) => {
+                        let _ = to_tab_tx.send(ToTab::QueryResponse(*process_query_id, response));
+                        false
+                    }
+                    None => true,
+                }
+            });
+
+            for event in manager_handler.collect_events() {
+                let is_close = matches!(event, Event::Close);
+
+                if has_exited && matches!(event, Event::RequestClose) {
+                    // no process left to ask
+                    manager_handler.send_request(Request::CloseResponse(CloseResponse::Accept));
+                    continue;
+                }
+
+                let _ = to_tab_tx.send(ToTab::Event(event));
+
+                if is_close {
+                    break 'mainloop;
+                }
+            }
+
+            // everything the process has sent so far
+            loop {
+                match to_manager_rx.try_recv() {
+                    Ok(Ok(ToManager::UIElement(ui_element))) => {
+                        manager_handler.update_ui_element(ui_element);
+                    }
+                    Ok(Ok(ToManager::Request(ExternalRequest::FileSystem(fs_request)))) => {
+                        // like a query, the process waits for the answer
+                        let fs_result = manager_handler.request_fs(fs_request);
+                        let _ = to_tab_tx.send(ToTab::FsResponse(fs_result));
+                    }
+                    Ok(Ok(ToManager::Request(external_request))) => {
+                        manager_handler.send_request(external_request.into());
+                    }
+                    Ok(Ok(ToManager::Query(process_query_id, query))) => {
+                        // answered below, once the manager gets to it
+                        let query_id = manager_handler.get_query_channels().send_query(query);
+                        pending_queries.push((query_id, process_query_id));
+                    }
+                    Ok(Err(err)) => {
+                        // nothing it sends after this can be trusted
+                        has_exited = true;
+                        let _ = child.kill();
+                        let _ = child.wait();
+
+                        manager_handler.update_ui_element(Self::message_element(format!(
+                            "`{}` broke the protocol:\n{err}",
+                            self.program.display()
+                        )));
+                    }
+                    Err(mpsc::TryRecvError::Empty) => break,
+                    Err(mpsc::TryRecvError::Disconnected) => {
+                        if !has_exited {
+                            has_exited = true;
+
+                            let message = match wait_with_timeout(&mut child, EXIT_TIMEOUT) {
+                                Some(exit_status) => {
+                                    format!("`{}` exited ({exit_status})", self.program.display())
+                                }
+                                None => format!(
+                                    "`{}` closed its stdout, but is still running",
+                                    self.program.display()
+                                ),
+                            };
+                            manager_handler.update_ui_element(Self::message_element(message));
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+
+        // the writer finishes sending `Event::Close` while the process gets some time to exit by itself
+        drop(to_tab_tx);
+        if wait_with_timeout(&mut child, EXIT_TIMEOUT).is_none() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        // NOTE: only joined once the process is gone, since a process that never handshakes
+        // keeps the writer waiting for the encoding (the reader drops it when stdout closes)
+        let _ = writer_thread.join();
+    }
+}
+
+/// None if the child is still running after the timeout
+fn wait_with_timeout(child: &mut Child, timeout: Duration) -> Option<ExitStatus> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(exit_status)) => return Some(exit_status),
+            Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+            _ => return None,
+        }
+    }
+}
+
+/// Runs the tab in this process, for an `ExternalTab` in the manager's process (tab side).
+///
+/// Returns when the tab is closed, or when the manager goes away.
+pub fn run_tab_over_stdio<F: 'static + TabCreator>(mut tab_creator: F) {
+    // MessagePack, since ui elements get big (and both sides are rust)
+    let encoding = Encoding::MessagePack;
+    let manager_encoding = match io::stdout()
+        .write_handshake(encoding)
+        .and_then(|()| io::stdin().lock().read_handshake())
+    {
+        Ok(handshake) => handshake.encoding,
+        Err(err) => {
+            eprintln!("Failed to connect to the manager: {err}");
+            return;
+        }
+    };
+
+    // the tab wakes this up like it would wake up the manager
+    let wakeup = Wakeup::new();
+    let (tab_channels, manager_channels) = create_channels(wakeup.clone());
+
+    let tab_thread = thread::spawn(move || {
+        tab_creator.create_tab(ManagerHandler {
+            manager_channels,
+            // the manager sends a resize right away
+            inner_area: DisplayArea::FULL,
+            focus: false,
+        })
+    });
+
+    let TabChannels {
+        event_tx,
+        request_rx,
+        respond_channels,
+        fs_response_tx,
+        ui_element,
+        tab_wakeup,
+    } = tab_channels;
+    // NOTE: queries are read by one thread and answered by the other
+    let respond_channels = Arc::new(Mutex::new(respond_channels));
+
+    // tab to manager
+    {
+        let respond_channels = respond_channels.clone();
+        thread::spawn(move || -> Result<(), ObjectStreamError> {
+            let mut last_ui_element = None;
+            loop {
+                wakeup.wait();
+
+                // NOTE: stdout is only locked per message, so that a stray `println!` in the tab doesn't deadlock
+                // (if the manager is gone, the stdin thread notices too, so this just stops)
+                for request in request_rx.try_iter() {
+                    io::stdout()
+                        .write_encoded_object(&ToManager::Request(request.into()), encoding)?;
+                }
+
+                let queries = respond_channels.lock().unwrap().take_queries();
+                for (query_id, query) in queries {
+                    io::stdout()
+                        .write_encoded_object(&ToManager::Query(query_id, query), encoding)?;
+                }
+
+                let current_ui_element = ui_element.lock().unwrap().clone();
+                if last_ui_element.as_ref() != Some(&current_ui_element) {
+                    io::stdout().write_encoded_object(
+                        &ToManager::UIElement(current_ui_element.clone()),
+                        encoding,
+                    )?;
+                    last_ui_element = Some(current_ui_element);
+                }
+            }
+        });
+    }
+
+    // manager to tab
+    let stdin_thread = thread::spawn(move || {
+        let mut stdin = io::stdin().lock();
+
+        loop {
+            match stdin.read_encoded_object::<ToTab>(manager_encoding) {
+                Ok(ToTab::Event(event)) => {
+                    let is_close = matches!(event, Event::Close);
+
+                    // if the tab already stopped, it doesn't care
+                    let _ = event_tx.send(event);
+                    tab_wakeup.notify();
+
+                    if is_close {
+                        return true;
+                    }
+                }
+                Ok(ToTab::QueryResponse(query_id, response)) => {
+                    respond_channels.lock().unwrap().respond(query_id, response);
+                }
+                Ok(ToTab::FsResponse(fs_result)) => {
+                    // if the tab already stopped, it doesn't care
+                    let _ = fs_response_tx.send(fs_result);
+                }
+                // the manager closed stdin (or exited)
+                Err(ObjectStreamError::Disconnected) => return false,
+                Err(err) => {
+                    eprintln!("Lost the connection to the manager: {err}");
+                    return false;
+                }
+            }
+        }
+    });
+
+    if let Ok(true) = stdin_thread.join() {
+        // closed normally, so let the tab finish up (eg: saving)
+        let _ = tab_thread.join();
+    }
+}
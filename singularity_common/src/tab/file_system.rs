@@ -0,0 +1,205 @@
+//! Files go through the manager, so that it can check them against the tab's `SubappFileSystemPermissions`
+//! (and ask the user about the rest).
+//!
+//! Tabs use `ManagerHandler::read_file` and co, which block until the manager answers with a `FsResult`.
+//!
+//! NOTE: watching is polling for now, there's no file watching crate yet
+
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::{mpsc::Sender, Weak},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use super::packets::Event;
+use singularity_ui::wakeup::Wakeup;
+
+/// How often watched paths are checked
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FsRequest {
+    Read(PathBuf),
+    Write(PathBuf, Vec<u8>),
+    ListDir(PathBuf),
+    /// Changes come as `Event::FileChanged`, until the tab closes
+    Watch(PathBuf),
+}
+impl FsRequest {
+    pub fn get_path(&self) -> &Path {
+        match self {
+            FsRequest::Read(path)
+            | FsRequest::Write(path, _)
+            | FsRequest::ListDir(path)
+            | FsRequest::Watch(path) => path,
+        }
+    }
+
+    /// What the tab needs permission for
+    pub fn get_access(&self) -> FileSystemAccess {
+        match self {
+            FsRequest::Read(_) | FsRequest::ListDir(_) | FsRequest::Watch(_) => {
+                FileSystemAccess::Read
+            }
+            FsRequest::Write(..) => FileSystemAccess::Write,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FsResponse {
+    Read(Vec<u8>),
+    Written,
+    DirEntries(Vec<DirEntry>),
+    Watching,
+}
+
+pub type FsResult = Result<FsResponse, FsError>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirEntry {
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FileSystemAccess {
+    Read,
+    Write,
+    Execute,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FsError {
+    /// not in the tab's permissions, and the user didn't allow it either
+    PermissionDenied(PathBuf),
+    NotFound(PathBuf),
+    /// the manager stopped answering for good (the tab is being closed)
+    ManagerGone,
+    Other(String),
+}
+impl FsError {
+    fn from_io(err: std::io::Error, path: &Path) -> Self {
+        match err.kind() {
+            std::io::ErrorKind::NotFound => FsError::NotFound(path.to_path_buf()),
+            _ => FsError::Other(format!("`{}`: {err}", path.display())),
+        }
+    }
+}
+impl std::fmt::Display for FsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FsError::PermissionDenied(path) => {
+                write!(f, "no permission for `{}`", path.display())
+            }
+            FsError::NotFound(path) => write!(f, "`{}` doesn't exist", path.display()),
+            FsError::ManagerGone => write!(f, "the manager is gone"),
+            FsError::Other(message) => write!(f, "{message}"),
+        }
+    }
+}
+impl std::error::Error for FsError {}
+
+/// Does the request for real, without checking anything (that's the manager's job).
+///
+/// NOTE: `FsRequest::Watch` needs the tab's channels, see `watch`
+pub fn perform(request: &FsRequest) -> FsResult {
+    let path = request.get_path();
+    match request {
+        FsRequest::Read(_) => std::fs::read(path).map(FsResponse::Read),
+        FsRequest::Write(_, contents) => {
+            std::fs::write(path, contents).map(|()| FsResponse::Written)
+        }
+        FsRequest::ListDir(_) => std::fs::read_dir(path).and_then(|read_dir| {
+            let mut entries = read_dir
+                .map(|entry| {
+                    let entry = entry?;
+                    Ok(DirEntry {
+                        is_dir: entry.file_type()?.is_dir(),
+                        path: entry.path(),
+                    })
+                })
+                .collect::<std::io::Result<Vec<_>>>()?;
+            // read_dir's order depends on the platform
+            entries.sort_by(|a, b| a.path.cmp(&b.path));
+            Ok(FsResponse::DirEntries(entries))
+        }),
+        FsRequest::Watch(_) => std::fs::metadata(path).map(|_| FsResponse::Watching),
+    }
+    .map_err(|err| FsError::from_io(err, path))
+}
+
+/// Sends `Event::FileChanged` to the tab whenever `path`'s modified time changes (or it appears or disappears).
+///
+/// Stops once `tab_handle` can't be upgraded anymore (the `TabHandler` was dropped, eg: closed or restarted).
+pub fn watch(path: PathBuf, event_tx: Sender<Event>, tab_wakeup: Wakeup, tab_handle: Weak<()>) {
+    let modified =
+        |path: &Path| -> Option<SystemTime> { std::fs::metadata(path).ok()?.modified().ok() };
+
+    thread::spawn(move || {
+        let mut last_modified = modified(&path);
+        loop {
+            thread::sleep(WATCH_INTERVAL);
+            if tab_handle.strong_count() == 0 {
+                return;
+            }
+
+            let current_modified = modified(&path);
+            if current_modified != last_modified {
+                last_modified = current_modified;
+                if event_tx.send(Event::FileChanged(path.clone())).is_err() {
+                    return;
+                }
+                tab_wakeup.notify();
+            }
+        }
+    });
+}
+
+/// Absolute (from the current directory), with symlinks resolved and without `.` and `..`,
+/// so that paths can be compared (eg: a symlink in the project can't lead out of it).
+///
+/// NOTE: the path might not exist yet (eg: writing a new file), so only its nearest existing ancestor is canonicalized,
+/// and the rest is added on as is
+pub fn resolve_path(path: &Path) -> PathBuf {
+    let path = if path.is_relative() {
+        std::env::current_dir()
+            .map(|current_dir| current_dir.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    } else {
+        path.to_path_buf()
+    };
+
+    // the components that don't exist, last first
+    let mut missing_components = Vec::new();
+    let mut existing_ancestor = path.as_path();
+    let mut resolved = loop {
+        if let Ok(canonical_ancestor) = existing_ancestor.canonicalize() {
+            break canonical_ancestor;
+        }
+        match (
+            existing_ancestor.components().next_back(),
+            existing_ancestor.parent(),
+        ) {
+            (Some(component), Some(parent)) => {
+                missing_components.push(component);
+                existing_ancestor = parent;
+            }
+            // (not even the root exists)
+            _ => break PathBuf::new(),
+        }
+    };
+
+    for component in missing_components.into_iter().rev() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            component => resolved.push(component),
+        }
+    }
+    resolved
+}
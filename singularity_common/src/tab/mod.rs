@@ -1,15 +1,33 @@
-use crate::project::project_settings::TabData;
-use packets::{create_query_channels, Event, QueryChannels, Request, RespondChannels};
-use singularity_ui::{display_units::DisplayArea, ui_element::UIElement};
+use crate::{keymap::Keymap, project::project_settings::TabData};
+use file_system::{DirEntry, FsError, FsRequest, FsResponse, FsResult};
+use packets::{
+    create_query_channels, CloseResponse, Event, QueryChannels, QueryError, Request,
+    RespondChannels,
+};
+use singularity_ui::{
+    color::Color,
+    display_units::DisplayArea,
+    ui_element::{CharGrid, UIElement},
+    ui_event::UIEvent,
+    wakeup::Wakeup,
+};
 use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
     sync::{
         mpsc::{self, Receiver, Sender},
-        Arc, Mutex,
+        Arc, Mutex, PoisonError,
     },
     thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
+pub mod external;
+pub mod file_system;
+pub mod overlay;
 pub mod packets;
+pub mod registry;
 pub mod tile;
 
 /// REVIEW: name this tab runner?
@@ -31,12 +49,40 @@ impl TabCreator for Box<dyn TabCreator> {
     }
 }
 
+/// Why a `BasicTab` couldn't start (eg: its file is gone), shown in place of the tab
+pub type InitError = Box<dyn std::error::Error + Send + Sync>;
+
 /// REVIEW: the parallel between this and `Component` is undeniable, maybe look into some higher level of abstraction in this
 pub trait BasicTab: Send + Sized {
-    fn initialize_tab(manager_handler: &ManagerHandler) -> Self;
+    /// The queries are `?`-able, since they only fail when the manager is gone
+    fn initialize_tab(manager_handler: &ManagerHandler) -> Result<Self, InitError>;
     fn render_tab(&mut self, manager_handler: &ManagerHandler) -> Option<UIElement>;
     fn handle_tab_event(&mut self, event: Event, manager_handler: &ManagerHandler);
 
+    /// The tab only rerenders when it gets an event,
+    /// so tabs that change by themselves (like timers) should return how often they need to be rerendered.
+    fn rerender_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// What to save as `TabData.session_data`, so that the tab can be reopened where it left off.
+    ///
+    /// None keeps whatever the tab was opened with.
+    /// It is sent whenever it changes, and once more when the manager saves the session.
+    fn session_data(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// If true when the tab is about to be closed, the user is asked whether to `save` first (or to not close).
+    ///
+    /// NOTE: mut, since some tabs need to collect their changes before they can tell
+    fn has_unsaved_changes(&mut self) -> bool {
+        false
+    }
+
+    /// Saves the unsaved changes, when the user chooses to while closing
+    fn save(&mut self, _manager_handler: &ManagerHandler) {}
+
     fn new_tab_creator() -> impl TabCreator {
         struct Inner(Box<dyn FnMut(ManagerHandler) + Send>);
         impl TabCreator for Inner {
@@ -45,19 +91,134 @@ pub trait BasicTab: Send + Sized {
             }
         }
 
+        /// Only sends the session data if it changed since `last_session_data`
+        fn update_session_data<T: BasicTab>(
+            tab: &T,
+            last_session_data: &mut Option<serde_json::Value>,
+            manager_handler: &ManagerHandler,
+        ) {
+            let session_data = tab.session_data();
+            if session_data.is_some() && session_data != *last_session_data {
+                manager_handler
+                    .send_request(Request::UpdateSessionData(session_data.clone().unwrap()));
+                *last_session_data = session_data;
+            }
+        }
+
+        /// "save unsaved changes?", drawn on top of the tab
+        fn render_close_prompt(keymap: &Keymap) -> UIElement {
+            UIElement::CharGrid(CharGrid::from(format!(
+                "Save unsaved changes?\n{}: save\n{}: don't save\n{}: cancel",
+                keymap.describe_shortcuts("close_prompt.save"),
+                keymap.describe_shortcuts("close_prompt.discard"),
+                keymap.describe_shortcuts("close_prompt.cancel"),
+            )))
+            .fill_bg(Color::DARK_GRAY)
+            .bordered(Color::LIGHT_YELLOW)
+            .contain(DisplayArea::new((0.1, 0.3), (0.9, 0.7)))
+        }
+
         Inner(Box::new(move |mut manager_handler: ManagerHandler| {
-            let mut tab = Self::initialize_tab(&manager_handler);
+            let mut tab = match Self::initialize_tab(&manager_handler) {
+                Ok(tab) => tab,
+                Err(err) => {
+                    if err.downcast_ref::<QueryError>() == Some(&QueryError::ManagerGone) {
+                        // no one to show it to
+                        return;
+                    }
+
+                    manager_handler.update_ui_element(
+                        UIElement::CharGrid(CharGrid::from(format!("Failed to start:\n{err}")))
+                            .fill_bg(Color::DARK_GRAY)
+                            .bordered(Color::RED),
+                    );
+
+                    // nothing to save, and nothing to lose by closing
+                    loop {
+                        manager_handler.wait_for_events(None);
+
+                        for event in manager_handler.collect_events() {
+                            match event {
+                                Event::RequestClose => manager_handler
+                                    .send_request(Request::CloseResponse(CloseResponse::Accept)),
+                                Event::Close => return,
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+            };
+            let mut last_session_data = None;
+            // Some while asking the user to save before closing
+            let mut close_prompt: Option<Keymap> = None;
 
             'mainloop: loop {
-                if let Some(new_display_buffer) = tab.render_tab(&manager_handler) {
+                let new_display_buffer = tab.render_tab(&manager_handler);
+                if let Some(keymap) = &close_prompt {
+                    // NOTE: tabs usually render every time, so None shouldn't hide much
+                    manager_handler.update_ui_element(UIElement::Container(
+                        new_display_buffer
+                            .into_iter()
+                            .chain([render_close_prompt(keymap)])
+                            .collect(),
+                    ));
+                } else if let Some(new_display_buffer) = new_display_buffer {
                     manager_handler.update_ui_element(new_display_buffer);
                 };
 
+                // sleep until there are events (or it is time to rerender)
+                manager_handler.wait_for_events(tab.rerender_interval());
+
                 for event in manager_handler.collect_events() {
                     match event {
                         Event::Close => {
                             break 'mainloop;
                         }
+                        Event::SaveSession => {
+                            update_session_data(&tab, &mut last_session_data, &manager_handler);
+                            manager_handler.send_request(Request::SessionDataUpToDate);
+                        }
+                        Event::RequestClose => {
+                            if tab.has_unsaved_changes() {
+                                // the manager might be too busy quitting, but the default keys are fine
+                                close_prompt = Some(
+                                    manager_handler
+                                        .get_query_channels()
+                                        .ask_keymap_timeout(Duration::from_secs(1))
+                                        .unwrap_or_default(),
+                                );
+
+                                manager_handler
+                                    .send_request(Request::CloseResponse(CloseResponse::Prompting));
+                            } else {
+                                manager_handler
+                                    .send_request(Request::CloseResponse(CloseResponse::Accept));
+                            }
+                        }
+                        Event::CloseCancelled => {
+                            close_prompt = None;
+                        }
+                        Event::UIEvent(UIEvent::KeyPress(key, key_modifiers))
+                            if close_prompt.is_some() =>
+                        {
+                            let keymap = close_prompt.as_ref().unwrap();
+                            let close_response =
+                                match keymap.get_action(&["close_prompt"], &key, key_modifiers) {
+                                    Some("close_prompt.save") => {
+                                        tab.save(&manager_handler);
+                                        CloseResponse::Accept
+                                    }
+                                    Some("close_prompt.discard") => CloseResponse::Accept,
+                                    Some("close_prompt.cancel") => CloseResponse::Veto,
+                                    _ => continue,
+                                };
+
+                            close_prompt = None;
+                            manager_handler.send_request(Request::CloseResponse(close_response));
+                        }
+                        Event::UIEvent(_) if close_prompt.is_some() => {
+                            // the prompt is in the way
+                        }
                         Event::Resize(inner_area) => {
                             manager_handler.inner_area = inner_area;
                             tab.handle_tab_event(event, &manager_handler);
@@ -70,11 +231,14 @@ pub trait BasicTab: Send + Sized {
                             manager_handler.focus = false;
                             tab.handle_tab_event(event, &manager_handler);
                         }
-                        Event::UIEvent(_) => {
+                        Event::UIEvent(_) | Event::FileChanged(_) => {
                             tab.handle_tab_event(event, &manager_handler);
                         }
                     }
                 }
+
+                // kept up to date, so restarting a crashed tab doesn't lose much either
+                update_session_data(&tab, &mut last_session_data, &manager_handler);
             }
         }))
     }
@@ -86,8 +250,13 @@ pub struct TabChannels {
     pub event_tx: Sender<Event>,
     pub request_rx: Receiver<Request>,
     pub respond_channels: RespondChannels,
+    /// the answers to `Request::FileSystem`
+    pub fs_response_tx: Sender<FsResult>,
 
     pub ui_element: Arc<Mutex<UIElement>>,
+
+    /// wakes up the tab after it is sent an event
+    pub tab_wakeup: Wakeup,
 }
 
 /// Represents communication with manager on tab side
@@ -95,14 +264,23 @@ struct ManagerChannels {
     event_rx: Receiver<Event>,
     request_tx: Sender<Request>,
     query_channels: QueryChannels,
+    fs_response_rx: Receiver<FsResult>,
 
     ui_element: Arc<Mutex<UIElement>>,
+
+    tab_wakeup: Wakeup,
+    /// wakes up the manager after a request or a new ui element.
+    /// Shared by all the tabs (and the display).
+    manager_wakeup: Wakeup,
 }
 
-fn create_channels() -> (TabChannels, ManagerChannels) {
+fn create_channels(manager_wakeup: Wakeup) -> (TabChannels, ManagerChannels) {
     let (event_tx, event_rx) = mpsc::channel();
     let (request_tx, request_rx) = mpsc::channel();
-    let (query_channels, respond_channels) = create_query_channels();
+    let tab_wakeup = Wakeup::new();
+    let (query_channels, respond_channels) =
+        create_query_channels(manager_wakeup.clone(), tab_wakeup.clone());
+    let (fs_response_tx, fs_response_rx) = mpsc::channel();
     let display_buffer: Arc<Mutex<UIElement>> =
         Arc::new(Mutex::new(UIElement::Container(Vec::new())));
 
@@ -111,13 +289,18 @@ fn create_channels() -> (TabChannels, ManagerChannels) {
             event_tx,
             request_rx,
             respond_channels,
+            fs_response_tx,
             ui_element: display_buffer.clone(),
+            tab_wakeup: tab_wakeup.clone(),
         },
         ManagerChannels {
             event_rx,
             request_tx,
             query_channels,
+            fs_response_rx,
             ui_element: display_buffer,
+            tab_wakeup,
+            manager_wakeup,
         },
     )
 }
@@ -132,43 +315,119 @@ pub struct TabHandler {
     tab_area: DisplayArea,
     tab_data: TabData,
 
-    /// REVIEW: idk if this will ever be used
-    /// I realized I can't kill threads anyways
-    _tab_thread: JoinHandle<()>,
+    /// Some(panic message) once the tab thread panicked
+    crash_message: Arc<Mutex<Option<String>>>,
+    /// kept for restarting the tab
+    manager_wakeup: Wakeup,
+
+    /// the file watchers stop once this is dropped (see `file_system::watch`)
+    watch_handle: Arc<()>,
+
+    /// joined when the tab is closed (see `join`)
+    /// NOTE: I realized I can't kill threads anyways, so hung tabs are left behind
+    tab_thread: JoinHandle<()>,
 }
 impl TabHandler {
     /// TODO: allow setting focus
+    ///
+    /// `manager_wakeup` is what the manager sleeps on, the tab notifies it when the manager has something to do
     pub fn new<F: 'static + TabCreator>(
         mut tab_creator: F,
         initial_tab_data: TabData,
         tab_area: DisplayArea,
+        manager_wakeup: Wakeup,
     ) -> Self {
-        let (tab_channels, manager_channels) = create_channels();
+        let (tab_channels, manager_channels) = create_channels(manager_wakeup.clone());
+        let crash_message = Arc::new(Mutex::new(None));
 
         // create tab thread with manager proxy
-        let tab_thread = thread::spawn(move || {
-            tab_creator.create_tab(ManagerHandler {
-                manager_channels,
-                inner_area: tab_area,
-                // TODO
-                focus: false,
+        let tab_thread = {
+            let crash_message = crash_message.clone();
+            let manager_wakeup = manager_wakeup.clone();
+
+            thread::spawn(move || {
+                // a panicking tab shouldn't take the manager down with it,
+                // so the manager is just told to show the crash instead
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    tab_creator.create_tab(ManagerHandler {
+                        manager_channels,
+                        inner_area: tab_area,
+                        // TODO
+                        focus: false,
+                    })
+                }));
+
+                if let Err(panic_payload) = result {
+                    *crash_message.lock().unwrap_or_else(PoisonError::into_inner) =
+                        Some(panic_message(panic_payload.as_ref()));
+                    manager_wakeup.notify();
+                }
             })
-        });
+        };
 
         Self {
             tab_channels,
-            _tab_thread: tab_thread,
+            tab_thread,
             tab_name: String::new(),
             tab_area,
             tab_data: initial_tab_data,
+            crash_message,
+            manager_wakeup,
+            watch_handle: Arc::new(()),
+        }
+    }
+
+    /// Stops the old tab (if it is still running) and starts `tab_creator` from the same `TabData` and area.
+    ///
+    /// The name is kept until the new tab changes it.
+    pub fn restart<F: 'static + TabCreator>(&mut self, tab_creator: F) {
+        self.send_event(Event::Close);
+
+        let tab_name = std::mem::take(&mut self.tab_name);
+        *self = Self::new(
+            tab_creator,
+            self.tab_data.clone(),
+            self.tab_area,
+            self.manager_wakeup.clone(),
+        );
+        self.tab_name = tab_name;
+    }
+
+    /// The panic message if the tab crashed
+    pub fn get_crash_message(&self) -> Option<String> {
+        self.crash_message
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    pub fn is_crashed(&self) -> bool {
+        self.get_crash_message().is_some()
+    }
+
+    /// Waits (until the deadline) for the tab thread to finish, which it should after `Event::Close`.
+    ///
+    /// Returns false if it is still running, in which case it is just left behind (threads can't be killed).
+    pub fn join(self, deadline: Instant) -> bool {
+        // the watchers stop either way
+        drop(self.watch_handle);
+
+        while !self.tab_thread.is_finished() {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(10));
         }
+
+        // NOTE: panics were already caught in the thread
+        let _ = self.tab_thread.join();
+        true
     }
 
     pub fn send_event(&self, event: Event) {
-        self.tab_channels
-            .event_tx
-            .send(event)
-            .expect("Failed to send event to tab");
+        // NOTE: if the tab thread is gone (crashed or finished), no one is listening anyways
+        let _ = self.tab_channels.event_tx.send(event);
+        self.tab_channels.tab_wakeup.notify();
     }
 
     pub fn collect_requests(&self) -> Vec<Request> {
@@ -180,8 +439,29 @@ impl TabHandler {
         &self.tab_channels.respond_channels
     }
 
+    /// The answer to the tab's `Request::FileSystem`
+    pub fn respond_fs(&self, fs_result: FsResult) {
+        // NOTE: same as `send_event`, the tab might be gone
+        let _ = self.tab_channels.fs_response_tx.send(fs_result);
+    }
+
+    /// For `FsRequest::Watch`, once it's allowed
+    pub fn watch_path(&self, path: PathBuf) {
+        file_system::watch(
+            path,
+            self.tab_channels.event_tx.clone(),
+            self.tab_channels.tab_wakeup.clone(),
+            Arc::downgrade(&self.watch_handle),
+        );
+    }
+
     pub fn get_ui_element(&self) -> UIElement {
-        self.tab_channels.ui_element.lock().unwrap().clone()
+        // (poisoned if the tab panicked mid update, but the last element is still fine to show)
+        self.tab_channels
+            .ui_element
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
     }
 
     pub fn get_area(&self) -> DisplayArea {
@@ -202,6 +482,29 @@ impl TabHandler {
     pub fn get_tab_data(&self) -> &TabData {
         &self.tab_data
     }
+
+    /// See `Request::UpdateSessionData`
+    pub fn set_session_data(&mut self, session_data: serde_json::Value) {
+        self.tab_data.session_data = session_data;
+    }
+}
+
+/// `panic!` payloads are almost always a `&str` or a `String`
+fn panic_message(panic_payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = panic_payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic_payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// The manager answered with the wrong kind of response (eg: a broken external manager)
+fn unexpected_fs_response(fs_response: FsResponse) -> FsError {
+    FsError::Other(format!(
+        "unexpected response from the manager: {fs_response:?}"
+    ))
 }
 
 /// Represents manager on tab side, is a wrapper for ManagerChannels
@@ -216,17 +519,81 @@ impl ManagerHandler {
         self.manager_channels
             .request_tx
             .send(request)
-            .expect("failed to send request")
+            .expect("failed to send request");
+        self.manager_channels.manager_wakeup.notify();
     }
 
     pub fn get_query_channels(&self) -> &QueryChannels {
         &self.manager_channels.query_channels
     }
 
+    /// Blocks until the manager answers, which can take a while if it has to ask the user.
+    /// See `file_system` (and the shortcuts below)
+    pub fn request_fs(&self, fs_request: FsRequest) -> FsResult {
+        // NOTE: not `send_request`, a tab that is being closed shouldn't panic over a file
+        self.manager_channels
+            .request_tx
+            .send(Request::FileSystem(fs_request))
+            .map_err(|_| FsError::ManagerGone)?;
+        self.manager_channels.manager_wakeup.notify();
+
+        self.manager_channels
+            .fs_response_rx
+            .recv()
+            .map_err(|_| FsError::ManagerGone)?
+    }
+
+    pub fn read_file(&self, path: impl Into<PathBuf>) -> Result<Vec<u8>, FsError> {
+        match self.request_fs(FsRequest::Read(path.into()))? {
+            FsResponse::Read(contents) => Ok(contents),
+            fs_response => Err(unexpected_fs_response(fs_response)),
+        }
+    }
+
+    pub fn read_file_to_string(&self, path: impl Into<PathBuf>) -> Result<String, FsError> {
+        String::from_utf8(self.read_file(path)?).map_err(|err| FsError::Other(err.to_string()))
+    }
+
+    pub fn write_file(
+        &self,
+        path: impl Into<PathBuf>,
+        contents: impl Into<Vec<u8>>,
+    ) -> Result<(), FsError> {
+        match self.request_fs(FsRequest::Write(path.into(), contents.into()))? {
+            FsResponse::Written => Ok(()),
+            fs_response => Err(unexpected_fs_response(fs_response)),
+        }
+    }
+
+    /// Sorted by path
+    pub fn list_dir(&self, path: impl Into<PathBuf>) -> Result<Vec<DirEntry>, FsError> {
+        match self.request_fs(FsRequest::ListDir(path.into()))? {
+            FsResponse::DirEntries(entries) => Ok(entries),
+            fs_response => Err(unexpected_fs_response(fs_response)),
+        }
+    }
+
+    /// Changes come as `Event::FileChanged` (until the tab closes)
+    pub fn watch(&self, path: impl Into<PathBuf>) -> Result<(), FsError> {
+        match self.request_fs(FsRequest::Watch(path.into()))? {
+            FsResponse::Watching => Ok(()),
+            fs_response => Err(unexpected_fs_response(fs_response)),
+        }
+    }
+
     pub fn update_ui_element(&mut self, ui_element: UIElement) {
-        *self.manager_channels.ui_element.lock().unwrap() =
+        let mut current_ui_element = self.manager_channels.ui_element.lock().unwrap();
+        if *current_ui_element == ui_element {
+            // don't wake the manager up for nothing
+            return;
+        }
+
+        *current_ui_element =
             // std::mem::take(&mut self.intermediate_display_buffer);
             ui_element;
+        drop(current_ui_element);
+
+        self.manager_channels.manager_wakeup.notify();
     }
 
     pub fn get_event_rx(&self) -> &Receiver<Event> {
@@ -236,4 +603,18 @@ impl ManagerHandler {
     pub fn collect_events(&self) -> Vec<Event> {
         self.get_event_rx().try_iter().collect()
     }
+
+    /// For other threads of the tab to wake it up (like `wait_for_events` does when the manager sends an event)
+    pub fn get_tab_wakeup(&self) -> Wakeup {
+        self.manager_channels.tab_wakeup.clone()
+    }
+
+    /// Blocks until the manager sends an event, or until the timeout (if any) runs out
+    pub fn wait_for_events(&self, timeout: Option<Duration>) {
+        if let Some(timeout) = timeout {
+            self.manager_channels.tab_wakeup.wait_timeout(timeout);
+        } else {
+            self.manager_channels.tab_wakeup.wait();
+        }
+    }
 }
@@ -0,0 +1,155 @@
+//! Tabs that aren't (only) placed by `super::tile::Tiles`:
+//! floating ones with their own area, minimized ones in the tray, and the maximized one.
+//!
+//! This replaced the old floating model (`display_order`), and sits on top of the tiles.
+
+use crate::utils::id_map::Id;
+use serde::{Deserialize, Serialize};
+use singularity_ui::display_units::{DisplayArea, DisplayCoord, DisplayUnits};
+
+use super::TabHandler;
+
+/// Where tabs float, when they weren't floating before
+pub const DEFAULT_FLOATING_AREA: DisplayArea =
+    DisplayArea::new_proportional([[0.25, 0.25], [0.75, 0.75]]);
+
+#[derive(Clone, Serialize, Deserialize, Debug, Copy, PartialEq)]
+pub struct FloatingTab {
+    pub tab_id: Id<TabHandler>,
+    pub area: DisplayArea,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Copy, PartialEq)]
+pub struct MinimizedTab {
+    pub tab_id: Id<TabHandler>,
+    /// Some if it was floating, so that it floats there again when restored.
+    /// Otherwise, it goes back into the tiles.
+    pub floating_area: Option<DisplayArea>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct Overlay {
+    /// drawn on top of the tiles, last on top
+    #[serde(default)]
+    floating: Vec<FloatingTab>,
+    /// hidden in the tray, oldest first
+    #[serde(default)]
+    minimized: Vec<MinimizedTab>,
+    /// shown over everything else (it can be tiled or floating)
+    #[serde(default)]
+    maximized: Option<Id<TabHandler>>,
+}
+impl Overlay {
+    pub fn is_empty(&self) -> bool {
+        self.floating.is_empty() && self.minimized.is_empty() && self.maximized.is_none()
+    }
+
+    /// floating or minimized
+    pub fn contains(&self, tab_id: Id<TabHandler>) -> bool {
+        self.get_floating_area(tab_id).is_some() || self.is_minimized(tab_id)
+    }
+
+    /// bottom to top
+    pub fn get_floating(&self) -> &[FloatingTab] {
+        &self.floating
+    }
+
+    pub fn get_floating_area(&self, tab_id: Id<TabHandler>) -> Option<DisplayArea> {
+        self.floating
+            .iter()
+            .find(|floating_tab| floating_tab.tab_id == tab_id)
+            .map(|floating_tab| floating_tab.area)
+    }
+
+    /// Puts it on top
+    pub fn float(&mut self, tab_id: Id<TabHandler>, area: DisplayArea) {
+        self.unfloat(tab_id);
+        self.floating.push(FloatingTab { tab_id, area });
+    }
+
+    /// Returns where it was floating
+    pub fn unfloat(&mut self, tab_id: Id<TabHandler>) -> Option<DisplayArea> {
+        let index = self
+            .floating
+            .iter()
+            .position(|floating_tab| floating_tab.tab_id == tab_id)?;
+
+        Some(self.floating.remove(index).area)
+    }
+
+    /// Puts it on top, if it is floating
+    pub fn raise(&mut self, tab_id: Id<TabHandler>) {
+        if let Some(area) = self.unfloat(tab_id) {
+            self.floating.push(FloatingTab { tab_id, area });
+        }
+    }
+
+    /// Moves a floating tab by `offset` (in proportions of the window)
+    pub fn move_floating(&mut self, tab_id: Id<TabHandler>, offset: [f32; 2]) {
+        if let Some(floating_tab) = self
+            .floating
+            .iter_mut()
+            .find(|floating_tab| floating_tab.tab_id == tab_id)
+        {
+            let offset = DisplayCoord::new(
+                DisplayUnits::Proportional(offset[0]),
+                DisplayUnits::Proportional(offset[1]),
+            );
+            let area = &mut floating_tab.area;
+            for corner in [&mut area.0, &mut area.1] {
+                corner.x = corner.x + offset.x;
+                corner.y = corner.y + offset.y;
+            }
+        }
+    }
+
+    /// oldest first
+    pub fn get_minimized(&self) -> &[MinimizedTab] {
+        &self.minimized
+    }
+
+    pub fn is_minimized(&self, tab_id: Id<TabHandler>) -> bool {
+        self.minimized
+            .iter()
+            .any(|minimized_tab| minimized_tab.tab_id == tab_id)
+    }
+
+    /// NOTE: it should already be out of the tiles and unfloated
+    pub fn minimize(&mut self, tab_id: Id<TabHandler>, floating_area: Option<DisplayArea>) {
+        if self.maximized == Some(tab_id) {
+            self.maximized = None;
+        }
+
+        self.minimized.push(MinimizedTab {
+            tab_id,
+            floating_area,
+        });
+    }
+
+    /// Takes it out of the tray (it's up to the caller to put it back)
+    pub fn unminimize(&mut self, tab_id: Id<TabHandler>) -> Option<MinimizedTab> {
+        let index = self
+            .minimized
+            .iter()
+            .position(|minimized_tab| minimized_tab.tab_id == tab_id)?;
+
+        Some(self.minimized.remove(index))
+    }
+
+    pub fn get_maximized(&self) -> Option<Id<TabHandler>> {
+        self.maximized
+    }
+
+    pub fn set_maximized(&mut self, maximized: Option<Id<TabHandler>>) {
+        self.maximized = maximized;
+    }
+
+    /// For when the tab is closed
+    pub fn remove(&mut self, tab_id: Id<TabHandler>) {
+        self.unfloat(tab_id);
+        self.unminimize(tab_id);
+        if self.maximized == Some(tab_id) {
+            self.maximized = None;
+        }
+    }
+}
@@ -1,15 +1,36 @@
-use super::TabCreator;
-use crate::{project::project_settings::TabData, utils::tree::tree_node_path::TreeNodePath};
+use super::{file_system::FsRequest, TabCreator};
+use crate::{
+    keymap::Keymap,
+    project::project_settings::{MergedSubappSettings, TabData},
+    utils::tree::tree_node_path::TreeNodePath,
+};
 use singularity_ui::{display_units::DisplayArea, ui_event::UIEvent};
+use std::{path::PathBuf, time::Duration};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum Event {
     UIEvent(UIEvent),
     Resize(DisplayArea),
     Focused,
     Unfocused,
-    /// TODO: close forcibly
+    /// The manager is about to save the session (eg: it is shutting down).
+    /// The tab should send its final `Request::UpdateSessionData` (if any),
+    /// then `Request::SessionDataUpToDate`.
+    ///
+    /// NOTE: `BasicTab`s do this by themselves
+    SaveSession,
+    /// The manager wants to close the tab (eg: Ctrl+W, or quitting).
+    /// The tab should answer with `Request::CloseResponse`.
+    ///
+    /// NOTE: `BasicTab`s do this by themselves, asking to save if they have unsaved changes
+    RequestClose,
+    /// Another tab vetoed the close, so the tab can stop asking to save
+    CloseCancelled,
+    /// The tab is being closed for real, so its thread should finish.
+    /// If it doesn't soon enough, the manager leaves it behind.
     Close,
+    /// A path the tab is watching changed, see `ManagerHandler::watch`
+    FileChanged(std::path::PathBuf),
 }
 impl Event {
     /// if mouseclick, return Some(remap area) if clicked within, else return None.
@@ -48,79 +69,373 @@ impl Event {
 pub enum Request {
     ChangeName(String),
     SpawnChildTab(Box<dyn TabCreator>, TabData),
+    /// Like `SpawnChildTab`, but the manager finds the tab creator from `TabData.tab_type`.
+    ///
+    /// NOTE: tabs in other processes can't send a `TabCreator`, so they use this
+    SpawnRegisteredChildTab(TabData),
+    /// Replaces the tab's `TabData.session_data`, which is what it gets back when it is reopened
+    UpdateSessionData(serde_json::Value),
+    /// The answer to `Event::SaveSession`
+    SessionDataUpToDate,
+    /// The answer to `Event::RequestClose`
+    CloseResponse(CloseResponse),
+    /// The tab waits for the answer, see `ManagerHandler::request_fs`
+    FileSystem(FsRequest),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum CloseResponse {
+    /// Ready to be closed
+    Accept,
+    /// Asking the user first, the real answer comes later.
+    ///
+    /// NOTE: tabs that don't answer at all are closed anyways after a timeout,
+    /// but prompting tabs are waited on
+    Prompting,
+    /// Don't close (this also cancels closing all the other tabs)
+    Veto,
+}
+
+/// Stands in for a query's argument, so that it can be written once per query with an argument
+macro_rules! query_arg {
+    ($arg:ident, $arg_type:ty) => {
+        $arg
+    };
+}
+
+/// `Name => Response` for queries without arguments, `Name(Argument) => Response` for the rest.
+///
+/// Queries that can fail answer with a `Result` (see `QueryError`).
+///
+/// Every query gets a `QueryId`, and its answer comes back with the same id,
+/// so a tab can have several queries out at once
+/// (see `QueryChannels::send_query` and the `send_*`s).
 macro_rules! query_macro {
-    ($($query_name:ident => $response_type:ty),*) => {paste::paste!{
+    ($($query_name:ident $(($arg_type:ty))? => $response_type:ty),* $(,)?) => {paste::paste!{
+        #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
         pub enum QueryTypes {
-            $($query_name),*
+            $($query_name $(($arg_type))?),*
+        }
+
+        /// The answer to any query, for when the query type isn't known at compile time
+        /// (eg: tabs in other processes)
+        #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+        pub enum QueryResponse {
+            $($query_name($response_type)),*
         }
 
         pub struct QueryChannels {
-            pub query_tx: std::sync::mpsc::Sender<QueryTypes>,
+            query_tx: std::sync::mpsc::Sender<(QueryId, QueryTypes)>,
+            response_rx: std::sync::mpsc::Receiver<(QueryId, QueryResponse)>,
+            /// answers that came in while waiting for a different one
+            unclaimed_responses:
+                std::cell::RefCell<std::collections::HashMap<QueryId, QueryResponse>>,
+            /// queries that timed out, so their answers are thrown away once they come
+            abandoned_queries: std::cell::RefCell<std::collections::HashSet<QueryId>>,
+            next_query_id: std::cell::Cell<u64>,
+
+            /// the manager sleeps until woken up, so it needs to be woken up to answer
+            pub manager_wakeup: singularity_ui::wakeup::Wakeup,
+        }
+        impl QueryChannels {
+            /// Doesn't wait for the answer, see `try_take_response` and `wait_for_response`
+            pub fn send_query(&self, query: QueryTypes) -> QueryId {
+                let query_id = QueryId(self.next_query_id.get());
+                self.next_query_id.set(query_id.0 + 1);
+
+                // (if the manager is gone, waiting for the answer says so)
+                let _ = self.query_tx.send((query_id, query));
+                self.manager_wakeup.notify();
+                query_id
+            }
+
+            /// None if it isn't answered yet
+            pub fn try_take_response(&self, query_id: QueryId) -> Option<QueryResponse> {
+                for (response_id, response) in self.response_rx.try_iter() {
+                    self.store_response(response_id, response);
+                }
+                self.unclaimed_responses.borrow_mut().remove(&query_id)
+            }
+
+            /// Keeps the answer until it's taken, unless no one is waiting for it anymore
+            fn store_response(&self, query_id: QueryId, response: QueryResponse) {
+                if !self.abandoned_queries.borrow_mut().remove(&query_id) {
+                    self.unclaimed_responses.borrow_mut().insert(query_id, response);
+                }
+            }
+
+            /// Waits forever if `timeout` is None
+            pub fn wait_for_response(
+                &self,
+                query_id: QueryId,
+                timeout: Option<std::time::Duration>,
+            ) -> Result<QueryResponse, QueryError> {
+                let deadline = timeout.map(|timeout| std::time::Instant::now() + timeout);
+                loop {
+                    let unclaimed_response =
+                        self.unclaimed_responses.borrow_mut().remove(&query_id);
+                    if let Some(response) = unclaimed_response {
+                        return Ok(response);
+                    }
+
+                    let (response_id, response) = match deadline {
+                        None => self.response_rx.recv().map_err(|_| QueryError::ManagerGone)?,
+                        Some(deadline) => match self.response_rx.recv_timeout(
+                            deadline.saturating_duration_since(std::time::Instant::now()),
+                        ) {
+                            Ok(received) => received,
+                            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                                // the answer might still come later
+                                self.abandoned_queries.borrow_mut().insert(query_id);
+                                return Err(QueryError::TimedOut);
+                            }
+                            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                                return Err(QueryError::ManagerGone);
+                            }
+                        },
+                    };
+                    if response_id == query_id {
+                        return Ok(response);
+                    }
+                    self.store_response(response_id, response);
+                }
+            }
+
             $(
-                pub [<$query_name:snake _rx>] : std::sync::mpsc::Receiver<$response_type>,
+                /// Blocks until the manager answers, or until it is gone (`QueryError::ManagerGone`)
+                pub fn [<ask_ $query_name:snake>](
+                    &self
+                    $(, arg: $arg_type)?
+                ) -> Result<$response_type, QueryError> {
+                    self.[<send_ $query_name:snake>]($(query_arg!(arg, $arg_type))?)
+                        .wait(self, None)
+                }
+
+                pub fn [<ask_ $query_name:snake _timeout>](
+                    &self
+                    $(, arg: $arg_type)?,
+                    timeout: std::time::Duration,
+                ) -> Result<$response_type, QueryError> {
+                    self.[<send_ $query_name:snake>]($(query_arg!(arg, $arg_type))?)
+                        .wait(self, Some(timeout))
+                }
+
+                /// Doesn't wait for the answer
+                pub fn [<send_ $query_name:snake>](
+                    &self
+                    $(, arg: $arg_type)?
+                ) -> PendingQuery<$response_type> {
+                    PendingQuery {
+                        query_id: self.send_query(
+                            QueryTypes::$query_name $((query_arg!(arg, $arg_type)))?
+                        ),
+                        take_response: |response| match response {
+                            QueryResponse::$query_name(response) => response,
+                            #[allow(unreachable_patterns)]
+                            response => panic!(
+                                "expected a `{}` response, got {response:?}",
+                                stringify!($query_name)
+                            ),
+                        },
+                    }
+                }
             )*
+
+            /// `ask_query!`, but the query is only known at runtime
+            pub fn ask(&self, query: QueryTypes) -> Result<QueryResponse, QueryError> {
+                self.ask_timeout(query, None)
+            }
+
+            /// Waits forever if `timeout` is None
+            pub fn ask_timeout(
+                &self,
+                query: QueryTypes,
+                timeout: Option<std::time::Duration>,
+            ) -> Result<QueryResponse, QueryError> {
+                self.wait_for_response(self.send_query(query), timeout)
+            }
         }
+        /// `ask_query!(query_channels, TabData)`,
+        /// or `ask_query!(query_channels, TabDataAt, org_path)` for queries with an argument
+        ///
+        /// Blocks like the `ask_*` methods, so it fails if the manager goes away while waiting
+        ///
+        /// NOTE: this can't be used from inside this crate, use the `ask_*` methods instead
         #[macro_export]
         macro_rules! ask_query {
-            $(($ask_channel:expr, $query_name) => {
-                {
-                    $ask_channel.query_tx
-                        .send($crate::tab::packets::QueryTypes::$query_name)
-                        .expect("failed to send query");
-
-                    $ask_channel.[<$query_name:snake _rx>]
-                        .recv()
-                        .expect("failed to get response")
-                }
-            };)*
+            $(
+                ($ask_channel:expr, $query_name) => {
+                    $ask_channel.[<ask_ $query_name:snake>]()
+                };
+                ($ask_channel:expr, $query_name, $query_arg:expr) => {
+                    $ask_channel.[<ask_ $query_name:snake>]($query_arg)
+                };
+            )*
         }
 
         pub struct RespondChannels {
-            pub query_rx: std::sync::mpsc::Receiver<QueryTypes>,
-            $(pub [<$query_name:snake _tx>]: std::sync::mpsc::Sender<$response_type>,)*
+            query_rx: std::sync::mpsc::Receiver<(QueryId, QueryTypes)>,
+            /// taken out of `query_rx` to see if there are more, but not answered yet
+            next_query: std::cell::RefCell<Option<(QueryId, QueryTypes)>>,
+            response_tx: std::sync::mpsc::Sender<(QueryId, QueryResponse)>,
+            /// for tabs that don't block on their queries
+            tab_wakeup: singularity_ui::wakeup::Wakeup,
         }
-        #[automatically_derived]
         impl RespondChannels {
-            pub fn answer_query<$([<$query_name Responder>]: FnOnce() -> $response_type,)*>(&self, $([<$query_name:snake _responder>]: [<$query_name Responder>],)*) {
-                if let Ok(query) = self.query_rx.try_recv() {
-                    match query {
+            /// Answers with a response that was made somewhere else
+            /// (eg: by the manager of another process)
+            pub fn respond(&self, query_id: QueryId, response: QueryResponse) {
+                // NOTE: the tab might be gone
+                let _ = self.response_tx.send((query_id, response));
+                self.tab_wakeup.notify();
+            }
+
+            fn try_recv_query(&self) -> Option<(QueryId, QueryTypes)> {
+                self.next_query
+                    .borrow_mut()
+                    .take()
+                    .or_else(|| self.query_rx.try_recv().ok())
+            }
+
+            /// If there are queries waiting to be answered
+            pub fn has_queries(&self) -> bool {
+                let mut next_query = self.next_query.borrow_mut();
+                if next_query.is_none() {
+                    *next_query = self.query_rx.try_recv().ok();
+                }
+                next_query.is_some()
+            }
+
+            /// Every query that hasn't been answered yet,
+            /// for answering them somewhere else (see `respond`)
+            pub fn take_queries(&self) -> Vec<(QueryId, QueryTypes)> {
+                std::iter::from_fn(|| self.try_recv_query()).collect()
+            }
+
+            /// Answers up to `limit` queries, so that one tab can't keep the others waiting.
+            /// The responders get the query's argument (if it has one).
+            ///
+            /// Returns true if there are still queries left (only when it stopped at the limit).
+            #[allow(clippy::too_many_arguments)] // (one responder per query)
+            pub fn answer_queries<
+                $([<$query_name Responder>]: FnMut($($arg_type)?) -> $response_type,)*
+            >(
+                &self,
+                limit: usize,
+                $(mut [<$query_name:snake _responder>]: [<$query_name Responder>],)*
+            ) -> bool {
+                for _ in 0..limit {
+                    let Some((query_id, query)) = self.try_recv_query() else {
+                        return false;
+                    };
+
+                    let response = match query {
                         $(
-                            $crate::tab::packets::QueryTypes::$query_name => {
-                                self.[<$query_name:snake _tx>]
-                                    .send([<$query_name:snake _responder>]())
-                                    .expect("failed to send response");
-                            },
+                            $crate::tab::packets::QueryTypes::$query_name
+                                $((query_arg!(arg, $arg_type)))? => QueryResponse::$query_name(
+                                    [<$query_name:snake _responder>]($(query_arg!(arg, $arg_type))?)
+                                ),
                         )*
-                    }
+                    };
+                    self.respond(query_id, response);
                 }
+                self.has_queries()
             }
         }
 
-        pub fn create_query_channels() -> (QueryChannels, RespondChannels) {
+        pub fn create_query_channels(
+            manager_wakeup: singularity_ui::wakeup::Wakeup,
+            tab_wakeup: singularity_ui::wakeup::Wakeup,
+        ) -> (QueryChannels, RespondChannels) {
             let (query_tx, query_rx) = std::sync::mpsc::channel();
-
-            $(
-                let ([<$query_name:snake _tx>], [<$query_name:snake _rx>]) = std::sync::mpsc::channel();
-            )*
+            let (response_tx, response_rx) = std::sync::mpsc::channel();
 
             (
                 QueryChannels {
                     query_tx,
-                    $(
-                        [<$query_name:snake _rx>],
-                    )*
+                    response_rx,
+                    unclaimed_responses: Default::default(),
+                    abandoned_queries: Default::default(),
+                    next_query_id: Default::default(),
+                    manager_wakeup,
                 },
                 RespondChannels {
                     query_rx,
-                    $(
-                        [<$query_name:snake _tx>],
-                    )*
+                    next_query: Default::default(),
+                    response_tx,
+                    tab_wakeup,
                 }
             )
         }
     }};
 }
-query_macro!(OrgPath => TreeNodePath, TabName => String, TabData => TabData);
-// TODO: add something to get the project directory
+query_macro!(
+    OrgPath => TreeNodePath,
+    TabName => String,
+    TabData => TabData,
+    Keymap => Keymap,
+    Settings => MergedSubappSettings,
+    ProjectDirectory => PathBuf,
+    // every open tab, in org tree order
+    TabList => Vec<TabInfo>,
+    FocusedTab => TabInfo,
+    TabDataAt(TreeNodePath) => Result<TabData, QueryError>,
+);
+
+/// Matches a query to its answer (unique per `QueryChannels`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct QueryId(pub u64);
+
+/// A query that was sent without waiting, see the `QueryChannels::send_*`s
+pub struct PendingQuery<T> {
+    pub query_id: QueryId,
+    take_response: fn(QueryResponse) -> T,
+}
+impl<T> PendingQuery<T> {
+    /// None if it isn't answered yet
+    pub fn try_take(&self, query_channels: &QueryChannels) -> Option<T> {
+        query_channels
+            .try_take_response(self.query_id)
+            .map(self.take_response)
+    }
+
+    /// Waits forever if `timeout` is None
+    pub fn wait(
+        self,
+        query_channels: &QueryChannels,
+        timeout: Option<Duration>,
+    ) -> Result<T, QueryError> {
+        query_channels
+            .wait_for_response(self.query_id, timeout)
+            .map(self.take_response)
+    }
+}
+
+/// What `TabList` and `FocusedTab` know about a tab
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TabInfo {
+    pub org_path: TreeNodePath,
+    /// empty if the tab didn't set one
+    pub tab_name: String,
+    pub tab_type: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum QueryError {
+    /// (it might have been closed since)
+    NoSuchTab(TreeNodePath),
+    /// the manager didn't answer in time (eg: it is busy, or shutting down)
+    TimedOut,
+    /// the manager stopped answering for good (the tab is being closed)
+    ManagerGone,
+}
+impl std::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryError::NoSuchTab(org_path) => write!(f, "no tab at {org_path:?}"),
+            QueryError::TimedOut => write!(f, "the manager didn't answer in time"),
+            QueryError::ManagerGone => write!(f, "the manager is gone"),
+        }
+    }
+}
+impl std::error::Error for QueryError {}
@@ -0,0 +1,147 @@
+//! Which tab types exist, so that `TabData.tab_type` can be turned into a running tab.
+//!
+//! Crates with tabs register them (see `singularity_standard_tabs::register_standard_tabs`),
+//! and the manager only looks at the registry.
+
+use super::{BasicTab, InitError, ManagerHandler, TabCreator};
+use crate::{project::project_settings::TabData, tab::packets::Event};
+use singularity_ui::{color::Color, ui_element::UIElement};
+
+type TabCreatorFactory = Box<dyn Fn() -> Box<dyn TabCreator> + Send + Sync>;
+
+pub struct RegisteredTabType {
+    /// What is saved as `TabData.tab_type` (eg: `"FILE_MANAGER"`)
+    pub type_id: String,
+    /// What the user sees (eg: `"File Manager"`)
+    pub display_name: String,
+    factory: TabCreatorFactory,
+}
+
+/// NOTE: keeps the order things were registered in, so the launcher doesn't shuffle around
+#[derive(Default)]
+pub struct TabRegistry {
+    tab_types: Vec<RegisteredTabType>,
+}
+impl TabRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registering a type id again replaces the old one
+    pub fn register<F>(
+        &mut self,
+        type_id: impl Into<String>,
+        display_name: impl Into<String>,
+        factory: F,
+    ) where
+        F: 'static + Fn() -> Box<dyn TabCreator> + Send + Sync,
+    {
+        let registered_tab_type = RegisteredTabType {
+            type_id: type_id.into(),
+            display_name: display_name.into(),
+            factory: Box::new(factory),
+        };
+
+        if let Some(existing) = self
+            .tab_types
+            .iter_mut()
+            .find(|existing| existing.type_id == registered_tab_type.type_id)
+        {
+            *existing = registered_tab_type;
+        } else {
+            self.tab_types.push(registered_tab_type);
+        }
+    }
+
+    /// Shorthand for registering a `BasicTab`
+    pub fn register_basic_tab<T: 'static + BasicTab>(
+        &mut self,
+        type_id: impl Into<String>,
+        display_name: impl Into<String>,
+    ) {
+        self.register(type_id, display_name, || Box::new(T::new_tab_creator()));
+    }
+
+    /// Registers a tab type that runs `program` as its own process (see `super::external`)
+    pub fn register_external(
+        &mut self,
+        type_id: impl Into<String>,
+        display_name: impl Into<String>,
+        program: impl Into<std::path::PathBuf>,
+        args: Vec<String>,
+    ) {
+        let program = program.into();
+        self.register(type_id, display_name, move || {
+            Box::new(super::external::ExternalTab::new(
+                program.clone(),
+                args.clone(),
+            ))
+        });
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RegisteredTabType> {
+        self.tab_types.iter()
+    }
+
+    pub fn contains(&self, type_id: &str) -> bool {
+        self.get(type_id).is_some()
+    }
+
+    pub fn get(&self, type_id: &str) -> Option<&RegisteredTabType> {
+        self.tab_types
+            .iter()
+            .find(|registered_tab_type| registered_tab_type.type_id == type_id)
+    }
+
+    pub fn get_tab_creator(&self, type_id: &str) -> Option<Box<dyn TabCreator>> {
+        self.get(type_id)
+            .map(|registered_tab_type| (registered_tab_type.factory)())
+    }
+
+    /// Unknown types get a `MissingTabType` tab instead, so a bad `core.json` doesn't crash the manager
+    pub fn get_tab_creator_or_placeholder(&self, type_id: &str) -> Box<dyn TabCreator> {
+        self.get_tab_creator(type_id).unwrap_or_else(|| {
+            println!("Unknown tab type `{type_id}`, using a placeholder");
+            Box::new(MissingTabType::new_tab_creator())
+        })
+    }
+}
+
+/// Stands in for a tab whose type isn't registered.
+///
+/// NOTE: the session data lives in the `TabHandler`, so it is saved like any other tab's,
+/// and the tab comes back once its type is registered again.
+pub struct MissingTabType {
+    tab_data: TabData,
+}
+impl BasicTab for MissingTabType {
+    fn initialize_tab(manager_handler: &ManagerHandler) -> Result<Self, InitError> {
+        let tab_data = manager_handler.get_query_channels().ask_tab_data()?;
+
+        manager_handler.send_request(crate::tab::packets::Request::ChangeName(format!(
+            "Missing: {}",
+            tab_data.tab_type
+        )));
+
+        Ok(Self { tab_data })
+    }
+
+    fn render_tab(&mut self, _manager_handler: &ManagerHandler) -> Option<UIElement> {
+        let session_data = serde_json::to_string_pretty(&self.tab_data.session_data)
+            .unwrap_or_else(|_| self.tab_data.session_data.to_string());
+
+        Some(
+            UIElement::CharGrid(
+                format!(
+                    "Missing tab type `{}`\nSession data (kept until the type is back):\n{}",
+                    self.tab_data.tab_type, session_data
+                )
+                .into(),
+            )
+            .fill_bg(Color::DARK_GRAY)
+            .bordered(Color::RED),
+        )
+    }
+
+    fn handle_tab_event(&mut self, _event: Event, _manager_handler: &ManagerHandler) {}
+}
@@ -1,5 +1,5 @@
 //! this is for tab placement, like hyprland
-//! Tabs are stored in a binary tree method
+//! Tabs are stored in a tree of containers (it used to be binary), like i3/sway
 
 use crate::utils::id_map::{Id, IdMap};
 use serde::{Deserialize, Serialize};
@@ -7,7 +7,15 @@ use std::collections::BTreeMap;
 
 use super::TabHandler;
 
-#[derive(Clone, Serialize, Deserialize, Debug, Copy)]
+/// Splits are kept between this and `1. - MIN_SPLIT`, so that a tile can't be resized away
+///
+/// With more than 2 children, this is the smallest share any child can get
+pub const MIN_SPLIT: f32 = 0.05;
+
+/// An area of the window, in proportions, as `[[min_x, min_y], [max_x, max_y]]`
+pub type TileArea = [[f32; 2]; 2];
+
+#[derive(Clone, Serialize, Deserialize, Debug, Copy, PartialEq, Eq)]
 pub enum Orientation {
     Horizontal,
     Vertical,
@@ -19,20 +27,168 @@ impl Orientation {
             Orientation::Vertical => Orientation::Horizontal,
         }
     }
+
+    /// Which coordinate (`[x, y]` index) the children are laid out along
+    ///
+    /// NOTE: `Horizontal` stacks the children vertically (the boundaries are horizontal)
+    pub fn axis(&self) -> usize {
+        match self {
+            Orientation::Horizontal => 1,
+            Orientation::Vertical => 0,
+        }
+    }
+}
+
+/// How `Tiles::add_tab` places new tabs, and how `Tiles::relayout` arranges all of them
+#[derive(Clone, Serialize, Deserialize, Debug, Copy, Default, PartialEq, Eq)]
+pub enum LayoutPolicy {
+    /// the focused tile is split in half, alternating directions, so it spirals inwards
+    #[default]
+    Spiral,
+    /// the first tab on the left, and every other tab stacked on the right
+    MasterStack,
+    /// rows of (about) as many tabs as there are rows
+    Grid,
+    /// the new tab goes on top of the focused one, as another tab of a tabbed container
+    ReplaceFocused,
+}
+impl LayoutPolicy {
+    pub const ALL: [LayoutPolicy; 4] = [
+        LayoutPolicy::Spiral,
+        LayoutPolicy::MasterStack,
+        LayoutPolicy::Grid,
+        LayoutPolicy::ReplaceFocused,
+    ];
+
+    /// for cycling through `ALL`
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|policy| policy == &self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Copy, Default, PartialEq, Eq)]
+pub enum ContainerMode {
+    /// the children are next to each other, sized by their weights
+    #[default]
+    Split,
+    /// only the `active` child is shown, under a title strip of every child (like i3/sway's tabbed layout)
+    Tabbed { active: usize },
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(from = "SavedContainer")]
+pub struct Container {
+    pub children: Vec<Id<Tile>>,
+    /// how much of the container each child gets, relative to the others (one per child)
+    pub weights: Vec<f32>,
+    pub orientation: Orientation,
+    pub mode: ContainerMode,
+}
+impl Container {
+    /// the children get the same weight
+    pub fn new(children: Vec<Id<Tile>>, orientation: Orientation) -> Self {
+        Self {
+            weights: vec![1.; children.len()],
+            children,
+            orientation,
+            mode: ContainerMode::Split,
+        }
+    }
+
+    /// the weights as proportions of the container (they add up to 1)
+    pub fn shares(&self) -> Vec<f32> {
+        let total: f32 = self.weights.iter().sum();
+        if total <= 0. {
+            return vec![1. / self.children.len() as f32; self.children.len()];
+        }
+
+        self.weights.iter().map(|weight| weight / total).collect()
+    }
+
+    /// How `area` is split between the children (ignores `mode`)
+    pub fn child_areas(&self, area: TileArea) -> Vec<TileArea> {
+        let axis = self.orientation.axis();
+        let [min, max] = area;
+
+        let mut start = min[axis];
+        self.shares()
+            .into_iter()
+            .map(|share| {
+                let end = start + share * (max[axis] - min[axis]);
+                let mut child_area = area;
+                child_area[0][axis] = start;
+                child_area[1][axis] = end;
+                start = end;
+
+                child_area
+            })
+            .collect()
+    }
+
+    /// Some if tabbed
+    pub fn get_active(&self) -> Option<usize> {
+        match self.mode {
+            ContainerMode::Split => None,
+            ContainerMode::Tabbed { active } => Some(active),
+        }
+    }
+
+    fn remove_child(&mut self, index: usize) {
+        self.children.remove(index);
+        self.weights.remove(index);
+
+        if let ContainerMode::Tabbed { active } = &mut self.mode {
+            if *active > index || *active >= self.children.len() {
+                *active = active.saturating_sub(1);
+            }
+        }
+    }
+}
+
+/// What `Container` is deserialized from, since older sessions had exactly 2 children and a `split`
+#[derive(Deserialize)]
+struct SavedContainer {
+    children: Vec<Id<Tile>>,
+    #[serde(default)]
+    weights: Vec<f32>,
+    orientation: Orientation,
+    #[serde(default)]
+    mode: ContainerMode,
+    /// (old) how much the first of the 2 children got
+    #[serde(default)]
+    split: Option<f32>,
+}
+impl From<SavedContainer> for Container {
+    fn from(saved: SavedContainer) -> Self {
+        let weights = match (saved.split, saved.children.len()) {
+            _ if saved.weights.len() == saved.children.len() => saved.weights,
+            (Some(split), 2) => vec![split, 1. - split],
+            (_, children_len) => vec![1.; children_len],
+        };
+        let mode = match saved.mode {
+            ContainerMode::Tabbed { active } => ContainerMode::Tabbed {
+                active: active.min(saved.children.len().saturating_sub(1)),
+            },
+            mode => mode,
+        };
+
+        Self {
+            children: saved.children,
+            weights,
+            orientation: saved.orientation,
+            mode,
+        }
+    }
 }
 
 /// Could do recursive enums, but I will do the UUID way
 ///
 /// REVIEW: abstract something about the pattern where items are represented by Uuids and stored in B-maps
 /// Maybe like ID map
-#[derive(Clone, Serialize, Deserialize, Debug, Copy)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub enum Tile {
-    Container {
-        // parent_tile: Option<Id<Tile>>,
-        children: [Id<Tile>; 2],
-        orientation: Orientation,
-        split: f32,
-    },
+    Container(Container),
     /// Leaf node points to a full window
     Tab {
         /// REVIEW: adding onto the id map idea, I want to be able to specify this is a Uuid pointing to Tab
@@ -40,14 +196,17 @@ pub enum Tile {
     },
 }
 impl Tile {
-    pub fn try_as_container(&self) -> Option<([Id<Tile>; 2], Orientation, f32)> {
-        if let Tile::Container {
-            children,
-            orientation,
-            split,
-        } = *self
-        {
-            Some((children, orientation, split))
+    pub fn try_as_container(&self) -> Option<&Container> {
+        if let Tile::Container(container) = self {
+            Some(container)
+        } else {
+            None
+        }
+    }
+
+    fn try_as_container_mut(&mut self) -> Option<&mut Container> {
+        if let Tile::Container(container) = self {
+            Some(container)
         } else {
             None
         }
@@ -89,9 +248,200 @@ impl Tiles {
         }
     }
 
+    /// Places the new tab, next to (or on top of) the focused one, depending on the policy
+    pub fn add_tab(
+        &mut self,
+        focused_tab_id: Id<TabHandler>,
+        new_tab_id: Id<TabHandler>,
+        layout_policy: LayoutPolicy,
+    ) {
+        match layout_policy {
+            LayoutPolicy::Spiral => {
+                // turn the other way from the container it is in
+                let orientation = self
+                    .get_parent_tile_id(self.leaf_registry[&focused_tab_id])
+                    .map_or(Orientation::Vertical, |parent_tile_id| {
+                        self.tiles[&parent_tile_id]
+                            .try_as_container()
+                            .unwrap()
+                            .orientation
+                            .get_transpose()
+                    });
+
+                self.split_leaf(focused_tab_id, new_tab_id, orientation);
+            }
+            LayoutPolicy::ReplaceFocused => {
+                self.give_sibling(focused_tab_id, new_tab_id);
+
+                // (if it was already in a tabbed container, then this is it)
+                let container_tile_id = self
+                    .get_parent_tile_id(self.leaf_registry[&new_tab_id])
+                    .unwrap();
+                let container = self.get_container_mut(container_tile_id).unwrap();
+                if container.get_active().is_none() {
+                    container.mode = ContainerMode::Tabbed { active: 1 };
+                }
+            }
+            LayoutPolicy::MasterStack | LayoutPolicy::Grid => {
+                let mut tab_ids = self.collect_leaves();
+                tab_ids.push(new_tab_id);
+
+                self.build(&tab_ids, layout_policy, new_tab_id);
+            }
+        }
+    }
+
+    /// Rearranges every tab (in their current order) with the policy
+    pub fn relayout(&mut self, layout_policy: LayoutPolicy, focused_tab_id: Id<TabHandler>) {
+        let tab_ids = self.collect_leaves();
+        self.build(&tab_ids, layout_policy, focused_tab_id);
+    }
+
+    /// Every tab, in order (depth first)
+    pub fn collect_leaves(&self) -> Vec<Id<TabHandler>> {
+        let mut tab_ids = Vec::new();
+        let mut unvisited_tile_ids = vec![self.root_id];
+        while let Some(tile_id) = unvisited_tile_ids.pop() {
+            match &self.tiles[&tile_id] {
+                Tile::Tab { tab_id } => tab_ids.push(*tab_id),
+                Tile::Container(container) => {
+                    unvisited_tile_ids.extend(container.children.iter().rev());
+                }
+            }
+        }
+
+        tab_ids
+    }
+
+    /// Throws away the current tiles, and arranges the tabs from scratch
+    fn build(
+        &mut self,
+        tab_ids: &[Id<TabHandler>],
+        layout_policy: LayoutPolicy,
+        focused_tab_id: Id<TabHandler>,
+    ) {
+        self.tiles = IdMap::new();
+        self.leaf_registry = BTreeMap::new();
+
+        self.root_id = match layout_policy {
+            LayoutPolicy::Spiral => self.build_spiral(tab_ids, Orientation::Vertical),
+            LayoutPolicy::MasterStack => {
+                let master_tile_id = self.insert_leaf(tab_ids[0]);
+                if tab_ids.len() == 1 {
+                    master_tile_id
+                } else {
+                    let stack_tile_id = self.build_line(&tab_ids[1..], Orientation::Horizontal);
+                    self.insert_container(
+                        vec![master_tile_id, stack_tile_id],
+                        Orientation::Vertical,
+                    )
+                }
+            }
+            LayoutPolicy::Grid => {
+                let columns = (tab_ids.len() as f32).sqrt().ceil() as usize;
+                let row_tile_ids = tab_ids
+                    .chunks(columns)
+                    .map(|row_tab_ids| self.build_line(row_tab_ids, Orientation::Vertical))
+                    .collect();
+                self.insert_container(row_tile_ids, Orientation::Horizontal)
+            }
+            LayoutPolicy::ReplaceFocused => {
+                let tile_id = self.build_line(tab_ids, Orientation::Horizontal);
+                if let Some(container) = self.get_container_mut(tile_id) {
+                    container.mode = ContainerMode::Tabbed { active: 0 };
+                }
+                tile_id
+            }
+        };
+
+        self.show_leaf(focused_tab_id);
+    }
+
+    fn build_spiral(&mut self, tab_ids: &[Id<TabHandler>], orientation: Orientation) -> Id<Tile> {
+        let first_tile_id = self.insert_leaf(tab_ids[0]);
+        if tab_ids.len() == 1 {
+            return first_tile_id;
+        }
+
+        let rest_tile_id = self.build_spiral(&tab_ids[1..], orientation.get_transpose());
+        self.insert_container(vec![first_tile_id, rest_tile_id], orientation)
+    }
+
+    /// every tab next to each other, with the same weights
+    fn build_line(&mut self, tab_ids: &[Id<TabHandler>], orientation: Orientation) -> Id<Tile> {
+        let child_tile_ids = tab_ids
+            .iter()
+            .map(|tab_id| self.insert_leaf(*tab_id))
+            .collect();
+        self.insert_container(child_tile_ids, orientation)
+    }
+
+    fn insert_leaf(&mut self, tab_id: Id<TabHandler>) -> Id<Tile> {
+        let tile_id = Id::generate();
+        self.tiles.insert(tile_id, Tile::Tab { tab_id });
+        self.leaf_registry.insert(tab_id, tile_id);
+
+        tile_id
+    }
+
+    /// A single child doesn't need a container
+    fn insert_container(&mut self, children: Vec<Id<Tile>>, orientation: Orientation) -> Id<Tile> {
+        if children.len() == 1 {
+            return children[0];
+        }
+
+        let tile_id = Id::generate();
+        self.tiles.insert(
+            tile_id,
+            Tile::Container(Container::new(children, orientation)),
+        );
+
+        tile_id
+    }
+
+    /// Splits the older tab's tile in half.
+    /// If it is in a tabbed container, then the younger tab is added as another tab instead.
     pub fn give_sibling(&mut self, older_tab_id: Id<TabHandler>, younger_tab_id: Id<TabHandler>) {
+        self.split_leaf(older_tab_id, younger_tab_id, Orientation::Horizontal);
+    }
+
+    /// (see `give_sibling`)
+    fn split_leaf(
+        &mut self,
+        older_tab_id: Id<TabHandler>,
+        younger_tab_id: Id<TabHandler>,
+        orientation: Orientation,
+    ) {
         let original_tile_id = self.leaf_registry[&older_tab_id];
 
+        if let Some(parent_tile_id) = self.get_parent_tile_id(original_tile_id) {
+            let parent = self.tiles[&parent_tile_id].try_as_container().unwrap();
+            if parent.get_active().is_some() {
+                let index = parent
+                    .children
+                    .iter()
+                    .position(|child_id| child_id == &original_tile_id)
+                    .unwrap()
+                    + 1;
+
+                let younger_tile_id = Id::generate();
+                self.tiles.insert(
+                    younger_tile_id,
+                    Tile::Tab {
+                        tab_id: younger_tab_id,
+                    },
+                );
+                self.leaf_registry.insert(younger_tab_id, younger_tile_id);
+
+                let parent = self.get_container_mut(parent_tile_id).unwrap();
+                parent.children.insert(index, younger_tile_id);
+                parent.weights.insert(index, 1.);
+                parent.mode = ContainerMode::Tabbed { active: index };
+
+                return;
+            }
+        }
+
         let older_tile_id = Id::generate();
         let younger_tile_id = Id::generate();
         self.tiles.insert(
@@ -113,11 +463,10 @@ impl Tiles {
 
         assert!(matches!(original_tile, Tile::Tab { tab_id } if tab_id==&older_tab_id));
 
-        *original_tile = Tile::Container {
-            children: [older_tile_id, younger_tile_id],
-            orientation: Orientation::Horizontal,
-            split: 0.5,
-        };
+        *original_tile = Tile::Container(Container::new(
+            vec![older_tile_id, younger_tile_id],
+            orientation,
+        ));
     }
 
     pub fn remove(&mut self, tab_id: Id<TabHandler>) {
@@ -126,22 +475,27 @@ impl Tiles {
             .get_parent_tile_id(tile_to_remove)
             .expect("tried to remove root tile");
 
-        let sibling_id = self.tiles[&parent_tile_id]
-            .try_as_container()
-            .expect("parent must be variant `Tile::Container`")
-            .0
-            .into_iter()
-            .find(|c| c != &tile_to_remove)
+        let parent = self
+            .get_container_mut(parent_tile_id)
+            .expect("parent must be variant `Tile::Container`");
+        let index = parent
+            .children
+            .iter()
+            .position(|c| c == &tile_to_remove)
             .unwrap();
+        parent.remove_child(index);
 
-        // the sibling takes the place of the parent
-        let sibling = self.tiles.remove(&sibling_id).unwrap();
-        self.tiles.insert(parent_tile_id, sibling).unwrap();
-        if let Some(sibling_tab) = sibling.try_as_tab() {
-            // update the leaf registry entry for sibling tab
-            self.leaf_registry
-                .insert(sibling_tab, parent_tile_id)
-                .unwrap();
+        if parent.children.len() == 1 {
+            // the last child takes the place of the parent
+            let sibling_id = parent.children[0];
+            let sibling = self.tiles.remove(&sibling_id).unwrap();
+            if let Some(sibling_tab) = sibling.try_as_tab() {
+                // update the leaf registry entry for sibling tab
+                self.leaf_registry
+                    .insert(sibling_tab, parent_tile_id)
+                    .unwrap();
+            }
+            self.tiles.insert(parent_tile_id, sibling).unwrap();
         }
 
         self.leaf_registry.remove(&tab_id).unwrap();
@@ -160,34 +514,325 @@ impl Tiles {
         self.tiles.get(&tile_id)
     }
 
+    fn get_container_mut(&mut self, container_tile_id: Id<Tile>) -> Option<&mut Container> {
+        self.tiles
+            .get_mut(&container_tile_id)
+            .and_then(Tile::try_as_container_mut)
+    }
+
     pub fn transpose_container(&mut self, container_tile_id: Id<Tile>) {
-        if let Some(Tile::Container {
-            children: _,
-            orientation,
-            split: _,
-        }) = self.tiles.get_mut(&container_tile_id)
-        {
-            *orientation = orientation.get_transpose();
+        if let Some(container) = self.get_container_mut(container_tile_id) {
+            container.orientation = container.orientation.get_transpose();
         }
     }
 
+    /// reverses the order of the children (so swaps them, if there are 2)
     pub fn swap_children(&mut self, container_tile_id: Id<Tile>) {
-        if let Some(Tile::Container {
-            children,
-            orientation: _,
-            split: _,
-        }) = self.tiles.get_mut(&container_tile_id)
-        {
-            children.swap(0, 1);
+        if let Some(container) = self.get_container_mut(container_tile_id) {
+            container.children.reverse();
+            container.weights.reverse();
+
+            if let ContainerMode::Tabbed { active } = &mut container.mode {
+                *active = container.children.len() - 1 - *active;
+            }
+        }
+    }
+
+    /// The two tabs trade places
+    pub fn swap_leaves(&mut self, tab_ids: [Id<TabHandler>; 2]) {
+        let tile_ids = tab_ids.map(|tab_id| self.leaf_registry[&tab_id]);
+
+        for (tile_id, tab_id) in tile_ids.into_iter().zip(tab_ids.into_iter().rev()) {
+            self.tiles.insert(tile_id, Tile::Tab { tab_id });
+            self.leaf_registry.insert(tab_id, tile_id);
+        }
+    }
+
+    /// The new tab takes the old one's place (for when the new one wasn't in the tiles)
+    pub fn replace_leaf(&mut self, old_tab_id: Id<TabHandler>, new_tab_id: Id<TabHandler>) {
+        let tile_id = self.leaf_registry.remove(&old_tab_id).unwrap();
+        self.tiles.insert(tile_id, Tile::Tab { tab_id: new_tab_id });
+        self.leaf_registry.insert(new_tab_id, tile_id);
+    }
+
+    /// Switches the tab's parent container between split and tabbed.
+    /// When it becomes tabbed, the tab is the one shown.
+    pub fn toggle_tabbed(&mut self, tab_id: Id<TabHandler>) {
+        let leaf_tile_id = self.get_leaf_tile_id(tab_id).unwrap();
+        let Some(container_tile_id) = self.get_parent_tile_id(leaf_tile_id) else {
+            // root can't have tabs
+            return;
+        };
+        let container = self.get_container_mut(container_tile_id).unwrap();
+
+        container.mode = match container.mode {
+            ContainerMode::Split => ContainerMode::Tabbed {
+                active: container
+                    .children
+                    .iter()
+                    .position(|child_id| child_id == &leaf_tile_id)
+                    .unwrap(),
+            },
+            ContainerMode::Tabbed { .. } => ContainerMode::Split,
+        };
+    }
+
+    /// Makes every tabbed container that the tab is in show it (for when it is focused)
+    pub fn show_leaf(&mut self, tab_id: Id<TabHandler>) {
+        let Some(mut tile_id) = self.get_leaf_tile_id(tab_id) else {
+            return;
+        };
+
+        while let Some(container_tile_id) = self.get_parent_tile_id(tile_id) {
+            let container = self.get_container_mut(container_tile_id).unwrap();
+            if let ContainerMode::Tabbed { active } = &mut container.mode {
+                *active = container
+                    .children
+                    .iter()
+                    .position(|child_id| child_id == &tile_id)
+                    .unwrap();
+            }
+
+            tile_id = container_tile_id;
+        }
+    }
+
+    /// False if a tabbed container is showing something else
+    pub fn is_leaf_visible(&self, tab_id: Id<TabHandler>) -> bool {
+        let Some(mut tile_id) = self.get_leaf_tile_id(tab_id) else {
+            return false;
+        };
+
+        while let Some(container_tile_id) = self.get_parent_tile_id(tile_id) {
+            let container = self.tiles[&container_tile_id].try_as_container().unwrap();
+            if let Some(active) = container.get_active() {
+                if container.children[active] != tile_id {
+                    return false;
+                }
+            }
+
+            tile_id = container_tile_id;
+        }
+
+        true
+    }
+
+    /// The tab that would be seen first in the tile (the active one for tabbed containers)
+    pub fn first_visible_leaf(&self, tile_id: Id<Tile>) -> Id<TabHandler> {
+        match &self.tiles[&tile_id] {
+            Tile::Tab { tab_id } => *tab_id,
+            Tile::Container(container) => self
+                .first_visible_leaf(container.children[container.get_active().unwrap_or_default()]),
         }
     }
 
+    /// Shows the child of a tabbed container, and returns the tab that should be focused
+    pub fn select_tabbed_child(
+        &mut self,
+        container_tile_id: Id<Tile>,
+        index: usize,
+    ) -> Option<Id<TabHandler>> {
+        let container = self.get_container_mut(container_tile_id)?;
+        let ContainerMode::Tabbed { active } = &mut container.mode else {
+            return None;
+        };
+        let child_id = *container.children.get(index)?;
+        *active = index;
+
+        Some(self.first_visible_leaf(child_id))
+    }
+
+    /// Moves the closest tabbed container (that the tab is in) `offset` tabs over, wrapping around,
+    /// and returns the tab that should be focused
+    pub fn cycle_tabbed(
+        &mut self,
+        tab_id: Id<TabHandler>,
+        offset: isize,
+    ) -> Option<Id<TabHandler>> {
+        let mut tile_id = self.get_leaf_tile_id(tab_id)?;
+
+        while let Some(container_tile_id) = self.get_parent_tile_id(tile_id) {
+            let container = self.tiles[&container_tile_id].try_as_container().unwrap();
+            if let Some(active) = container.get_active() {
+                let children_len = container.children.len() as isize;
+                let new_active = (active as isize + offset).rem_euclid(children_len) as usize;
+
+                return self.select_tabbed_child(container_tile_id, new_active);
+            }
+
+            tile_id = container_tile_id;
+        }
+
+        None
+    }
+
+    /// Sets how much of the container the child gets, and scales the others to fit.
+    /// Clamped (see `MIN_SPLIT`)
+    pub fn set_share(&mut self, container_tile_id: Id<Tile>, index: usize, share: f32) {
+        let Some(container) = self.get_container_mut(container_tile_id) else {
+            return;
+        };
+        let children_len = container.children.len();
+        if index >= children_len {
+            return;
+        }
+
+        let share = share.clamp(MIN_SPLIT, 1. - MIN_SPLIT * (children_len - 1) as f32);
+        let mut shares = container.shares();
+        let others_total = 1. - shares[index];
+        for (other_index, other_share) in shares.iter_mut().enumerate() {
+            if other_index == index {
+                *other_share = share;
+            } else if others_total > 0. {
+                *other_share *= (1. - share) / others_total;
+            } else {
+                *other_share = (1. - share) / (children_len - 1) as f32;
+            }
+        }
+
+        container.weights = shares;
+    }
+
+    /// Gives the tab's tile `delta` more of the closest split container it is in (or less, if negative)
+    pub fn resize_leaf(&mut self, tab_id: Id<TabHandler>, delta: f32) {
+        let mut tile_id = self.get_leaf_tile_id(tab_id).unwrap();
+
+        // resizing tabs of a tabbed container wouldn't do anything
+        while let Some(container_tile_id) = self.get_parent_tile_id(tile_id) {
+            let container = self.tiles[&container_tile_id].try_as_container().unwrap();
+            if container.get_active().is_none() {
+                let index = container
+                    .children
+                    .iter()
+                    .position(|child_id| child_id == &tile_id)
+                    .unwrap();
+                let share = container.shares()[index];
+
+                self.set_share(container_tile_id, index, share + delta);
+                return;
+            }
+
+            tile_id = container_tile_id;
+        }
+
+        // root takes up everything already
+    }
+
+    /// The container with a boundary within `tolerance` of `point`,
+    /// which boundary it is (`index` is between child `index` and `index + 1`),
+    /// and the area of that container.
+    ///
+    /// Everything is in proportions of the window, including the height of tabbed containers' title strips
+    pub fn find_boundary(
+        &self,
+        point: [f32; 2],
+        tolerance: [f32; 2],
+        title_strip_height: f32,
+    ) -> Option<(Id<Tile>, usize, TileArea)> {
+        self.find_boundary_recursive(
+            self.root_id,
+            [[0., 0.], [1., 1.]],
+            point,
+            tolerance,
+            title_strip_height,
+        )
+    }
+
+    fn find_boundary_recursive(
+        &self,
+        tile_id: Id<Tile>,
+        area: TileArea,
+        point: [f32; 2],
+        tolerance: [f32; 2],
+        title_strip_height: f32,
+    ) -> Option<(Id<Tile>, usize, TileArea)> {
+        let container = self.tiles[&tile_id].try_as_container()?;
+        let [min, max] = area;
+
+        // outside of this container
+        if (0..2).any(|axis| {
+            point[axis] < min[axis] - tolerance[axis] || point[axis] > max[axis] + tolerance[axis]
+        }) {
+            return None;
+        }
+
+        if let Some(active) = container.get_active() {
+            // only the active child is shown, under the title strip
+            let mut child_area = area;
+            child_area[0][1] += title_strip_height;
+
+            return self.find_boundary_recursive(
+                container.children[active],
+                child_area,
+                point,
+                tolerance,
+                title_strip_height,
+            );
+        }
+
+        let axis = container.orientation.axis();
+        let child_areas = container.child_areas(area);
+        for (index, child_area) in child_areas.iter().enumerate().skip(1) {
+            let boundary = child_area[0][axis];
+            if (point[axis] - boundary).abs() <= tolerance[axis] {
+                return Some((tile_id, index - 1, area));
+            }
+        }
+
+        container
+            .children
+            .iter()
+            .zip(child_areas)
+            .find_map(|(child_id, child_area)| {
+                self.find_boundary_recursive(
+                    *child_id,
+                    child_area,
+                    point,
+                    tolerance,
+                    title_strip_height,
+                )
+            })
+    }
+
+    /// Moves the container's boundary to `point` (see `find_boundary`),
+    /// which only changes the two children next to it
+    pub fn drag_boundary(
+        &mut self,
+        container_tile_id: Id<Tile>,
+        index: usize,
+        area: TileArea,
+        point: [f32; 2],
+    ) {
+        let Some(container) = self.get_container_mut(container_tile_id) else {
+            return;
+        };
+        if index + 1 >= container.children.len() {
+            return;
+        }
+
+        let axis = container.orientation.axis();
+        let [min, max] = area;
+        let mut shares = container.shares();
+        let start = min[axis] + shares[..index].iter().sum::<f32>() * (max[axis] - min[axis]);
+        let pair_share = shares[index] + shares[index + 1];
+        if pair_share <= 2. * MIN_SPLIT {
+            return;
+        }
+
+        let share = ((point[axis] - start) / (max[axis] - min[axis]))
+            .clamp(MIN_SPLIT, pair_share - MIN_SPLIT);
+        shares[index] = share;
+        shares[index + 1] = pair_share - share;
+
+        container.weights = shares;
+    }
+
     /// NOTE: currently searches for parent that has the child
     /// REVIEW: optimize by storing the parents
-    pub fn get_parent_tile_id(&mut self, child_tile_id: Id<Tile>) -> Option<Id<Tile>> {
+    pub fn get_parent_tile_id(&self, child_tile_id: Id<Tile>) -> Option<Id<Tile>> {
         self.tiles.iter().find_map(|(parent_id, parent_tile)| {
-            if let Tile::Container { children, .. } = parent_tile {
-                if children.contains(&child_tile_id) {
+            if let Tile::Container(container) = parent_tile {
+                if container.children.contains(&child_tile_id) {
                     Some(*parent_id)
                 } else {
                     None
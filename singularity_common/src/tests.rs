@@ -0,0 +1,1157 @@
+use crate::keymap::{Keymap, KeymapOverride, KeymapOverrides, Shortcut, ShortcutKey};
+use crate::project::{
+    project_settings::{
+        Layered, SettingsLayer, SubappFileSystemPermission, SubappFileSystemPermissions,
+        PROJECT_DIRECTORY_PLACEHOLDER,
+    },
+    schema::{BlocksFile, SchemaError, BLOCKS_SCHEMA, CORE_SCHEMA},
+    user_settings::UserSettings,
+    Project, ProjectError,
+};
+use crate::tab::file_system::{
+    self, DirEntry, FileSystemAccess, FsError, FsRequest, FsResponse, FsResult,
+};
+use crate::tab::overlay::{Overlay, DEFAULT_FLOATING_AREA};
+use crate::tab::packets::{
+    create_query_channels, QueryError, QueryResponse, QueryTypes, Request, TabInfo,
+};
+use crate::tab::tile::{Container, ContainerMode, LayoutPolicy, Orientation, Tiles, MIN_SPLIT};
+use crate::tab::{ManagerHandler, TabCreator, TabHandler};
+use crate::utils::id_map::Id;
+use crate::utils::object_stream::{
+    Encoding, ObjectInputStream, ObjectOutputStream, ObjectStreamError, MAX_OBJECT_LENGTH,
+};
+use crate::utils::tree::{
+    id_tree::IdTree,
+    tree_node_path::{TreeNodePath, TreeTraversal},
+};
+use singularity_ui::{
+    color::Color,
+    display_units::DisplayArea,
+    ui_element::{CharGrid, UIElement},
+    ui_event::{Key, KeyModifiers},
+};
+use std::path::{Path, PathBuf};
+
+#[test]
+fn shortcut_parsing_test() {
+    let shortcut: Shortcut = "Ctrl+Shift+q".parse().unwrap();
+    assert_eq!(shortcut.modifiers, KeyModifiers::CTRL | KeyModifiers::SHIFT);
+    assert_eq!(shortcut.key, ShortcutKey::Char('q'));
+
+    assert_eq!("+".parse::<Shortcut>().unwrap().key, ShortcutKey::Char('+'));
+    assert_eq!(
+        "Alt++".parse::<Shortcut>().unwrap(),
+        Shortcut {
+            modifiers: KeyModifiers::ALT,
+            key: ShortcutKey::Char('+'),
+        }
+    );
+    assert_eq!(
+        "escape".parse::<Shortcut>().unwrap().key,
+        ShortcutKey::RawCode(1)
+    );
+
+    assert!("Hyper+q".parse::<Shortcut>().is_err());
+    assert!("Ctrl+qq".parse::<Shortcut>().is_err());
+
+    // display should parse back into the same shortcut
+    for shortcut_str in ["Ctrl+Q", "Alt+Enter", "Logo+=", "Space", "+", "Alt+Shift+P"] {
+        let shortcut: Shortcut = shortcut_str.parse().unwrap();
+        assert_eq!(shortcut.to_string(), shortcut_str);
+        assert_eq!(shortcut.to_string().parse::<Shortcut>().unwrap(), shortcut);
+    }
+}
+
+#[test]
+fn keymap_serialization_test() {
+    // keys without names still round trip
+    let shortcut: Shortcut = "Ctrl+<200>".parse().unwrap();
+    assert_eq!(shortcut.key, ShortcutKey::RawCode(200));
+    assert_eq!(shortcut.to_string(), "Ctrl+<200>");
+
+    let keymap = Keymap::default();
+    let serialized = serde_json::to_string(&keymap).unwrap();
+    assert_eq!(serde_json::from_str::<Keymap>(&serialized).unwrap(), keymap);
+}
+
+#[test]
+fn keymap_matching_test() {
+    let keymap = Keymap::default();
+
+    // ctrl+s, with num lock on
+    let num_lock = KeyModifiers {
+        num_lock: true,
+        ..KeyModifiers::CTRL
+    };
+    assert!(keymap.is_action("tab.save", &Key::new(31, Some('s')), num_lock));
+    assert!(!keymap.is_action("tab.save", &Key::new(31, Some('s')), KeyModifiers::NONE));
+
+    // "+" is typed with shift on a US layout, but shouldn't need it in the keymap
+    assert_eq!(
+        keymap.get_action(
+            &["task_organizer"],
+            &Key::new(13, Some('+')),
+            KeyModifiers::SHIFT
+        ),
+        Some("task_organizer.add_task")
+    );
+
+    // actions outside of the namespaces aren't returned
+    assert_eq!(
+        keymap.get_action(&["manager"], &Key::new(31, Some('s')), KeyModifiers::CTRL),
+        None
+    );
+
+    assert_eq!(
+        keymap.get_tree_traversal(
+            &Key::new(3, Some('2')),
+            KeyModifiers::ALT,
+            KeyModifiers::ALT
+        ),
+        Some(TreeTraversal::Child(1))
+    );
+    assert_eq!(
+        keymap.get_tree_traversal(
+            &Key::new(3, Some('2')),
+            KeyModifiers::NONE,
+            KeyModifiers::ALT
+        ),
+        None
+    );
+}
+
+#[test]
+fn keymap_override_test() {
+    let mut keymap = Keymap::default();
+
+    let overrides: KeymapOverrides =
+        serde_json::from_str(r#"{ "manager.quit": "Ctrl+Alt+Q", "tree.parent": ["h", "Left"] }"#)
+            .unwrap();
+    assert_eq!(
+        overrides["tree.parent"],
+        KeymapOverride::Multiple(vec!["h".to_string(), "Left".to_string()])
+    );
+    keymap.apply_overrides(&overrides);
+
+    assert!(!keymap.is_action("manager.quit", &Key::new(16, Some('q')), KeyModifiers::CTRL));
+    assert!(keymap.is_action(
+        "manager.quit",
+        &Key::new(16, Some('q')),
+        KeyModifiers::CTRL | KeyModifiers::ALT
+    ));
+
+    assert_eq!(
+        keymap.get_tree_traversal(&Key::new(105, None), KeyModifiers::NONE, KeyModifiers::NONE),
+        Some(TreeTraversal::Parent)
+    );
+    assert_eq!(
+        keymap.get_tree_traversal(
+            &Key::new(30, Some('a')),
+            KeyModifiers::NONE,
+            KeyModifiers::NONE
+        ),
+        None
+    );
+}
+
+#[test]
+fn keymap_user_config_test() {
+    let test_directory =
+        std::env::temp_dir().join(format!("singularity_keymap_test_{}", std::process::id()));
+    std::fs::create_dir_all(&test_directory).unwrap();
+    let user_config_path = test_directory.join("keymap.json");
+    std::fs::write(
+        &user_config_path,
+        r#"{ "manager.quit": "Ctrl+Alt+Q", "manager.close_tab": "Ctrl+Alt+W" }"#,
+    )
+    .unwrap();
+    let quit = Key::new(16, Some('q'));
+    let close_tab = Key::new(17, Some('w'));
+
+    // the project goes on top of the user
+    let project_overrides: KeymapOverrides =
+        serde_json::from_str(r#"{ "manager.quit": "Ctrl+Shift+Q" }"#).unwrap();
+    let keymap = Keymap::load_with_user_config(Some(&user_config_path), &project_overrides);
+    assert!(keymap.is_action(
+        "manager.quit",
+        &quit,
+        KeyModifiers::CTRL | KeyModifiers::SHIFT
+    ));
+    assert!(keymap.is_action(
+        "manager.close_tab",
+        &close_tab,
+        KeyModifiers::CTRL | KeyModifiers::ALT
+    ));
+
+    // a missing (or broken) user config is the defaults
+    let keymap = Keymap::load_with_user_config(
+        Some(&test_directory.join("missing.json")),
+        &KeymapOverrides::default(),
+    );
+    assert!(keymap.is_action("manager.quit", &quit, KeyModifiers::CTRL));
+    std::fs::write(&user_config_path, "{ oops").unwrap();
+    let keymap =
+        Keymap::load_with_user_config(Some(&user_config_path), &KeymapOverrides::default());
+    assert!(keymap.is_action("manager.quit", &quit, KeyModifiers::CTRL));
+
+    std::fs::remove_dir_all(&test_directory).unwrap();
+}
+
+#[test]
+fn object_stream_large_frame_test() {
+    // way past the old u16 length
+    let ui_element =
+        UIElement::CharGrid(CharGrid::from("singularity\n".repeat(20_000))).bordered(Color::RED);
+
+    for encoding in [Encoding::Json, Encoding::MessagePack] {
+        let mut stream = Vec::new();
+        stream.write_handshake(encoding).unwrap();
+        stream.write_encoded_object(&ui_element, encoding).unwrap();
+        stream.write_encoded_object(&"after", encoding).unwrap();
+        assert!(stream.len() > u16::MAX as usize);
+
+        let mut reader = stream.as_slice();
+        let handshake = reader.read_handshake().unwrap();
+        assert_eq!(handshake.encoding, encoding);
+        assert_eq!(
+            reader
+                .read_encoded_object::<UIElement>(handshake.encoding)
+                .unwrap(),
+            ui_element
+        );
+        assert_eq!(
+            reader
+                .read_encoded_object::<String>(handshake.encoding)
+                .unwrap(),
+            "after"
+        );
+
+        // nothing left is a clean disconnect
+        assert!(matches!(
+            reader.read_encoded_object::<String>(handshake.encoding),
+            Err(ObjectStreamError::Disconnected)
+        ));
+    }
+}
+
+#[test]
+fn object_stream_broken_stream_test() {
+    let mut stream = Vec::new();
+    stream.write_object(&"cut off").unwrap();
+
+    // the peer died in the middle of an object
+    let mut reader = &stream[..stream.len() - 2];
+    assert!(matches!(
+        reader.read_object::<String>(),
+        Err(ObjectStreamError::Io(_))
+    ));
+
+    // or in the middle of the length
+    let mut reader = &stream[..2];
+    assert!(matches!(
+        reader.read_object::<String>(),
+        Err(ObjectStreamError::Io(_))
+    ));
+
+    // not an object stream at all
+    let mut reader = "hello world".as_bytes();
+    assert!(matches!(
+        reader.read_object::<String>(),
+        Err(ObjectStreamError::TooLarge(length)) if length > MAX_OBJECT_LENGTH
+    ));
+
+    // the wrong type
+    let mut reader = stream.as_slice();
+    assert!(matches!(
+        reader.read_object::<u32>(),
+        Err(ObjectStreamError::Deserialize(_))
+    ));
+
+    // an older (or newer) version
+    let mut stream = Vec::new();
+    stream
+        .write_object(&serde_json::json!({ "version": 1, "encoding": "Json" }))
+        .unwrap();
+    assert!(matches!(
+        stream.as_slice().read_handshake(),
+        Err(ObjectStreamError::VersionMismatch { theirs: 1, .. })
+    ));
+}
+
+/// root -> [a -> [a1, a2], b]
+fn example_id_tree() -> (IdTree<()>, [Id<()>; 5]) {
+    let root = Id::generate();
+    let mut id_tree = IdTree::new(root);
+    let a = id_tree.create_child(root).unwrap();
+    let b = id_tree.create_child(root).unwrap();
+    let a1 = id_tree.create_child(a).unwrap();
+    let a2 = id_tree.create_child(a).unwrap();
+
+    (id_tree, [root, a, b, a1, a2])
+}
+
+#[test]
+fn id_tree_reattach_children_test() {
+    let (mut id_tree, [root, a, b, a1, a2]) = example_id_tree();
+
+    // a's children go where a was, before b
+    id_tree.remove_and_reattach_children(&a).unwrap();
+    assert_eq!(id_tree.get_children(&root), &vec![a1, a2, b]);
+    assert_eq!(id_tree.get_parent(&a1), Some(root));
+    assert_eq!(id_tree.get_parent(&a2), Some(root));
+    assert_eq!(id_tree.get_parent(&a), None);
+    assert_eq!(id_tree.get_path(b).unwrap(), vec![2].into());
+
+    // root has nowhere to reattach to
+    assert!(id_tree.remove_and_reattach_children(&root).is_none());
+    assert_eq!(id_tree.get_root_id(), root);
+}
+
+#[test]
+fn id_tree_promote_first_child_test() {
+    let (mut id_tree, [root, a, b, a1, a2]) = example_id_tree();
+
+    // a1 takes a's place, and a2 becomes its child
+    assert_eq!(id_tree.remove_and_promote_first_child(&a), Some(a1));
+    assert_eq!(id_tree.get_children(&root), &vec![a1, b]);
+    assert_eq!(id_tree.get_children(&a1), &vec![a2]);
+    assert_eq!(id_tree.get_parent(&a2), Some(a1));
+    assert_eq!(id_tree.get_parent(&a1), Some(root));
+
+    // nothing to promote
+    assert!(id_tree.remove_and_promote_first_child(&b).is_none());
+
+    // root can be replaced too
+    assert_eq!(id_tree.remove_and_promote_first_child(&root), Some(a1));
+    assert_eq!(id_tree.get_root_id(), a1);
+    assert_eq!(id_tree.get_parent(&a1), None);
+    assert_eq!(id_tree.get_children(&a1), &vec![a2, b]);
+    assert_eq!(id_tree.get_path(b).unwrap(), vec![1].into());
+}
+
+#[test]
+fn tile_split_resizing_test() {
+    let [first_tab, second_tab] = [Id::generate(), Id::generate()];
+    let mut tiles = Tiles::new_from_root(first_tab);
+    // horizontal, so the boundary is at y = 0.5
+    tiles.give_sibling(first_tab, second_tab);
+    let root_tile = tiles.get_root_tile();
+    let split = |tiles: &Tiles| {
+        tiles
+            .get_tile(root_tile)
+            .unwrap()
+            .try_as_container()
+            .unwrap()
+            .shares()[0]
+    };
+
+    // growing the second tab moves the boundary up
+    tiles.resize_leaf(second_tab, 0.1);
+    assert!((split(&tiles) - 0.4).abs() < 1e-5);
+    tiles.resize_leaf(first_tab, 10.);
+    assert_eq!(split(&tiles), 1. - MIN_SPLIT);
+
+    tiles.set_share(root_tile, 0, 0.5);
+    assert!(tiles.find_boundary([0.3, 0.3], [0.01, 0.01], 0.).is_none());
+    let (container, index, area) = tiles.find_boundary([0.3, 0.505], [0.01, 0.01], 0.).unwrap();
+    assert_eq!(container, root_tile);
+    assert_eq!(index, 0);
+    assert_eq!(area, [[0., 0.], [1., 1.]]);
+
+    tiles.drag_boundary(container, index, area, [0.9, 0.25]);
+    assert!((split(&tiles) - 0.25).abs() < 1e-5);
+    tiles.drag_boundary(container, index, area, [0.9, -3.]);
+    assert_eq!(split(&tiles), MIN_SPLIT);
+}
+
+#[test]
+fn tile_tabbed_containers_test() {
+    let [a, b, c] = [Id::generate(), Id::generate(), Id::generate()];
+    let mut tiles = Tiles::new_from_root(a);
+    tiles.give_sibling(a, b);
+    let root_tile = tiles.get_root_tile();
+    let root_container = |tiles: &Tiles| {
+        tiles
+            .get_tile(root_tile)
+            .unwrap()
+            .try_as_container()
+            .unwrap()
+            .clone()
+    };
+
+    // new tabs go into tabbed containers, instead of splitting
+    tiles.toggle_tabbed(b);
+    tiles.give_sibling(b, c);
+    assert_eq!(root_container(&tiles).children.len(), 3);
+    assert_eq!(
+        root_container(&tiles).mode,
+        ContainerMode::Tabbed { active: 2 }
+    );
+    assert!(tiles.is_leaf_visible(c));
+    assert!(!tiles.is_leaf_visible(a));
+
+    // wraps around
+    assert_eq!(tiles.cycle_tabbed(c, 1), Some(a));
+    assert!(tiles.is_leaf_visible(a));
+    tiles.show_leaf(b);
+    assert_eq!(
+        root_container(&tiles).mode,
+        ContainerMode::Tabbed { active: 1 }
+    );
+
+    // back to split, as 3 equal rows
+    tiles.toggle_tabbed(b);
+    assert!(tiles.is_leaf_visible(a) && tiles.is_leaf_visible(c));
+    let (container, index, area) = tiles
+        .find_boundary([0.5, 2. / 3.], [0.01, 0.01], 0.)
+        .unwrap();
+    assert_eq!((container, index), (root_tile, 1));
+    // only the 2 rows next to the boundary change
+    tiles.drag_boundary(container, index, area, [0.5, 0.5]);
+    let shares = root_container(&tiles).shares();
+    assert!((shares[0] - 1. / 3.).abs() < 1e-5);
+    assert!((shares[1] - 1. / 6.).abs() < 1e-5);
+    assert!((shares[2] - 1. / 2.).abs() < 1e-5);
+
+    tiles.set_share(root_tile, 2, 0.8);
+    let shares = root_container(&tiles).shares();
+    assert!((shares.iter().sum::<f32>() - 1.).abs() < 1e-5);
+    assert!((shares[2] - 0.8).abs() < 1e-5);
+
+    // removing down to one tab gets rid of the container
+    tiles.remove(a);
+    assert_eq!(root_container(&tiles).children.len(), 2);
+    tiles.remove(b);
+    assert_eq!(tiles.get_tile(root_tile).unwrap().try_as_tab(), Some(c));
+}
+
+#[test]
+fn tile_old_container_format_test() {
+    // sessions from when containers were binary
+    let container: Container = serde_json::from_value(serde_json::json!({
+        "children": [uuid::Uuid::new_v4(), uuid::Uuid::new_v4()],
+        "orientation": "Vertical",
+        "split": 0.3,
+    }))
+    .unwrap();
+
+    assert_eq!(container.weights, vec![0.3, 0.7]);
+    assert_eq!(container.mode, ContainerMode::Split);
+}
+
+#[test]
+fn tile_layout_policies_test() {
+    let ids: Vec<_> = (0..5).map(|_| Id::generate()).collect();
+    let mut tiles = Tiles::new_from_root(ids[0]);
+    let container = |tiles: &Tiles, tile_id| {
+        tiles
+            .get_tile(tile_id)
+            .unwrap()
+            .try_as_container()
+            .unwrap()
+            .clone()
+    };
+
+    // master on the left, the rest stacked on the right
+    for pair in ids.windows(2) {
+        tiles.add_tab(pair[0], pair[1], LayoutPolicy::MasterStack);
+    }
+    assert_eq!(tiles.collect_leaves(), ids);
+    let root = container(&tiles, tiles.get_root_tile());
+    assert_eq!(root.orientation, Orientation::Vertical);
+    let stack = container(&tiles, root.children[1]);
+    assert_eq!(stack.orientation, Orientation::Horizontal);
+    assert_eq!(stack.children.len(), 4);
+
+    // 5 tabs is 2 rows of (up to) 3
+    tiles.relayout(LayoutPolicy::Grid, ids[0]);
+    assert_eq!(tiles.collect_leaves(), ids);
+    let root = container(&tiles, tiles.get_root_tile());
+    assert_eq!(root.orientation, Orientation::Horizontal);
+    assert_eq!(container(&tiles, root.children[0]).children.len(), 3);
+    assert_eq!(container(&tiles, root.children[1]).children.len(), 2);
+
+    // alternates directions
+    tiles.relayout(LayoutPolicy::Spiral, ids[0]);
+    assert_eq!(tiles.collect_leaves(), ids);
+    let root = container(&tiles, tiles.get_root_tile());
+    assert_eq!(root.orientation, Orientation::Vertical);
+    let inner = container(&tiles, root.children[1]);
+    assert_eq!(inner.orientation, Orientation::Horizontal);
+    assert_eq!(
+        container(&tiles, inner.children[1]).orientation,
+        Orientation::Vertical
+    );
+
+    // everything in one tabbed container, still showing the focused tab
+    tiles.relayout(LayoutPolicy::ReplaceFocused, ids[3]);
+    let root = container(&tiles, tiles.get_root_tile());
+    assert_eq!(root.children.len(), 5);
+    assert_eq!(root.mode, ContainerMode::Tabbed { active: 3 });
+
+    // new tabs go on top of a split tile
+    let mut tiles = Tiles::new_from_root(ids[0]);
+    tiles.add_tab(ids[0], ids[1], LayoutPolicy::Spiral);
+    tiles.add_tab(ids[1], ids[2], LayoutPolicy::ReplaceFocused);
+    assert!(tiles.is_leaf_visible(ids[0]) && tiles.is_leaf_visible(ids[2]));
+    assert!(!tiles.is_leaf_visible(ids[1]));
+    let root = container(&tiles, tiles.get_root_tile());
+    assert_eq!(
+        container(&tiles, root.children[1]).mode,
+        ContainerMode::Tabbed { active: 1 }
+    );
+}
+
+#[test]
+fn overlay_test() {
+    let [a, b] = [Id::generate(), Id::generate()];
+    let mut overlay = Overlay::default();
+    assert!(overlay.is_empty());
+
+    overlay.float(a, DEFAULT_FLOATING_AREA);
+    overlay.float(b, DEFAULT_FLOATING_AREA);
+    overlay.raise(a);
+    assert_eq!(overlay.get_floating().last().unwrap().tab_id, a);
+
+    overlay.move_floating(a, [0.25, 0.]);
+    assert_eq!(
+        overlay.get_floating_area(a),
+        Some(DisplayArea::new_proportional([[0.5, 0.25], [1., 0.75]]))
+    );
+
+    // minimizing the maximized tab unmaximizes it
+    overlay.set_maximized(Some(b));
+    let floating_area = overlay.unfloat(b);
+    overlay.minimize(b, floating_area);
+    assert!(overlay.is_minimized(b));
+    assert_eq!(overlay.get_maximized(), None);
+    assert_eq!(
+        overlay.unminimize(b).unwrap().floating_area,
+        Some(DEFAULT_FLOATING_AREA)
+    );
+
+    overlay.remove(a);
+    assert!(overlay.is_empty());
+}
+
+#[test]
+fn layered_settings_test() {
+    let project_directory =
+        std::env::temp_dir().join(format!("singularity_settings_test_{}", std::process::id()));
+    std::fs::create_dir_all(project_directory.join(".project")).unwrap();
+    std::fs::write(
+        project_directory.join(".project/core.json"),
+        r#"{
+            "subapps": {
+                "task_organizer": {
+                    "subapp_specific_settings": { "debug_border": false }
+                }
+            },
+            "open_tabs": null
+        }"#,
+    )
+    .unwrap();
+
+    let user_settings: UserSettings = serde_json::from_str(
+        r#"{
+            "subapps": {
+                "task_organizer": {
+                    "subapp_standard_settings": {
+                        "subapp_file_system_permissions": { "property": null }
+                    },
+                    "subapp_specific_settings": { "debug_border": true, "font_size": 12 }
+                },
+                "time_manager": {
+                    "subapp_specific_settings": { "week_start": "monday" }
+                }
+            }
+        }"#,
+    )
+    .unwrap();
+
+    let project = Project::new_with_user_settings(project_directory.clone(), &user_settings);
+    std::fs::remove_dir_all(&project_directory).unwrap();
+
+    // the project's value wins, the rest comes from the user
+    let task_organizer = project.get_subapp_settings("task_organizer");
+    assert_eq!(
+        task_organizer.subapp_specific_settings.get("debug_border"),
+        Some(&Layered {
+            value: serde_json::json!(false),
+            layer: SettingsLayer::Project,
+        })
+    );
+    assert_eq!(
+        task_organizer.subapp_specific_settings.get("font_size"),
+        Some(&Layered {
+            value: serde_json::json!(12),
+            layer: SettingsLayer::User,
+        })
+    );
+    assert_eq!(
+        task_organizer
+            .subapp_file_system_permissions
+            .map(|permissions| permissions.layer),
+        Some(SettingsLayer::User)
+    );
+
+    // only the user has settings for it
+    assert_eq!(
+        project
+            .get_subapp_settings("time_manager")
+            .get_specific_setting("week_start"),
+        Some(&serde_json::json!("monday"))
+    );
+
+    // neither has any
+    assert_eq!(project.get_subapp_settings("missing"), Default::default());
+}
+
+#[test]
+fn project_init_test() {
+    let test_directory =
+        std::env::temp_dir().join(format!("singularity_init_test_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&test_directory);
+
+    // any directory becomes a project the first time it's opened
+    let project_directory = test_directory.join("plain");
+    std::fs::create_dir_all(&project_directory).unwrap();
+    assert!(!Project::is_project(&project_directory));
+    let project =
+        Project::new_with_user_settings(project_directory.clone(), &UserSettings::default());
+    assert!(Project::is_project(&project_directory));
+    assert!(project.session.is_none());
+    let tasks: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(project_directory.join(".project/tasks.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(tasks["children"], serde_json::json!([]));
+    let blocks: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(project_directory.join(".project/blocks.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(blocks["blocks"], serde_json::json!([]));
+    // the standard tabs can use their own files, but nothing else is allowed without asking
+    let task_organizer_permissions = project
+        .get_subapp_settings("task_organizer")
+        .subapp_file_system_permissions
+        .unwrap()
+        .value;
+    assert!(task_organizer_permissions.allows(
+        &project_directory.join(".project/tasks.json"),
+        FileSystemAccess::Write,
+        &project_directory
+    ));
+    assert!(!task_organizer_permissions.allows(
+        &project_directory.join("notes.txt"),
+        FileSystemAccess::Read,
+        &project_directory
+    ));
+    assert!(project
+        .get_subapp_settings("some_other_tab")
+        .subapp_file_system_permissions
+        .is_none());
+    // the session isn't for git
+    assert!(
+        std::fs::read_to_string(project_directory.join(".project/.gitignore"))
+            .unwrap()
+            .contains("session.json")
+    );
+
+    // templates are copied, and the rest is filled in
+    let template_directory = test_directory.join("template");
+    std::fs::create_dir_all(template_directory.join(".project")).unwrap();
+    std::fs::create_dir_all(template_directory.join("notes")).unwrap();
+    std::fs::write(
+        template_directory.join("notes/todo.txt"),
+        "from the template",
+    )
+    .unwrap();
+    std::fs::write(
+        template_directory.join(".project/core.json"),
+        r#"{ "subapps": {}, "layout_policy": "Grid", "open_tabs": null }"#,
+    )
+    .unwrap();
+
+    let project_directory = test_directory.join("from_template");
+    Project::init_with_template_directory(&project_directory, Some(&template_directory)).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(project_directory.join("notes/todo.txt")).unwrap(),
+        "from the template"
+    );
+    assert!(project_directory.join(".project/tasks.json").is_file());
+    let project =
+        Project::new_with_user_settings(project_directory.clone(), &UserSettings::default());
+    assert_eq!(
+        project.get_project_settings().layout_policy,
+        Some(LayoutPolicy::Grid)
+    );
+
+    // initializing again doesn't overwrite anything
+    std::fs::write(project_directory.join(".project/blocks.json"), "[ ]").unwrap();
+    Project::init_with_template_directory(&project_directory, None).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(project_directory.join(".project/blocks.json")).unwrap(),
+        "[ ]"
+    );
+
+    assert!(Project::init(&project_directory, Some("no such template")).is_err());
+
+    // but paths that don't exist aren't made into projects by opening them
+    let missing_directory = test_directory.join("missing");
+    assert!(Project::init_if_new(&missing_directory).is_err());
+    assert!(!missing_directory.exists());
+    assert!(matches!(
+        Project::try_new_with_user_settings(missing_directory.clone(), &UserSettings::default()),
+        Err(ProjectError::Init(_))
+    ));
+
+    // a session from a newer build isn't replaced with a new one
+    let session_path = project_directory.join(".project/session.json");
+    let newer_session = format!(
+        r#"{{ "schema_version": {} }}"#,
+        crate::project::schema::SESSION_SCHEMA.current_version() + 1
+    );
+    std::fs::write(&session_path, &newer_session).unwrap();
+    assert!(matches!(
+        Project::try_new_with_user_settings(project_directory.clone(), &UserSettings::default()),
+        Err(ProjectError::Session(SchemaError::TooNew { .. }))
+    ));
+    assert_eq!(
+        std::fs::read_to_string(&session_path).unwrap(),
+        newer_session
+    );
+    std::fs::remove_file(&session_path).unwrap();
+
+    // a broken core file is reported instead of panicking, and left alone
+    std::fs::write(project_directory.join(".project/core.json"), "{ oops").unwrap();
+    assert!(matches!(
+        Project::try_new_with_user_settings(project_directory.clone(), &UserSettings::default()),
+        Err(ProjectError::Settings(SchemaError::Json(_)))
+    ));
+    assert_eq!(
+        std::fs::read_to_string(project_directory.join(".project/core.json")).unwrap(),
+        "{ oops"
+    );
+
+    std::fs::remove_dir_all(&test_directory).unwrap();
+}
+
+#[test]
+fn schema_migration_test() {
+    let test_directory =
+        std::env::temp_dir().join(format!("singularity_schema_test_{}", std::process::id()));
+    std::fs::create_dir_all(&test_directory).unwrap();
+
+    // from before versions: just the list of blocks
+    let blocks_path = test_directory.join("blocks.json");
+    std::fs::write(&blocks_path, "[1, 2, 3]").unwrap();
+
+    let blocks_file: BlocksFile<u32> = BLOCKS_SCHEMA.load(&blocks_path).unwrap();
+    assert_eq!(blocks_file.blocks, vec![1, 2, 3]);
+    // the original is kept, and the file is upgraded
+    assert_eq!(
+        std::fs::read_to_string(test_directory.join("blocks.json.v0.backup")).unwrap(),
+        "[1, 2, 3]"
+    );
+    let upgraded: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&blocks_path).unwrap()).unwrap();
+    assert_eq!(
+        upgraded,
+        serde_json::json!({ "schema_version": BLOCKS_SCHEMA.current_version(), "blocks": [1, 2, 3] })
+    );
+    // already current, so loading again changes nothing
+    let blocks_file: BlocksFile<u32> = BLOCKS_SCHEMA.load(&blocks_path).unwrap();
+    assert_eq!(blocks_file.blocks, vec![1, 2, 3]);
+
+    // from a newer build
+    let core_path = test_directory.join("core.json");
+    let newer_version = CORE_SCHEMA.current_version() + 1;
+    std::fs::write(
+        &core_path,
+        format!(r#"{{ "schema_version": {newer_version}, "subapps": {{}}, "open_tabs": null }}"#),
+    )
+    .unwrap();
+    let err = CORE_SCHEMA
+        .load::<crate::project::project_settings::ProjectSettings>(&core_path)
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        SchemaError::TooNew { version, .. } if version == newer_version
+    ));
+    assert!(err.to_string().contains("newer version"));
+
+    std::fs::remove_dir_all(&test_directory).unwrap();
+}
+
+#[test]
+fn file_system_permissions_test() {
+    let project_directory = Path::new("/home/user/project");
+
+    // by default, nothing (the user is asked instead)
+    let read = FileSystemAccess::Read;
+    assert!(!SubappFileSystemPermissions::default().allows(
+        &project_directory.join("notes.txt"),
+        read,
+        project_directory
+    ));
+
+    // only the project (and not anything that just starts with the same name)
+    let permissions = SubappFileSystemPermissions::new(Some(SubappFileSystemPermission::new(
+        PROJECT_DIRECTORY_PLACEHOLDER,
+        true,
+        true,
+        false,
+    )));
+    assert!(permissions.allows(
+        &project_directory.join("notes.txt"),
+        read,
+        project_directory
+    ));
+    assert!(permissions.allows(
+        &project_directory.join(".project/tasks.json"),
+        FileSystemAccess::Write,
+        project_directory
+    ));
+    assert!(!permissions.allows(
+        &project_directory.join("run.sh"),
+        FileSystemAccess::Execute,
+        project_directory
+    ));
+    assert!(!permissions.allows(Path::new("/home/user/project2/a"), read, project_directory));
+    // `..` can't be used to get out
+    assert!(!permissions.allows(
+        &project_directory.join("../secrets.txt"),
+        read,
+        project_directory
+    ));
+
+    let permission = SubappFileSystemPermission::new(
+        format!("{PROJECT_DIRECTORY_PLACEHOLDER}/../shared/./docs"),
+        true,
+        false,
+        false,
+    );
+    assert_eq!(
+        permission.expand_location(project_directory),
+        PathBuf::from("/home/user/shared/docs")
+    );
+    let permissions = SubappFileSystemPermissions::new(Some(permission));
+    assert!(permissions.allows(
+        Path::new("/home/user/shared/docs/a.md"),
+        read,
+        project_directory
+    ));
+    assert!(!permissions.allows(
+        Path::new("/home/user/shared/docs/a.md"),
+        FileSystemAccess::Write,
+        project_directory
+    ));
+    assert!(!permissions.allows(&project_directory.join("a.md"), read, project_directory));
+
+    assert!(!SubappFileSystemPermissions::new(None).allows(
+        &project_directory.join("a.md"),
+        read,
+        project_directory
+    ));
+}
+
+#[cfg(unix)]
+#[test]
+fn file_system_permissions_symlink_test() {
+    let test_directory =
+        std::env::temp_dir().join(format!("singularity_symlink_test_{}", std::process::id()));
+    let project_directory = test_directory.join("project");
+    let outside_directory = test_directory.join("outside");
+    std::fs::create_dir_all(&project_directory).unwrap();
+    std::fs::create_dir_all(&outside_directory).unwrap();
+    std::fs::write(outside_directory.join("secrets.txt"), "secret").unwrap();
+    std::os::unix::fs::symlink(&outside_directory, project_directory.join("link")).unwrap();
+    std::os::unix::fs::symlink(&project_directory, test_directory.join("project_alias")).unwrap();
+
+    let permissions = SubappFileSystemPermissions::new(Some(SubappFileSystemPermission::new(
+        PROJECT_DIRECTORY_PLACEHOLDER,
+        true,
+        true,
+        false,
+    )));
+    let read = FileSystemAccess::Read;
+    // the symlink leads out of the project
+    assert!(!permissions.allows(
+        &project_directory.join("link/secrets.txt"),
+        read,
+        &project_directory
+    ));
+    // even for files that don't exist yet
+    assert!(!permissions.allows(
+        &project_directory.join("link/new/file.txt"),
+        FileSystemAccess::Write,
+        &project_directory
+    ));
+    assert!(permissions.allows(
+        &project_directory.join("new/file.txt"),
+        FileSystemAccess::Write,
+        &project_directory
+    ));
+    // the same project, through another path
+    assert!(permissions.allows(
+        &test_directory.join("project_alias/notes.txt"),
+        read,
+        &project_directory
+    ));
+    assert!(permissions.allows(
+        &project_directory.join("notes.txt"),
+        read,
+        &test_directory.join("project_alias")
+    ));
+
+    std::fs::remove_dir_all(&test_directory).unwrap();
+}
+
+#[test]
+fn file_system_perform_test() {
+    let test_directory =
+        std::env::temp_dir().join(format!("singularity_fs_test_{}", std::process::id()));
+    std::fs::create_dir_all(test_directory.join("b_directory")).unwrap();
+
+    let file_path = test_directory.join("a.txt");
+    assert_eq!(
+        file_system::perform(&FsRequest::Read(file_path.clone())),
+        Err(FsError::NotFound(file_path.clone()))
+    );
+    assert_eq!(
+        file_system::perform(&FsRequest::Write(file_path.clone(), b"hello".to_vec())),
+        Ok(FsResponse::Written)
+    );
+    assert_eq!(
+        file_system::perform(&FsRequest::Read(file_path.clone())),
+        Ok(FsResponse::Read(b"hello".to_vec()))
+    );
+    assert_eq!(
+        file_system::perform(&FsRequest::ListDir(test_directory.clone())),
+        Ok(FsResponse::DirEntries(vec![
+            DirEntry {
+                path: file_path,
+                is_dir: false
+            },
+            DirEntry {
+                path: test_directory.join("b_directory"),
+                is_dir: true
+            },
+        ]))
+    );
+
+    std::fs::remove_dir_all(&test_directory).unwrap();
+}
+
+#[test]
+fn query_arguments_test() {
+    let manager_wakeup = singularity_ui::wakeup::Wakeup::new();
+    let (query_channels, respond_channels) = create_query_channels(
+        manager_wakeup.clone(),
+        singularity_ui::wakeup::Wakeup::new(),
+    );
+    let tab_data = crate::project::project_settings::TabData {
+        tab_type: "PLAIN".to_string(),
+        session_data: serde_json::Value::Null,
+    };
+    let child_path = TreeNodePath::from([0]);
+
+    // like the manager, answering whatever comes in
+    let tab_thread = std::thread::spawn(move || {
+        let tab_data_at = query_channels
+            .ask_tab_data_at(TreeNodePath::from([0]))
+            .unwrap();
+        let missing = query_channels
+            .ask_tab_data_at(TreeNodePath::from([3]))
+            .unwrap();
+        let project_directory = query_channels.ask_project_directory().unwrap();
+        // (how tabs in other processes ask)
+        let focused_tab = query_channels.ask(QueryTypes::FocusedTab).unwrap();
+        (tab_data_at, missing, project_directory, focused_tab)
+    });
+    while !tab_thread.is_finished() {
+        respond_channels.answer_queries(
+            1,
+            TreeNodePath::new_root,
+            || "Plain".to_string(),
+            || tab_data.clone(),
+            Keymap::default,
+            Default::default,
+            || PathBuf::from("/home/user/project"),
+            Vec::new,
+            || TabInfo {
+                org_path: child_path.clone(),
+                tab_name: "Plain".to_string(),
+                tab_type: "PLAIN".to_string(),
+            },
+            |org_path| {
+                if org_path == child_path {
+                    Ok(tab_data.clone())
+                } else {
+                    Err(QueryError::NoSuchTab(org_path))
+                }
+            },
+        );
+        manager_wakeup.wait_timeout(std::time::Duration::from_millis(10));
+    }
+    let (tab_data_at, missing, project_directory, focused_tab) = tab_thread.join().unwrap();
+
+    assert_eq!(tab_data_at, Ok(tab_data));
+    assert_eq!(missing, Err(QueryError::NoSuchTab(TreeNodePath::from([3]))));
+    assert_eq!(project_directory, PathBuf::from("/home/user/project"));
+    assert!(matches!(
+        focused_tab,
+        QueryResponse::FocusedTab(TabInfo { org_path, .. }) if org_path == TreeNodePath::from([0])
+    ));
+
+    // arguments go between processes too
+    let query: QueryTypes = serde_json::from_str(
+        &serde_json::to_string(&QueryTypes::TabDataAt(TreeNodePath::from([1, 2]))).unwrap(),
+    )
+    .unwrap();
+    assert!(
+        matches!(query, QueryTypes::TabDataAt(org_path) if org_path == TreeNodePath::from([1, 2]))
+    );
+}
+
+#[test]
+fn query_timeout_test() {
+    let (query_channels, respond_channels) = create_query_channels(
+        singularity_ui::wakeup::Wakeup::new(),
+        singularity_ui::wakeup::Wakeup::new(),
+    );
+    let answer_two = |respond_channels: &crate::tab::packets::RespondChannels| {
+        respond_channels.answer_queries(
+            2,
+            || unreachable!(),
+            || "Plain".to_string(),
+            || unreachable!(),
+            Keymap::default,
+            || unreachable!(),
+            || PathBuf::from("/home/user/project"),
+            || unreachable!(),
+            || unreachable!(),
+            |_| unreachable!(),
+        )
+    };
+
+    // several at once, without waiting
+    let tab_name = query_channels.send_tab_name();
+    let project_directory = query_channels.send_project_directory();
+    assert!(tab_name.try_take(&query_channels).is_none());
+    // no one is answering yet
+    let keymap = query_channels.send_keymap();
+    let keymap_id = keymap.query_id;
+    assert_eq!(
+        keymap
+            .wait(&query_channels, Some(std::time::Duration::from_millis(10)))
+            .unwrap_err(),
+        QueryError::TimedOut
+    );
+
+    // only up to the limit, and the answers can be taken in any order
+    assert!(answer_two(&respond_channels));
+    assert_eq!(
+        project_directory.wait(&query_channels, Some(std::time::Duration::ZERO)),
+        Ok(PathBuf::from("/home/user/project"))
+    );
+    assert_eq!(
+        tab_name.try_take(&query_channels),
+        Some("Plain".to_string())
+    );
+    // the keymap that timed out, whose answer is thrown away
+    assert!(!answer_two(&respond_channels));
+    assert!(query_channels.try_take_response(keymap_id).is_none());
+
+    // stopping right at the limit isn't "more left"
+    let tab_name = query_channels.send_tab_name();
+    let project_directory = query_channels.send_project_directory();
+    assert!(!answer_two(&respond_channels));
+    assert!(tab_name.try_take(&query_channels).is_some());
+    assert!(project_directory.try_take(&query_channels).is_some());
+
+    // eg: the manager is shutting down
+    drop(respond_channels);
+    assert_eq!(
+        query_channels.ask_tab_name_timeout(std::time::Duration::from_secs(10)),
+        Err(QueryError::ManagerGone)
+    );
+}
+
+#[test]
+fn file_system_watch_test() {
+    let test_directory =
+        std::env::temp_dir().join(format!("singularity_watch_test_{}", std::process::id()));
+    std::fs::create_dir_all(&test_directory).unwrap();
+    let file_path = test_directory.join("watched.txt");
+
+    let (event_tx, event_rx) = std::sync::mpsc::channel();
+    let watch_handle = std::sync::Arc::new(());
+    file_system::watch(
+        file_path.clone(),
+        event_tx,
+        singularity_ui::wakeup::Wakeup::new(),
+        std::sync::Arc::downgrade(&watch_handle),
+    );
+
+    // (the watcher checks the file when it starts)
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    std::fs::write(&file_path, "changed").unwrap();
+    assert!(matches!(
+        event_rx.recv_timeout(std::time::Duration::from_secs(5)),
+        Ok(crate::tab::packets::Event::FileChanged(path)) if path == file_path
+    ));
+
+    // the thread stops (and drops its sender) without the file changing again
+    drop(watch_handle);
+    assert!(matches!(
+        event_rx.recv_timeout(std::time::Duration::from_secs(5)),
+        Err(std::sync::mpsc::RecvTimeoutError::Disconnected)
+    ));
+
+    std::fs::remove_dir_all(&test_directory).unwrap();
+}
+
+#[test]
+fn file_system_manager_gone_test() {
+    /// Reads a file once it's told to, and sends back what it got
+    struct ReadingTab {
+        go_rx: std::sync::mpsc::Receiver<()>,
+        result_tx: std::sync::mpsc::Sender<FsResult>,
+    }
+    impl TabCreator for ReadingTab {
+        fn create_tab(&mut self, manager_handler: ManagerHandler) {
+            self.go_rx.recv().unwrap();
+            let fs_result = manager_handler.request_fs(FsRequest::Read(PathBuf::from("a.txt")));
+            self.result_tx.send(fs_result).unwrap();
+        }
+    }
+
+    let (go_tx, go_rx) = std::sync::mpsc::channel();
+    let (result_tx, result_rx) = std::sync::mpsc::channel();
+    let tab_handler = TabHandler::new(
+        ReadingTab { go_rx, result_tx },
+        crate::project::project_settings::TabData {
+            tab_type: "READING".to_string(),
+            session_data: serde_json::Value::Null,
+        },
+        DisplayArea::FULL,
+        singularity_ui::wakeup::Wakeup::new(),
+    );
+
+    // the manager goes away while the tab is waiting for the answer
+    go_tx.send(()).unwrap();
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while !tab_handler
+        .collect_requests()
+        .iter()
+        .any(|request| matches!(request, Request::FileSystem(_)))
+    {
+        assert!(std::time::Instant::now() < deadline);
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+    drop(tab_handler);
+    assert_eq!(result_rx.recv().unwrap(), Err(FsError::ManagerGone));
+}
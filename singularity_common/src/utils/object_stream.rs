@@ -1,5 +1,111 @@
 use serde::{Deserialize, Serialize};
-use std::io::{Read, Write};
+use std::{
+    fmt,
+    io::{self, Read, Write},
+};
+
+/// Bumped whenever the framing or the encodings change (1 was the old `u16` length header)
+pub const OBJECT_STREAM_VERSION: u32 = 2;
+
+/// Anything bigger than this is treated as a broken stream, instead of trying to allocate it
+pub const MAX_OBJECT_LENGTH: usize = 64 * 1024 * 1024;
+
+/// How the objects themselves are turned into bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Encoding {
+    /// Easy to write from any language, and to debug
+    #[default]
+    Json,
+    /// MessagePack (with field names), a lot smaller and faster for big ui elements
+    MessagePack,
+}
+impl Encoding {
+    fn encode<T: Serialize>(self, object: &T) -> Result<Vec<u8>, ObjectStreamError> {
+        match self {
+            Encoding::Json => serde_json::to_vec(object)
+                .map_err(|err| ObjectStreamError::Serialize(err.to_string())),
+            Encoding::MessagePack => rmp_serde::to_vec_named(object)
+                .map_err(|err| ObjectStreamError::Serialize(err.to_string())),
+        }
+    }
+
+    fn decode<T: for<'de> Deserialize<'de>>(
+        self,
+        raw_object: &[u8],
+    ) -> Result<T, ObjectStreamError> {
+        match self {
+            Encoding::Json => serde_json::from_slice(raw_object)
+                .map_err(|err| ObjectStreamError::Deserialize(err.to_string())),
+            Encoding::MessagePack => rmp_serde::from_slice(raw_object)
+                .map_err(|err| ObjectStreamError::Deserialize(err.to_string())),
+        }
+    }
+}
+
+/// The first object each side sends, so that both know what the other one writes.
+///
+/// NOTE: the handshake is always JSON, everything after it is in `encoding`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Handshake {
+    pub version: u32,
+    /// What the sender writes its objects in
+    pub encoding: Encoding,
+}
+impl Handshake {
+    pub fn new(encoding: Encoding) -> Self {
+        Self {
+            version: OBJECT_STREAM_VERSION,
+            encoding,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ObjectStreamError {
+    /// The other side closed the stream (or exited) between objects
+    Disconnected,
+    Io(io::Error),
+    Serialize(String),
+    Deserialize(String),
+    /// The object (or the length header that was read) is bigger than `MAX_OBJECT_LENGTH`
+    TooLarge(usize),
+    /// The other side speaks a different `OBJECT_STREAM_VERSION`
+    VersionMismatch {
+        ours: u32,
+        theirs: u32,
+    },
+}
+impl fmt::Display for ObjectStreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjectStreamError::Disconnected => write!(f, "the other side disconnected"),
+            ObjectStreamError::Io(err) => write!(f, "io error: {err}"),
+            ObjectStreamError::Serialize(err) => write!(f, "failed to serialize object: {err}"),
+            ObjectStreamError::Deserialize(err) => {
+                write!(f, "failed to deserialize object: {err}")
+            }
+            ObjectStreamError::TooLarge(length) => write!(
+                f,
+                "object is {length} bytes, which is more than the max of {MAX_OBJECT_LENGTH}"
+            ),
+            ObjectStreamError::VersionMismatch { ours, theirs } => write!(
+                f,
+                "object stream version {theirs} is not supported (expected {ours})"
+            ),
+        }
+    }
+}
+impl std::error::Error for ObjectStreamError {}
+impl From<io::Error> for ObjectStreamError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::BrokenPipe
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted => ObjectStreamError::Disconnected,
+            _ => ObjectStreamError::Io(err),
+        }
+    }
+}
 
 /// Objects need to be serde serializable and deserializable.
 /// The reciever's deserializer should be compatible with
@@ -8,51 +114,109 @@ use std::io::{Read, Write};
 /// ## Details:
 ///
 /// Once the object is serialized into an array of u8,
-/// two u8s are sent, representing the array's u16 size in big
+/// four u8s are sent, representing the array's u32 size in big
 /// endian. Then, the array is sent.
 ///
-/// The array is currently in JSON format.
+/// The array is in the `Encoding` that was agreed on with the handshake
+/// (`read_object`/`write_object` are JSON).
+///
+/// Both sides should start with `write_handshake` and `read_handshake`.
 pub trait ObjectInputStream {
-    fn read_object<T: for<'de> Deserialize<'de>>(&mut self) -> T;
+    fn read_encoded_object<T: for<'de> Deserialize<'de>>(
+        &mut self,
+        encoding: Encoding,
+    ) -> Result<T, ObjectStreamError>;
+
+    fn read_object<T: for<'de> Deserialize<'de>>(&mut self) -> Result<T, ObjectStreamError> {
+        self.read_encoded_object(Encoding::Json)
+    }
+
+    /// Fails if the other side has a different `OBJECT_STREAM_VERSION`
+    fn read_handshake(&mut self) -> Result<Handshake, ObjectStreamError> {
+        let handshake: Handshake = self.read_object()?;
+
+        if handshake.version != OBJECT_STREAM_VERSION {
+            return Err(ObjectStreamError::VersionMismatch {
+                ours: OBJECT_STREAM_VERSION,
+                theirs: handshake.version,
+            });
+        }
+
+        Ok(handshake)
+    }
 }
 impl<R: Read> ObjectInputStream for R {
-    fn read_object<T: for<'de> Deserialize<'de>>(&mut self) -> T {
+    fn read_encoded_object<T: for<'de> Deserialize<'de>>(
+        &mut self,
+        encoding: Encoding,
+    ) -> Result<T, ObjectStreamError> {
         let raw_message_length = {
-            let mut raw_message_length_buffer = [0; 2];
+            let mut raw_message_length_buffer = [0; 4];
 
-            self.read_exact(&mut raw_message_length_buffer)
-                .expect("failed to read message length from subapp process");
+            // nothing at all means the other side is done, but half a header means it broke
+            let mut filled = 0;
+            while filled < raw_message_length_buffer.len() {
+                match self.read(&mut raw_message_length_buffer[filled..]) {
+                    Ok(0) if filled == 0 => return Err(ObjectStreamError::Disconnected),
+                    Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                    Ok(read) => filled += read,
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                    Err(err) => return Err(err.into()),
+                }
+            }
 
-            usize::from(u16::from_be_bytes(raw_message_length_buffer))
+            u32::from_be_bytes(raw_message_length_buffer) as usize
         };
 
+        if raw_message_length > MAX_OBJECT_LENGTH {
+            return Err(ObjectStreamError::TooLarge(raw_message_length));
+        }
+
         let mut raw_message_buffer = vec![0; raw_message_length];
-        self.read_exact(&mut raw_message_buffer)
-            .expect("failed to read message from subapp process");
+        self.read_exact(&mut raw_message_buffer)?;
 
-        serde_json::from_slice(&raw_message_buffer).expect("failed to deserialize object")
+        encoding.decode(&raw_message_buffer)
     }
 }
 
 pub trait ObjectOutputStream {
-    fn write_object<T: Serialize>(&mut self, object: &T);
-}
-impl<W: Write> ObjectOutputStream for W {
-    fn write_object<T: Serialize>(&mut self, object: &T) {
-        let raw_object = serde_json::to_vec(object).expect("failed to serialize object");
+    fn write_encoded_object<T: Serialize>(
+        &mut self,
+        object: &T,
+        encoding: Encoding,
+    ) -> Result<(), ObjectStreamError>;
 
-        // send raw object length
-        raw_object.len();
+    fn write_object<T: Serialize>(&mut self, object: &T) -> Result<(), ObjectStreamError> {
+        self.write_encoded_object(object, Encoding::Json)
+    }
 
-        // send raw object
-        let raw_object_len = (raw_object.len() as u16).to_be_bytes();
+    /// Tells the other side which encoding this side writes in
+    fn write_handshake(&mut self, encoding: Encoding) -> Result<(), ObjectStreamError> {
+        self.write_object(&Handshake::new(encoding))
+    }
+}
+impl<W: Write> ObjectOutputStream for W {
+    fn write_encoded_object<T: Serialize>(
+        &mut self,
+        object: &T,
+        encoding: Encoding,
+    ) -> Result<(), ObjectStreamError> {
+        let raw_object = encoding.encode(object)?;
 
-        self.write_all(&raw_object_len)
-            .expect("failed to send message length");
+        // checked here, so the other side never sees a length it would refuse
+        if raw_object.len() > MAX_OBJECT_LENGTH {
+            return Err(ObjectStreamError::TooLarge(raw_object.len()));
+        }
 
-        self.write_all(&raw_object).expect("failed to send message");
+        // NOTE: written as one buffer, so a length is never sent without its object
+        let mut message = Vec::with_capacity(4 + raw_object.len());
+        message.extend_from_slice(&(raw_object.len() as u32).to_be_bytes());
+        message.extend_from_slice(&raw_object);
+        self.write_all(&message)?;
 
         // Need to flush for buffered writers
-        self.flush().unwrap();
+        self.flush()?;
+
+        Ok(())
     }
 }
@@ -83,6 +83,65 @@ impl<T> IdTree<T> {
         })
     }
 
+    /// removes only this node, and connects its parent directly to its children
+    /// (the children go where the node was, in the same order)
+    ///
+    /// Root has no parent, so returns None
+    pub fn remove_and_reattach_children(&mut self, id: &Id<T>) -> Option<()> {
+        let parent_id = self.nodes.get(id)?.parent?;
+        let node = self.nodes.remove(id).unwrap();
+
+        for child_id in &node.children {
+            self.nodes.get_mut(child_id).unwrap().parent = Some(parent_id);
+        }
+
+        let siblings = &mut self.nodes.get_mut(&parent_id).unwrap().children;
+        let index = siblings
+            .iter()
+            .position(|sibling_id| sibling_id == id)
+            .unwrap();
+        siblings.splice(index..=index, node.children);
+
+        Some(())
+    }
+
+    /// removes only this node, and the first child takes its place (kind of like deleting from a heap).
+    /// The other children don't move, so they become children of the first child (after its own).
+    ///
+    /// Also works for root. Returns the promoted child, or None if there are no children.
+    pub fn remove_and_promote_first_child(&mut self, id: &Id<T>) -> Option<Id<T>> {
+        let (promoted_id, other_children) = {
+            let (promoted_id, other_children) = self.nodes.get(id)?.children.split_first()?;
+            (*promoted_id, other_children.to_vec())
+        };
+        let node = self.nodes.remove(id).unwrap();
+
+        for child_id in &other_children {
+            self.nodes.get_mut(child_id).unwrap().parent = Some(promoted_id);
+        }
+        let promoted_node = self.nodes.get_mut(&promoted_id).unwrap();
+        promoted_node.parent = node.parent;
+        promoted_node.children.extend(other_children);
+
+        // take the node's place under its parent
+        match node.parent {
+            Some(parent_id) => {
+                let sibling = self
+                    .nodes
+                    .get_mut(&parent_id)
+                    .unwrap()
+                    .children
+                    .iter_mut()
+                    .find(|sibling_id| sibling_id == &id)
+                    .unwrap();
+                *sibling = promoted_id;
+            }
+            None => self.root_id = promoted_id,
+        }
+
+        Some(promoted_id)
+    }
+
     /// similar logic to `add_child`
     ///
     /// meant to be used with pluck, for pluck and place
@@ -118,6 +177,11 @@ impl<T> IdTree<T> {
         &self.nodes.get(parent_id).unwrap().children
     }
 
+    /// None for root (or ids that aren't in the tree)
+    pub fn get_parent(&self, id: &Id<T>) -> Option<Id<T>> {
+        self.nodes.get(id)?.parent
+    }
+
     /// climb upwards
     pub fn get_path(&self, id: Id<T>) -> Option<TreeNodePath> {
         let mut path_vec = Vec::new();
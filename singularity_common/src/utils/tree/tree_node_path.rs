@@ -47,9 +47,41 @@ pub trait TraversableTree {
 pub const TREE_TRAVERSE_KEYS: [char; 16] = [
     'w', 'a', 's', 'd', 'q', 'e', '0', '1', '2', '3', '4', '5', '6', '7', '8', '9',
 ];
+
+/// Ways to move around a tree, so that traversal isn't tied to specific keys.
+///
+/// The keys are decided by the keymap (the `tree.*` actions), see `crate::keymap`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeTraversal {
+    Parent,
+    FirstChild,
+    PreviousSibling,
+    NextSibling,
+    DfsPrevious,
+    DfsNext,
+    /// 0-indexed
+    Child(usize),
+    LastChild,
+}
+impl TreeTraversal {
+    /// The old hard-coded "wasd" keys, see `checked_traverse_based_on_wasd`
+    pub fn from_wasd(traverse_key: char) -> Option<Self> {
+        match traverse_key {
+            'a' | '0' => Some(Self::Parent),
+            'd' => Some(Self::FirstChild),
+            'w' => Some(Self::PreviousSibling),
+            's' => Some(Self::NextSibling),
+            'q' => Some(Self::DfsPrevious),
+            'e' => Some(Self::DfsNext),
+            '1'..='8' => Some(Self::Child(traverse_key.to_digit(10)? as usize - 1)),
+            '9' => Some(Self::LastChild),
+            _ => None,
+        }
+    }
+}
 /// For the traverse functions, some require the original tree to be safe
 mod tree_node_path_traversal_impls {
-    use super::{TraversableTree, TreeNodePath};
+    use super::{TraversableTree, TreeNodePath, TreeTraversal};
     impl TreeNodePath {
         pub fn traverse_to_parent(&self) -> Option<Self> {
             if self.0.is_empty() {
@@ -191,6 +223,36 @@ mod tree_node_path_traversal_impls {
             }
         }
 
+        /// returns None if the traversal is invalid
+        pub fn checked_traverse<T: TraversableTree>(
+            &self,
+            tree_to_traverse: &T,
+            traversal: TreeTraversal,
+        ) -> Option<Self> {
+            match traversal {
+                TreeTraversal::Parent => self.traverse_to_parent(),
+                TreeTraversal::FirstChild => self.traverse_to_first_child(tree_to_traverse),
+                TreeTraversal::PreviousSibling => self.traverse_to_previous_sibling(),
+                TreeTraversal::NextSibling => self.traverse_to_next_sibling(tree_to_traverse),
+                TreeTraversal::DfsPrevious => self.traverse_dfs_prev(tree_to_traverse),
+                TreeTraversal::DfsNext => self.traverse_dfs_next(tree_to_traverse),
+                TreeTraversal::Child(child_index) => {
+                    self.traverse_to_child(tree_to_traverse, child_index)
+                }
+                TreeTraversal::LastChild => self.traverse_to_last_child(tree_to_traverse),
+            }
+        }
+
+        /// `checked_traverse` but if something goes wrong, return self
+        pub fn clamped_traverse<T: TraversableTree>(
+            &self,
+            tree_to_traverse: &T,
+            traversal: TreeTraversal,
+        ) -> Self {
+            self.checked_traverse(tree_to_traverse, traversal)
+                .unwrap_or(self.clone())
+        }
+
         /// This is a helper function, traversing trees based on char input.
         ///
         /// - "wasd" should vaguely correspond to how it looks when the tree is vertically displayed
@@ -201,7 +263,7 @@ mod tree_node_path_traversal_impls {
         ///
         /// returns None if keycode isn't recognized or if the traversal is invalid
         ///
-        /// REVIEW: not sure if this belongs here, as it should be pure logic but this is more input handling
+        /// NOTE: these are just the default keys now, input should go through the keymap and `checked_traverse`
         ///
         /// TODO: seperate functions for wrapped traversal
         pub fn checked_traverse_based_on_wasd<T: TraversableTree>(
@@ -209,19 +271,7 @@ mod tree_node_path_traversal_impls {
             tree_to_traverse: &T,
             traverse_key: char,
         ) -> Option<Self> {
-            match traverse_key {
-                'a' => self.traverse_to_parent(),
-                'd' => self.traverse_to_first_child(tree_to_traverse),
-                'w' => self.traverse_to_previous_sibling(),
-                's' => self.traverse_to_next_sibling(tree_to_traverse),
-                'q' => self.traverse_dfs_prev(tree_to_traverse),
-                'e' => self.traverse_dfs_next(tree_to_traverse),
-                '0' => self.traverse_to_parent(), // same as 'a'
-                '1'..='8' => self
-                    .traverse_to_child(tree_to_traverse, traverse_key.to_digit(10)? as usize - 1),
-                '9' => self.traverse_to_last_child(tree_to_traverse),
-                _ => None,
-            }
+            self.checked_traverse(tree_to_traverse, TreeTraversal::from_wasd(traverse_key)?)
         }
 
         /// `checked_traverse_based_on_wasd` but if something goes wrong, return self
@@ -1,15 +1,55 @@
 pub mod project_manager;
 mod tests;
 
-/// this should be run from cli
-fn main() -> Result<(), std::io::Error> {
+use singularity_common::project::Project;
+use std::process::ExitCode;
+
+/// this should be run from cli:
+/// - `singularity_manager [project path]` opens a project (and initializes it if it is a directory that isn't one yet)
+/// - `singularity_manager init [path] [--template <name>]` only initializes a project
+fn main() -> ExitCode {
     let args: Vec<String> = std::env::args().collect();
 
-    let project_manager = project_manager::ProjectManager::new(
-        args.get(1)
-            .unwrap_or(&"examples/root-project".to_string())
-            .clone(),
-    );
+    let result = if args.get(1).map(String::as_str) == Some("init") {
+        init(&args[2..])
+    } else {
+        open(args.get(1).map_or("examples/root-project", String::as_str))
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn open(project_directory: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let project_manager = project_manager::ProjectManager::new(project_directory.to_string())?;
+
+    Ok(project_manager.run()?)
+}
+
+/// `args` are the ones after `init`
+fn init(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut project_directory = ".".to_string();
+    let mut template = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--template" {
+            let Some(template_name) = args.next() else {
+                return Err("`--template` needs a template name".into());
+            };
+            template = Some(template_name.clone());
+        } else {
+            project_directory = arg.clone();
+        }
+    }
+
+    Project::init(&project_directory, template.as_deref())?;
+    println!("Initialized a project in `{project_directory}`");
 
-    project_manager.run()
+    Ok(())
 }
@@ -0,0 +1,124 @@
+//! Overlay for opening any tab type at runtime, instead of editing `core.json` by hand.
+
+use singularity_common::{
+    components::{text_box::TextBox, Component},
+    keymap::Keymap,
+    project::project_settings::TabData,
+    tab::{packets::Event, registry::TabRegistry},
+};
+use singularity_ui::{
+    color::Color,
+    display_units::DisplayArea,
+    ui_element::{CharCell, CharGrid, UIElement},
+    ui_event::{Key, KeyModifiers, UIEvent},
+};
+use std::path::Path;
+
+pub enum LauncherOutcome {
+    /// Spawn a tab with this data
+    Launch(TabData),
+    Cancel,
+}
+
+pub struct Launcher {
+    /// `(type_id, display_name)` of every registered tab type
+    tab_types: Vec<(String, String)>,
+    /// index into `tab_types`
+    selected_index: usize,
+    /// what will be the new tab's `TabData.session_data`
+    session_path: TextBox,
+}
+impl Launcher {
+    pub fn new(project_directory: &Path, tab_registry: &TabRegistry) -> Self {
+        let mut session_path = TextBox::new(project_directory.to_string_lossy().to_string());
+        session_path.move_cursor_to_end();
+
+        Self {
+            tab_types: tab_registry
+                .iter()
+                .map(|tab_type| (tab_type.type_id.clone(), tab_type.display_name.clone()))
+                .collect(),
+            selected_index: 0,
+            session_path,
+        }
+    }
+
+    /// Returns None while the launcher should stay open
+    pub fn handle_key(
+        &mut self,
+        key: Key,
+        key_modifiers: KeyModifiers,
+        keymap: &Keymap,
+    ) -> Option<LauncherOutcome> {
+        match keymap.get_action(&["launcher"], &key, key_modifiers) {
+            // (nothing to select if nothing is registered)
+            Some("launcher.previous_type") if !self.tab_types.is_empty() => {
+                self.selected_index =
+                    (self.selected_index + self.tab_types.len() - 1) % self.tab_types.len();
+            }
+            Some("launcher.next_type") if !self.tab_types.is_empty() => {
+                self.selected_index = (self.selected_index + 1) % self.tab_types.len();
+            }
+            Some("launcher.launch") => {
+                let Some((type_id, _)) = self.tab_types.get(self.selected_index) else {
+                    // nothing is registered
+                    return Some(LauncherOutcome::Cancel);
+                };
+
+                return Some(LauncherOutcome::Launch(TabData {
+                    tab_type: type_id.clone(),
+                    session_data: serde_json::to_value(self.session_path.get_text_as_string())
+                        .unwrap(),
+                }));
+            }
+            Some("launcher.cancel") => {
+                return Some(LauncherOutcome::Cancel);
+            }
+            _ => {
+                // everything else is for typing the path
+                self.session_path
+                    .handle_event(Event::UIEvent(UIEvent::KeyPress(key, key_modifiers)));
+            }
+        }
+
+        None
+    }
+
+    pub fn render(&self) -> UIElement {
+        let mut launcher_display = CharGrid::from("Open tab:".to_string());
+
+        for (index, (_, display_name)) in self.tab_types.iter().enumerate() {
+            let bg = if index == self.selected_index {
+                Color::CYAN
+            } else {
+                Color::TRANSPARENT
+            };
+
+            let mut tab_type_display = vec![CharCell::new(' '); 2];
+            for character in display_name.chars() {
+                tab_type_display.push(CharCell {
+                    character,
+                    fg: Color::LIGHT_GREEN,
+                    bg,
+                });
+            }
+
+            launcher_display.content.push(tab_type_display);
+        }
+
+        launcher_display.content.push(Vec::new());
+        launcher_display
+            .content
+            .extend(CharGrid::from("Path:".to_string()).content);
+        launcher_display.content.extend(
+            self.session_path
+                .render_grid_with_color((Color::BLACK, Color::LIGHT_YELLOW))
+                .content,
+        );
+
+        UIElement::CharGrid(launcher_display)
+            .fill_bg(Color::DARK_GRAY)
+            .bordered(Color::LIGHT_GREEN)
+            .contain(DisplayArea::new((0.3, 0.3), (0.7, 0.7)))
+    }
+}
@@ -1,29 +1,76 @@
+use launcher::{Launcher, LauncherOutcome};
+use permission_prompt::{PermissionPrompt, PermissionPromptOutcome};
 use singularity_common::{
-    project::Project,
-    tab::{packets::Request, tile::Tile, TabHandler},
+    keymap::Keymap,
+    project::{project_settings::TabData, user_settings::UserSettings, Project, ProjectError},
+    tab::{
+        file_system::{self, resolve_path, FileSystemAccess, FsError, FsRequest},
+        packets::{CloseResponse, Event, QueryError, Request, TabInfo},
+        registry::TabRegistry,
+        tile::{Tile, TileArea},
+        TabHandler,
+    },
     utils::{
         id_map::Id,
-        tree::{id_tree::IdTree, tree_node_path::{TraversableTree, TreeNodePath, TREE_TRAVERSE_KEYS}},
+        tree::{
+            id_tree::IdTree,
+            tree_node_path::{TraversableTree, TreeNodePath},
+        },
     },
 };
 use singularity_ui::{
+    cell_buffer::{CELL_HEIGHT, CELL_WIDTH},
     color::Color,
-    display_units::{DisplayArea, DisplayCoord, DisplaySize},
+    display_units::{DisplayArea, DisplayCoord, DisplaySize, DisplayUnits},
+    headless_backend::HeadlessDisplay,
     ui_element::{CharCell, CharGrid, UIElement},
-    ui_event::{KeyModifiers, KeyTrait, UIEvent},
+    ui_event::{KeyModifiers, UIEvent},
+    wakeup::Wakeup,
     UIDisplay,
 };
 use std::{
+    collections::VecDeque,
     io::{self},
+    path::PathBuf,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc, Mutex,
     },
     thread,
+    time::{Duration, Instant},
 };
-use tabs::Tabs;
+use tabs::{ChildrenOnClose, Direction, Tabs};
+use workspace_prompt::{WorkspacePrompt, WorkspacePromptOutcome, WorkspacePromptPurpose};
 
+mod launcher;
+mod permission_prompt;
 mod tabs;
+mod workspace_prompt;
+
+/// How long tabs get to answer `Event::SaveSession` when the manager shuts down
+const SAVE_SESSION_TIMEOUT: Duration = Duration::from_secs(1);
+/// How long tabs get to answer `Event::RequestClose` (unless they are asking the user),
+/// and how long they get to finish after `Event::Close`
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+/// How many queries a tab gets answered per loop,
+/// so that a tab with lots of them can't hold up the others
+const MAX_QUERIES_PER_TAB: usize = 16;
+/// How much `manager.tile.increment_split` grows the focused tile by
+const SPLIT_STEP: f32 = 0.05;
+/// How close a mouse press has to be to the boundary between two tiles to drag it
+const BOUNDARY_GRAB_PX: f32 = 6.;
+
+/// Waiting for tabs to agree to be closed (see `Event::RequestClose`)
+struct Closing {
+    /// the tab to close (and what happens to its children), or None to quit
+    tab: Option<(Id<TabHandler>, ChildrenOnClose)>,
+    /// haven't accepted yet
+    awaiting: Vec<Id<TabHandler>>,
+    /// asking the user, so they are waited on for as long as it takes
+    prompting: Vec<Id<TabHandler>>,
+    /// tabs that haven't answered by then probably don't know how to
+    deadline: Instant,
+}
 
 #[derive(Debug, Clone)]
 enum Mode {
@@ -62,58 +109,218 @@ pub struct ProjectManager {
     project: Project,
 
     tabs: Tabs,
+    /// every tab type that can be opened
+    tab_registry: TabRegistry,
 
     mode: Mode,
+    /// Some while the tab launcher overlay is open
+    launcher: Option<Launcher>,
+    /// tabs that were sent `Event::SaveSession` and haven't answered yet
+    awaiting_session_data: Vec<Id<TabHandler>>,
+    /// Some while tabs are being asked to close
+    closing: Option<Closing>,
+    /// Some while the boundary between two tiles is being dragged (see `Tiles::find_boundary`)
+    dragged_boundary: Option<(Id<Tile>, usize, TileArea)>,
+    /// where the tabbed containers' title strips were last drawn, for clicking on them
+    title_strips: Vec<(DisplayArea, Id<Tile>)>,
+    /// where the minimized tabs' tray was last drawn (if it was), for clicking on them
+    tray_area: Option<DisplayArea>,
+    /// Some while a workspace's name is being typed
+    workspace_prompt: Option<WorkspacePrompt>,
+    /// file requests the tabs don't have permission for, waiting for the user (the first is shown)
+    permission_prompts: VecDeque<PermissionPrompt>,
+    /// what the user always allowed, until the tab is closed
+    file_system_grants: Vec<(Id<TabHandler>, PathBuf, FileSystemAccess)>,
+    keymap: Keymap,
     is_running: Arc<AtomicBool>,
+    /// the manager sleeps on this until there is input, a tab request/query, or a tab rerendered
+    manager_wakeup: Wakeup,
 
     /// gui
     ui_element: Arc<Mutex<UIElement>>,
+    /// tells the display that `ui_element` changed, it doesn't redraw otherwise
+    redraw_wakeup: Wakeup,
     ui_event_queue: Arc<Mutex<Vec<UIEvent>>>,
-    // ui_window_px: [u32; 2],
+    /// from the last `UIEvent::WindowResized`, every backend sends one at the start
+    ui_window_px: [u32; 2],
 }
 impl ProjectManager {
-    pub fn new<P>(project_directory: P) -> Self
+    /// With the tabs from `singularity_standard_tabs`
+    pub fn new<P>(project_directory: P) -> Result<Self, ProjectError>
+    where
+        P: 'static + AsRef<std::path::Path> + Clone + Send,
+        std::path::PathBuf: From<P>,
+    {
+        let mut tab_registry = TabRegistry::new();
+        singularity_standard_tabs::register_standard_tabs(&mut tab_registry);
+
+        Self::new_with_registry(project_directory, tab_registry)
+    }
+
+    /// The project's `external_tabs` are registered on top of `tab_registry`
+    pub fn new_with_registry<P>(
+        project_directory: P,
+        tab_registry: TabRegistry,
+    ) -> Result<Self, ProjectError>
+    where
+        P: 'static + AsRef<std::path::Path> + Clone + Send,
+        std::path::PathBuf: From<P>,
+    {
+        Self::new_with_user_config(
+            project_directory,
+            tab_registry,
+            &UserSettings::load(),
+            Keymap::user_config_path().as_deref(),
+        )
+    }
+
+    /// `new_with_registry`, but with these instead of the user's settings and keymap files
+    /// (see `Project::new_with_user_settings` and `Keymap::load_with_user_config`)
+    pub fn new_with_user_config<P>(
+        project_directory: P,
+        mut tab_registry: TabRegistry,
+        user_settings: &UserSettings,
+        user_keymap_path: Option<&std::path::Path>,
+    ) -> Result<Self, ProjectError>
     where
         P: 'static + AsRef<std::path::Path> + Clone + Send,
         std::path::PathBuf: From<P>,
     {
-        let project = Project::new(project_directory.clone());
-        let tabs = Tabs::parse_from_project(&project);
+        let project =
+            Project::try_new_with_user_settings(project_directory.clone(), user_settings)?;
+        for (type_id, external_tab_settings) in &project.project_settings.external_tabs {
+            let program = std::path::Path::new(&external_tab_settings.program);
+            // paths are from the project, but plain names are looked up in PATH
+            let program = if program.components().count() > 1 && program.is_relative() {
+                project.get_project_directory().join(program)
+            } else {
+                program.to_path_buf()
+            };
 
-        Self {
+            tab_registry.register_external(
+                type_id.clone(),
+                external_tab_settings
+                    .display_name
+                    .clone()
+                    .unwrap_or_else(|| type_id.clone()),
+                program,
+                external_tab_settings.args.clone(),
+            );
+        }
+        let manager_wakeup = Wakeup::new();
+        let tabs = Tabs::parse_from_project(&project, &tab_registry, &manager_wakeup);
+
+        Ok(Self {
+            keymap: Keymap::load_with_user_config(
+                user_keymap_path,
+                &project.project_settings.keymap,
+            ),
             project,
             tabs,
+            tab_registry,
             mode: Mode::Normal,
+            launcher: None,
+            awaiting_session_data: Vec::new(),
+            closing: None,
+            dragged_boundary: None,
+            title_strips: Vec::new(),
+            tray_area: None,
+            workspace_prompt: None,
+            permission_prompts: VecDeque::new(),
+            file_system_grants: Vec::new(),
             is_running: Arc::new(AtomicBool::new(false)),
+            manager_wakeup,
             ui_element: Arc::new(Mutex::new(UIElement::Container(Vec::new()))),
+            redraw_wakeup: Wakeup::new(),
             ui_event_queue: Arc::new(Mutex::new(Vec::new())),
-        }
+            ui_window_px: [0, 0],
+        })
+    }
+
+    pub fn run(self) -> io::Result<()> {
+        self.run_with_display(UIDisplay::run_display)
+    }
+
+    /// Runs without a window, rendering into memory instead.
+    ///
+    /// Mostly for tests; the display's snapshots show what the manager drew.
+    pub fn run_headless(self, headless_display: HeadlessDisplay) -> io::Result<()> {
+        self.run_with_display(
+            move |ui_element, redraw_wakeup, ui_event_queue, ui_event_wakeup, is_running| {
+                headless_display.run_display(
+                    ui_element,
+                    redraw_wakeup,
+                    ui_event_queue,
+                    ui_event_wakeup,
+                    is_running,
+                )
+            },
+        )
     }
 
-    pub fn run(mut self) -> io::Result<()> {
+    /// `run_display` should have the same signature and behavior as `UIDisplay::run_display`
+    fn run_with_display<F>(mut self, run_display: F) -> io::Result<()>
+    where
+        F: 'static
+            + Send
+            + FnOnce(Arc<Mutex<UIElement>>, Wakeup, Arc<Mutex<Vec<UIEvent>>>, Wakeup, Arc<AtomicBool>),
+    {
         self.is_running.store(true, Ordering::Relaxed);
 
         let ui_element_clone = self.ui_element.clone();
+        let redraw_wakeup_clone = self.redraw_wakeup.clone();
         let ui_event_queue_clone = self.ui_event_queue.clone();
+        let manager_wakeup_clone = self.manager_wakeup.clone();
         let is_running_clone = self.is_running.clone();
         let ui_thread_handle = thread::spawn(move || {
-            UIDisplay::run_display(ui_element_clone, ui_event_queue_clone, is_running_clone);
+            run_display(
+                ui_element_clone,
+                redraw_wakeup_clone,
+                ui_event_queue_clone,
+                manager_wakeup_clone,
+                is_running_clone,
+            );
         });
 
         while self.is_running.load(Ordering::Relaxed) {
-            self.draw_app();
             self.handle_input();
             self.process_tab_requests();
             self.answer_tab_queries();
+            self.update_closing();
+            // draw last, so that the changes from above are shown before sleeping
+            self.draw_app();
+
+            if !self.is_running.load(Ordering::Relaxed) {
+                // closed while handling input, and nothing will wake the manager up anymore
+                break;
+            }
 
-            // FIXME: somehow prevent singularity from eating all of my CPU
-            // const SLEEP_DURATION: std::time::Duration = std::time::Duration::from_millis(100);
-            // thread::sleep(SLEEP_DURATION);
+            // sleep until there is something to do, instead of eating all of my CPU
+            // (or until tabs that didn't answer `Event::RequestClose` ran out of time)
+            match self
+                .closing
+                .as_ref()
+                .map(|closing| closing.deadline.saturating_duration_since(Instant::now()))
+            {
+                Some(timeout) if !timeout.is_zero() => {
+                    self.manager_wakeup.wait_timeout(timeout);
+                }
+                _ => self.manager_wakeup.wait(),
+            }
         }
 
         ui_thread_handle.join().unwrap();
 
+        // the tabs are waiting on these, and no one is left to answer them
+        while let Some(permission_prompt) = self.permission_prompts.pop_front() {
+            self.deny_fs_request(permission_prompt.tab_id, &permission_prompt.fs_request);
+        }
+        // NOTE: the tabs were asked already (see `request_quit`),
+        // unless quitting didn't wait for them
+        self.collect_session_data();
         self.save_to_file();
+        // NOTE: after saving, so that hung tabs can't lose the session
+        Self::close_tab_handlers(self.tabs.take_tab_handlers());
 
         Ok(())
     }
@@ -123,59 +330,245 @@ impl ProjectManager {
         tile_id: Id<Tile>,
         container_area: DisplayArea,
     ) -> UIElement {
-        let tile = *self.tabs.get_display_tiles().get_tile(tile_id).unwrap();
+        let tile = self
+            .tabs
+            .get_display_tiles()
+            .get_tile(tile_id)
+            .unwrap()
+            .clone();
 
         match tile {
-            Tile::Container {
-                children,
-                orientation,
-                split,
-            } => {
-                let area_splits = match orientation {
-                    singularity_common::tab::tile::Orientation::Horizontal => [
-                        DisplayArea::new((0., 0.), (1., split)),
-                        DisplayArea::new((0., split), (1., 1.)),
-                    ],
-                    singularity_common::tab::tile::Orientation::Vertical => [
-                        DisplayArea::new((0., 0.), (split, 1.)),
-                        DisplayArea::new((split, 0.), (1., 1.)),
-                    ],
-                };
+            Tile::Container(container) => {
+                if let Some(active) = container.get_active() {
+                    // title strip on top, and only the active child under it
+                    let title_strip_area =
+                        DisplayArea::new((0., 0.), (1., CELL_HEIGHT)).map_onto(container_area);
+                    self.title_strips.push((title_strip_area, tile_id));
+
+                    return UIElement::Container(vec![
+                        self.render_title_strip(&container.children, active)
+                            .contain(title_strip_area),
+                        self.render_tile_recursive(
+                            container.children[active],
+                            DisplayArea::new((0., CELL_HEIGHT), (1., 1.)).map_onto(container_area),
+                        ),
+                    ]);
+                }
 
-                UIElement::Container(vec![
-                    self.render_tile_recursive(
-                        children[0],
-                        area_splits[0].map_onto(container_area),
-                    ),
-                    self.render_tile_recursive(
-                        children[1],
-                        area_splits[1].map_onto(container_area),
-                    ),
-                ])
+                let axis = container.orientation.axis();
+                let child_elements = container
+                    .children
+                    .iter()
+                    .zip(container.child_areas([[0., 0.], [1., 1.]]))
+                    .map(|(child_id, mut child_area)| {
+                        // only split along the axis
+                        child_area[0][1 - axis] = 0.;
+                        child_area[1][1 - axis] = 1.;
+
+                        self.render_tile_recursive(
+                            *child_id,
+                            DisplayArea::new_proportional(child_area).map_onto(container_area),
+                        )
+                    })
+                    .collect();
+
+                UIElement::Container(child_elements)
             }
-            Tile::Tab { tab_id } => {
-                let tab = self.tabs.get_mut_tab_handler(tab_id).unwrap();
+            Tile::Tab { tab_id } => self
+                .render_tab(tab_id, container_area)
+                .contain(container_area),
+        }
+    }
 
-                // NOTE: rn, this is how the tab area is updated, but there's gotta be a better way
-                tab.set_area(container_area);
+    /// (not contained in the area yet, so that it can be decorated first)
+    fn render_tab(&mut self, tab_id: Id<TabHandler>, area: DisplayArea) -> UIElement {
+        let tab = self.tabs.get_mut_tab_handler(tab_id).unwrap();
 
-                tab.get_ui_element().contain(container_area)
+        // NOTE: rn, this is how the tab area is updated, but there's gotta be a better way
+        tab.set_area(area);
+
+        if let Some(crash_message) = tab.get_crash_message() {
+            Self::render_crashed_tab(tab, &crash_message, &self.keymap)
+        } else {
+            tab.get_ui_element()
+        }
+    }
+
+    /// The titles of the minimized tabs, oldest first
+    fn render_tray(&self) -> UIElement {
+        let minimized_tabs = self.tabs.get_overlay().get_minimized();
+        let minimized_len = minimized_tabs.len() as f32;
+
+        UIElement::Container(
+            minimized_tabs
+                .iter()
+                .enumerate()
+                .map(|(index, minimized_tab)| {
+                    let title = Self::get_tab_title(
+                        self.tabs.get_tab_handler(minimized_tab.tab_id).unwrap(),
+                    );
+
+                    UIElement::CharGrid(CharGrid::new_monostyled(
+                        format!(" {title}"),
+                        Color::LIGHT_YELLOW,
+                        Color::DARK_GRAY,
+                    ))
+                    .fill_bg(Color::DARK_GRAY)
+                    .contain(DisplayArea::new_proportional([
+                        [index as f32 / minimized_len, 0.],
+                        [(index + 1) as f32 / minimized_len, 1.],
+                    ]))
+                })
+                .collect(),
+        )
+    }
+
+    /// Every workspace's name, with the active one highlighted
+    fn render_workspace_list(&self) -> CharGrid {
+        let mut workspace_list = Vec::new();
+        for (index, workspace_name) in self.tabs.get_workspace_names().into_iter().enumerate() {
+            let (fg, bg) = if index == self.tabs.get_active_workspace_index() {
+                (Color::BLACK, Color::LIGHT_GREEN)
+            } else {
+                (Color::LIGHT_GREEN, Color::DARK_GRAY)
+            };
+
+            for character in format!(" {}:{workspace_name} ", index + 1).chars() {
+                workspace_list.push(CharCell { character, fg, bg });
             }
         }
+
+        CharGrid {
+            content: vec![workspace_list],
+        }
+    }
+
+    /// Where the tiles are drawn: everything, except the bottom bar (if there is anything in it)
+    fn get_tiles_area(&self) -> DisplayArea {
+        if self.tabs.get_overlay().get_minimized().is_empty()
+            && self.tabs.get_workspace_names().len() == 1
+        {
+            DisplayArea::FULL
+        } else {
+            DisplayArea::new((0., 0.), (1., DisplayUnits::from_mixed(-CELL_HEIGHT, 1.)))
+        }
+    }
+
+    /// A point in the window, in proportions of the tiles area (see `get_tiles_area`), for `Tiles`
+    fn to_tiles_point(&self, [x, y]: [u32; 2], [tot_width, tot_height]: [u32; 2]) -> [f32; 2] {
+        let tiles_height = self.get_tiles_area().1.y.pixels(tot_height as i32).max(1);
+
+        [x as f32 / tot_width as f32, y as f32 / tiles_height as f32]
+    }
+
+    /// The tab's name, or its type if it didn't set one
+    fn get_tab_title(tab: &TabHandler) -> &str {
+        if tab.tab_name.is_empty() {
+            &tab.get_tab_data().tab_type
+        } else {
+            &tab.tab_name
+        }
+    }
+
+    /// One title per child of a tabbed container, with the shown one highlighted
+    fn render_title_strip(&self, children: &[Id<Tile>], active: usize) -> UIElement {
+        let children_len = children.len() as f32;
+
+        UIElement::Container(
+            children
+                .iter()
+                .enumerate()
+                .map(|(index, child_id)| {
+                    let tab_id = self.tabs.get_display_tiles().first_visible_leaf(*child_id);
+                    let title = Self::get_tab_title(self.tabs.get_tab_handler(tab_id).unwrap());
+                    let (fg, bg) = if index == active {
+                        (Color::BLACK, Color::LIGHT_GREEN)
+                    } else {
+                        (Color::LIGHT_GREEN, Color::DARK_GRAY)
+                    };
+
+                    UIElement::CharGrid(CharGrid::new_monostyled(format!(" {title}"), fg, bg))
+                        .fill_bg(bg)
+                        .contain(DisplayArea::new_proportional([
+                            [index as f32 / children_len, 0.],
+                            [(index + 1) as f32 / children_len, 1.],
+                        ]))
+                })
+                .collect(),
+        )
+    }
+
+    /// Stands in for a tab that panicked, until it is restarted or closed
+    fn render_crashed_tab(tab: &TabHandler, crash_message: &str, keymap: &Keymap) -> UIElement {
+        let tab_name = Self::get_tab_title(tab);
+
+        UIElement::CharGrid(CharGrid::from(format!(
+            "`{tab_name}` crashed:\n{crash_message}\n\n{}: restart\n{}: close",
+            keymap.describe_shortcuts("crashed_tab.restart"),
+            keymap.describe_shortcuts("crashed_tab.close"),
+        )))
+        .fill_bg(Color::DARK_GRAY)
+        .bordered(Color::RED)
     }
 
     fn draw_app(&mut self) {
         let mut tab_elements = Vec::new();
+        self.title_strips.clear();
+        self.tray_area = None;
 
         // for tab_id in self.tabs.get_display_order().clone() {
         //     let tab = &mut self.tabs.get_mut_tab_handler(tab_id).unwrap();
 
         //     tab_elements.push(tab.get_ui_element().contain(tab.get_area()));
         // }
-        tab_elements.push(self.render_tile_recursive(
-            self.tabs.get_display_tiles().get_root_tile(),
-            DisplayArea::FULL,
-        ));
+        if let Some(maximized_tab_id) = self.tabs.get_overlay().get_maximized() {
+            // covers everything, so nothing else is drawn
+            let maximized_element = self.render_tab(maximized_tab_id, DisplayArea::FULL);
+            tab_elements.push(
+                maximized_element
+                    .fill_bg(Color::BLACK)
+                    .contain(DisplayArea::FULL),
+            );
+        } else {
+            tab_elements.push(self.render_tile_recursive(
+                self.tabs.get_display_tiles().get_root_tile(),
+                self.get_tiles_area(),
+            ));
+
+            // floating tabs on top of the tiles, last on top
+            for floating_tab in self.tabs.get_overlay().get_floating().to_vec() {
+                let floating_element = self.render_tab(floating_tab.tab_id, floating_tab.area);
+                tab_elements.push(
+                    floating_element
+                        .fill_bg(Color::BLACK)
+                        .bordered(Color::LIGHT_GREEN)
+                        .contain(floating_tab.area),
+                );
+            }
+
+            // bottom bar: the workspaces (if there are multiple), then the tray
+            let bar_top = DisplayUnits::from_mixed(-CELL_HEIGHT, 1.);
+            let mut workspaces_width = 0;
+            if self.tabs.get_workspace_names().len() > 1 {
+                let workspace_list = self.render_workspace_list();
+                workspaces_width = CELL_WIDTH * workspace_list.content[0].len() as i32;
+                tab_elements.push(
+                    UIElement::CharGrid(workspace_list)
+                        .fill_bg(Color::DARK_GRAY)
+                        .contain(DisplayArea::new((0., bar_top), (workspaces_width, 1.))),
+                );
+            }
+            if !self.tabs.get_overlay().get_minimized().is_empty() {
+                let tray_area = DisplayArea::new((workspaces_width, bar_top), (1., 1.));
+                self.tray_area = Some(tray_area);
+                tab_elements.push(self.render_tray().contain(tray_area));
+            }
+        }
+
+        // display the workspace name prompt on top of the tabs
+        if let Some(workspace_prompt) = &self.workspace_prompt {
+            tab_elements.push(workspace_prompt.render());
+        }
 
         // display the tab focuser/selector
         if let Mode::ChoosingFocus { focusing_index, plucked } = &self.mode {
@@ -256,48 +649,515 @@ impl ProjectManager {
             }
         }
 
-        *(self.ui_element.lock().unwrap()) =
-            UIElement::Container(tab_elements).fill_bg(Color::BLACK);
+        // display the tab launcher on top of everything
+        if let Some(launcher) = &self.launcher {
+            tab_elements.push(launcher.render());
+        }
+
+        // except for tabs waiting on the user
+        if let Some(permission_prompt) = self.permission_prompts.front() {
+            tab_elements.push(permission_prompt.render(&self.keymap));
+        }
+
+        let new_ui_element = UIElement::Container(tab_elements).fill_bg(Color::BLACK);
+        let mut ui_element = self.ui_element.lock().unwrap();
+        // the manager loops on every input/request, most of which don't change anything visible
+        if *ui_element != new_ui_element {
+            *ui_element = new_ui_element;
+            self.redraw_wakeup.notify();
+        }
+    }
+
+    /// Asks every tab for its final session data, and waits for them (up to `SAVE_SESSION_TIMEOUT`)
+    fn collect_session_data(&mut self) {
+        // crashed tabs can't answer, but their last session data is still there
+        self.awaiting_session_data = self
+            .tabs
+            .collect_tab_ids()
+            .into_iter()
+            .filter(|tab_id| !self.tabs.get_tab_handler(*tab_id).unwrap().is_crashed())
+            .collect();
+        for tab_id in &self.awaiting_session_data {
+            self.tabs
+                .get_tab_handler(*tab_id)
+                .unwrap()
+                .send_event(singularity_common::tab::packets::Event::SaveSession);
+        }
+
+        let deadline = Instant::now() + SAVE_SESSION_TIMEOUT;
+        loop {
+            self.process_tab_requests();
+            // tabs might still be initializing, which can need queries
+            self.answer_tab_queries();
+
+            let tabs = &self.tabs;
+            self.awaiting_session_data.retain(|tab_id| {
+                tabs.get_tab_handler(*tab_id)
+                    .is_some_and(|tab| !tab.is_crashed())
+            });
+
+            let now = Instant::now();
+            if self.awaiting_session_data.is_empty() || now >= deadline {
+                break;
+            }
+            self.manager_wakeup.wait_timeout(deadline - now);
+        }
+
+        if !self.awaiting_session_data.is_empty() {
+            println!(
+                "{} tab(s) didn't send their session data in time",
+                self.awaiting_session_data.len()
+            );
+        }
+    }
+
+    /// Asks the tab (and its children, if they are closed too) or every tab, to quit, whether they can be closed.
+    /// They are closed once they all accept (see `update_closing`).
+    fn start_closing(&mut self, tab: Option<(Id<TabHandler>, ChildrenOnClose)>) {
+        let awaiting = match tab {
+            Some((tab_id, children_on_close)) => {
+                self.tabs.collect_removed_ids(&tab_id, children_on_close)
+            }
+            None => self.tabs.collect_tab_ids(),
+        };
+        for tab_id in &awaiting {
+            self.tabs
+                .get_tab_handler(*tab_id)
+                .unwrap()
+                .send_event(Event::RequestClose);
+        }
+
+        self.closing = Some(Closing {
+            tab,
+            awaiting,
+            prompting: Vec::new(),
+            deadline: Instant::now() + CLOSE_TIMEOUT,
+        });
+    }
+
+    /// Ctrl+Q or closing the window.
+    /// Asks every tab first, unless they are already being asked,
+    /// in which case it quits without waiting for them.
+    fn request_quit(&mut self) {
+        if self
+            .closing
+            .as_ref()
+            .is_some_and(|closing| closing.tab.is_none())
+        {
+            self.is_running.store(false, Ordering::Relaxed);
+        } else {
+            self.start_closing(None);
+        }
+    }
+
+    /// Ctrl+W (or closing a crashed tab), and Ctrl+Shift+W/Ctrl+Alt+W to keep the children
+    fn close_focused_tab(&mut self, children_on_close: ChildrenOnClose) {
+        let focused_tab_id = self.tabs.get_focused_tab_id();
+
+        if !self.tabs.can_remove_tab(&focused_tab_id, children_on_close) {
+            println!("Can't close {focused_tab_id:?} with {children_on_close:?}");
+        } else if self.closing.is_some() {
+            println!("Already closing tabs");
+        } else {
+            self.start_closing(Some((focused_tab_id, children_on_close)));
+        }
+    }
+
+    fn handle_close_response(&mut self, tab_id: Id<TabHandler>, close_response: CloseResponse) {
+        let Some(closing) = &mut self.closing else {
+            // (eg: a prompt that was answered after another tab vetoed)
+            return;
+        };
+
+        match close_response {
+            CloseResponse::Accept => {
+                closing
+                    .awaiting
+                    .retain(|awaiting_id| *awaiting_id != tab_id);
+                closing
+                    .prompting
+                    .retain(|prompting_id| *prompting_id != tab_id);
+            }
+            CloseResponse::Prompting => {
+                if !closing.prompting.contains(&tab_id) {
+                    closing.prompting.push(tab_id);
+                }
+            }
+            CloseResponse::Veto => {
+                // the other tabs can stop asking
+                for awaiting_id in &closing.awaiting {
+                    if let Some(tab) = self.tabs.get_tab_handler(*awaiting_id) {
+                        tab.send_event(Event::CloseCancelled);
+                    }
+                }
+                self.closing = None;
+            }
+        }
+    }
+
+    /// Closes the tabs once they all accepted
+    fn update_closing(&mut self) {
+        let Some(closing) = &mut self.closing else {
+            return;
+        };
+
+        let now = Instant::now();
+        let tabs = &self.tabs;
+        closing.awaiting.retain(|tab_id| {
+            // crashed tabs can't answer
+            tabs.get_tab_handler(*tab_id)
+                .is_some_and(|tab| !tab.is_crashed())
+                && (now < closing.deadline || closing.prompting.contains(tab_id))
+        });
+        closing
+            .prompting
+            .retain(|tab_id| closing.awaiting.contains(tab_id));
+
+        if closing.awaiting.is_empty() {
+            match self.closing.take().unwrap().tab {
+                Some((tab_id, children_on_close)) => {
+                    // tabs waiting on the user can't close until they're answered
+                    let removed_ids = self.tabs.collect_removed_ids(&tab_id, children_on_close);
+                    for permission_prompt in std::mem::take(&mut self.permission_prompts) {
+                        if removed_ids.contains(&permission_prompt.tab_id) {
+                            self.deny_fs_request(
+                                permission_prompt.tab_id,
+                                &permission_prompt.fs_request,
+                            );
+                        } else {
+                            self.permission_prompts.push_back(permission_prompt);
+                        }
+                    }
+                    self.file_system_grants
+                        .retain(|(granted_id, ..)| !removed_ids.contains(granted_id));
+
+                    Self::close_tab_handlers(
+                        self.tabs.remove_tab_and_refocus(&tab_id, children_on_close),
+                    );
+                }
+                None => {
+                    // the tabs are closed after the main loop
+                    self.is_running.store(false, Ordering::Relaxed);
+                }
+            }
+        } else if let Some(prompting_id) = closing.prompting.first().copied() {
+            // prompts need key presses, so bring one to the user
+            if !closing.prompting.contains(&self.tabs.get_focused_tab_id()) {
+                self.tabs.set_focused_tab_id(prompting_id);
+            }
+        }
+    }
+
+    /// Sends `Event::Close`, then waits (up to `CLOSE_TIMEOUT`) for the tab threads to finish
+    fn close_tab_handlers(tab_handlers: Vec<TabHandler>) {
+        for tab in &tab_handlers {
+            tab.send_event(Event::Close);
+        }
+
+        let deadline = Instant::now() + CLOSE_TIMEOUT;
+        for tab in tab_handlers {
+            let tab_name = tab.tab_name.clone();
+            if !tab.join(deadline) {
+                println!("`{tab_name}` didn't close in time, leaving it behind");
+            }
+        }
     }
 
-    fn save_to_file(mut self) {
+    fn save_to_file(&mut self) {
         // save the tabs session
         let open_tabs = self.tabs.save_session();
-        self.project.project_settings.open_tabs = Some(open_tabs);
-        self.project.save_to_file();
+        self.project.save_session(open_tabs);
     }
 
     fn handle_input(&mut self) {
-        for ui_event in std::mem::take(&mut *(self.ui_event_queue.lock().unwrap())) {
+        let ui_events = std::mem::take(&mut *(self.ui_event_queue.lock().unwrap()));
+        for ui_event in ui_events {
             use singularity_ui::ui_event::UIEvent;
             match ui_event {
-                UIEvent::KeyPress(key, KeyModifiers::CTRL) if key.raw_code == 16 => {
-                    // Ctrl+Q
-                    dbg!("Goodbye!");
-                    self.is_running.store(false, Ordering::Relaxed);
-                    return;
-                }
-                UIEvent::KeyPress(key, KeyModifiers::ALT)
-                    if key.to_char() == Some('\n')
-                        // `' '` is a placeholder for some key that isn't in tree traverse
-                        || TREE_TRAVERSE_KEYS.contains(&key.to_char().unwrap_or(' ')) =>
-                {
-                    // Alt + arrows should be like alt tab for Windows and Linux but tree based
-                    // Alt + Enter either opens the tab chooser or closes it and chooses the tab
-
-                    if key.to_char() == Some('\n') && self.mode.try_as_choosing_focus().is_some() {
-                        // place if needed, save tree index, and close window
+                UIEvent::KeyPress(ref key, key_modifiers) => {
+                    if let Some(permission_prompt) = self.permission_prompts.front() {
+                        // same as the launcher, and it's in front of it
+                        if !self.keymap.is_action("manager.quit", key, key_modifiers) {
+                            if let Some(outcome) =
+                                permission_prompt.handle_key(*key, key_modifiers, &self.keymap)
+                            {
+                                let PermissionPrompt {
+                                    tab_id, fs_request, ..
+                                } = self.permission_prompts.pop_front().unwrap();
+                                match outcome {
+                                    PermissionPromptOutcome::AllowOnce => {
+                                        self.perform_fs_request(tab_id, fs_request)
+                                    }
+                                    PermissionPromptOutcome::AllowAlways => {
+                                        self.file_system_grants.push((
+                                            tab_id,
+                                            resolve_path(fs_request.get_path()),
+                                            fs_request.get_access(),
+                                        ));
+                                        self.perform_fs_request(tab_id, fs_request);
+                                    }
+                                    PermissionPromptOutcome::Deny => {
+                                        self.deny_fs_request(tab_id, &fs_request)
+                                    }
+                                }
+                            }
+                            continue;
+                        }
+                    }
 
-                        let (new_focus_index, pluck) = self.mode.try_as_choosing_focus_mut().unwrap();
+                    if let Some(launcher) = &mut self.launcher {
+                        // the launcher takes every key press while it is open, except for quitting
+                        if !self.keymap.is_action("manager.quit", key, key_modifiers) {
+                            match launcher.handle_key(*key, key_modifiers, &self.keymap) {
+                                Some(LauncherOutcome::Launch(tab_data)) => {
+                                    self.launcher = None;
+                                    self.launch_tab(tab_data);
+                                }
+                                Some(LauncherOutcome::Cancel) => {
+                                    self.launcher = None;
+                                }
+                                None => {}
+                            }
+                            continue;
+                        }
+                    }
 
-                        if let Some(pluck) = pluck.take() {
-                            self.tabs.org_place(pluck, self.tabs.get_id_by_org_path(new_focus_index).unwrap());
+                    if let Some(workspace_prompt) = &mut self.workspace_prompt {
+                        // same as the launcher
+                        if !self.keymap.is_action("manager.quit", key, key_modifiers) {
+                            match workspace_prompt.handle_key(*key, key_modifiers, &self.keymap) {
+                                Some(WorkspacePromptOutcome::Confirm(name)) => {
+                                    match workspace_prompt.get_purpose() {
+                                        WorkspacePromptPurpose::Create => {
+                                            self.tabs.create_workspace(name)
+                                        }
+                                        WorkspacePromptPurpose::Rename => {
+                                            self.tabs.rename_workspace(name)
+                                        }
+                                    }
+                                    self.workspace_prompt = None;
+                                }
+                                Some(WorkspacePromptOutcome::Cancel) => {
+                                    self.workspace_prompt = None;
+                                }
+                                None => {}
+                            }
+                            continue;
                         }
+                    }
 
-                        self.tabs.set_focused_tab_path(new_focus_index);
+                    if let Some(action) = self.keymap.get_action(&["manager"], key, key_modifiers) {
+                        match action {
+                            "manager.quit" => {
+                                // Ctrl+Q
+                                self.request_quit();
+                            }
+                            "manager.toggle_focuser" => {
+                                // Alt + Enter either opens the tab chooser or closes it and chooses the tab
+
+                                if self.mode.try_as_choosing_focus().is_some() {
+                                    // place if needed, save tree index, and close window
+
+                                    let (new_focus_index, pluck) =
+                                        self.mode.try_as_choosing_focus_mut().unwrap();
+
+                                    if let Some(pluck) = pluck.take() {
+                                        self.tabs.org_place(
+                                            pluck,
+                                            self.tabs.get_id_by_org_path(new_focus_index).unwrap(),
+                                        );
+                                    }
+
+                                    self.tabs.set_focused_tab_path(new_focus_index);
+
+                                    self.mode = Mode::Normal;
+                                } else {
+                                    self.mode = Mode::ChoosingFocus {
+                                        focusing_index: self
+                                            .tabs
+                                            .get_tab_path(&self.tabs.get_focused_tab_id())
+                                            .unwrap(),
+                                        plucked: None,
+                                    };
+                                }
+                                dbg!(&self.mode);
+                            }
+                            "manager.pluck_or_place" => {
+                                // Alt + Shift + P does pluck/place
+
+                                let (focusing_index, plucked) = match self.mode {
+                                    Mode::Normal => (
+                                        &self
+                                            .tabs
+                                            .get_tab_path(&self.tabs.get_focused_tab_id())
+                                            .unwrap(),
+                                        None,
+                                    ),
+                                    Mode::ChoosingFocus {
+                                        ref focusing_index,
+                                        ref mut plucked,
+                                    } => (focusing_index, plucked.take()),
+                                };
+
+                                if let Some(plucked) = plucked {
+                                    // place
+                                    self.tabs.org_place(
+                                        plucked,
+                                        self.tabs.get_id_by_org_path(focusing_index).unwrap(),
+                                    );
+                                } else {
+                                    // pluck
+                                    if !focusing_index.is_root() {
+                                        self.mode = Mode::ChoosingFocus {
+                                            focusing_index: focusing_index
+                                                .traverse_to_parent()
+                                                .unwrap(),
+                                            plucked: self.tabs.org_pluck(
+                                                &self
+                                                    .tabs
+                                                    .get_id_by_org_path(focusing_index)
+                                                    .unwrap(),
+                                            ),
+                                        };
+                                    }
+                                }
+                            }
+                            "manager.swap_focused_and_focusing" => {
+                                // Alt + Shift + S swaps actually focused and focusing
+
+                                if let Some(focuser_path) =
+                                    self.mode.try_get_focusing_index().cloned()
+                                {
+                                    let focusing =
+                                        self.tabs.get_id_by_org_path(&focuser_path).unwrap();
+                                    let actually_focused = self.tabs.get_focused_tab_id();
+
+                                    self.tabs.org_swap([focusing, actually_focused]);
+
+                                    // self.app_focuser_index = Some(todo!());
+                                }
+                            }
+                            "manager.tile.maximize" => {
+                                // LOGO+"F"ullscreen
+                                self.tabs.toggle_focused_maximized();
+                            }
+                            "manager.tile.toggle_floating" => {
+                                self.tabs.toggle_focused_floating();
+                            }
+                            "manager.tile.minimize" => {
+                                self.tabs.minimize_focused();
+                            }
+                            "manager.tile.restore_minimized" => {
+                                self.tabs.restore_last_minimized();
+                            }
+                            "manager.tile.focus_left" => {
+                                self.tabs
+                                    .focus_in_direction(Direction::Left, self.ui_window_px);
+                            }
+                            "manager.tile.focus_right" => {
+                                self.tabs
+                                    .focus_in_direction(Direction::Right, self.ui_window_px);
+                            }
+                            "manager.tile.focus_up" => {
+                                self.tabs
+                                    .focus_in_direction(Direction::Up, self.ui_window_px);
+                            }
+                            "manager.tile.focus_down" => {
+                                self.tabs
+                                    .focus_in_direction(Direction::Down, self.ui_window_px);
+                            }
+                            "manager.tile.move_left" => {
+                                self.tabs
+                                    .move_focused_tile(Direction::Left, self.ui_window_px);
+                            }
+                            "manager.tile.move_right" => {
+                                self.tabs
+                                    .move_focused_tile(Direction::Right, self.ui_window_px);
+                            }
+                            "manager.tile.move_up" => {
+                                self.tabs
+                                    .move_focused_tile(Direction::Up, self.ui_window_px);
+                            }
+                            "manager.tile.move_down" => {
+                                self.tabs
+                                    .move_focused_tile(Direction::Down, self.ui_window_px);
+                            }
+                            "manager.tile.increment_split" => {
+                                // LOGO+"=" (but it represents "+")
+                                self.tabs.resize_focused_tile(SPLIT_STEP);
+                            }
+                            "manager.tile.decrement_split" => {
+                                // LOGO+"-"
+                                self.tabs.resize_focused_tile(-SPLIT_STEP);
+                            }
+                            "manager.tile.toggle_tabbed" => {
+                                // LOGO+"W", like i3/sway
+                                self.tabs.toggle_focused_tile_tabbed();
+                            }
+                            "manager.tile.next_tab" => {
+                                self.tabs.cycle_focused_tabbed(1);
+                            }
+                            "manager.tile.previous_tab" => {
+                                self.tabs.cycle_focused_tabbed(-1);
+                            }
+                            "manager.tile.cycle_layout" => {
+                                let layout_policy = self.tabs.get_layout_policy().next();
+                                self.tabs.relayout(layout_policy);
+                            }
+                            "manager.tile.transpose" => {
+                                // "T"ranspose selected tile's container (change horizontal vs vertical)
+                                self.tabs.transpose_focused_tile_parent();
+                            }
+                            "manager.tile.swap_siblings" => {
+                                // "S"wap selected tile's siblings
+                                self.tabs.swap_focused_tile_siblings();
+                            }
+                            "manager.workspace.next" => {
+                                self.tabs.cycle_workspace(1);
+                            }
+                            "manager.workspace.previous" => {
+                                self.tabs.cycle_workspace(-1);
+                            }
+                            "manager.workspace.create" => {
+                                // (empty, since the focused tab is what goes into it)
+                                self.workspace_prompt =
+                                    Some(WorkspacePrompt::new(WorkspacePromptPurpose::Create, ""));
+                            }
+                            "manager.workspace.rename" => {
+                                self.workspace_prompt = Some(WorkspacePrompt::new(
+                                    WorkspacePromptPurpose::Rename,
+                                    self.tabs.get_workspace_name(),
+                                ));
+                            }
+                            "manager.workspace.delete" => {
+                                self.tabs.delete_workspace();
+                            }
+                            "manager.open_launcher" => {
+                                self.launcher = Some(Launcher::new(
+                                    self.project.get_project_directory(),
+                                    &self.tab_registry,
+                                ));
+                            }
+                            "manager.close_tab" => {
+                                println!("Deletin");
+                                self.close_focused_tab(ChildrenOnClose::Close);
+                            }
+                            "manager.close_tab.reattach_children" => {
+                                self.close_focused_tab(ChildrenOnClose::ReattachToParent);
+                            }
+                            "manager.close_tab.promote_first_child" => {
+                                self.close_focused_tab(ChildrenOnClose::PromoteFirstChild);
+                            }
+                            _ => {
+                                println!("Unknown manager action `{action}`");
+                            }
+                        }
+                    } else if let Some(traversal) =
+                        self.keymap
+                            .get_tree_traversal(key, key_modifiers, KeyModifiers::ALT)
+                    {
+                        // Alt + traversal keys should be like alt tab for Windows and Linux but tree based
 
-                        self.mode = Mode::Normal;
-                    } else {
                         let (new_focus_index, plucked) = match &self.mode {
                             Mode::Normal => {
                                 (&self.tabs
@@ -308,170 +1168,166 @@ impl ProjectManager {
                         };
 
                         self.mode = Mode::ChoosingFocus { 
-                            focusing_index: match key.to_char() {
-                                Some('\n') => new_focus_index.clone(),
-                                Some(traverse_key) if TREE_TRAVERSE_KEYS.contains(&traverse_key) => {
-                                    new_focus_index
-                                        .clamped_traverse_based_on_wasd(&self.tabs, traverse_key)
-                                }
-                                _ => panic!(),
-                            },
+                            focusing_index: new_focus_index.clamped_traverse(&self.tabs, traversal),
                             plucked: plucked.clone(),
                         };
-                    }
-                    dbg!(&self.mode);
-                    // dbg!(&self.focused_tab_path);
-                }
-                UIEvent::KeyPress(
-                    key,
-                    KeyModifiers {
-                        ctrl: false,
-                        alt: true,
-                        shift: false,
-                        caps_lock: false,
-                        logo: true,
-                        num_lock: false,
-                    },
-                ) if // `' '` is a placeholder for some key that isn't in tree traverse
-                TREE_TRAVERSE_KEYS.contains(&key.to_char().unwrap_or(' ')) =>
-                {
-                    // Alt + Windows + traversal key swaps position of focused and what would be the new focused
-                    
-                    let (prev_focus_index, plucked) = match &self.mode {
-                        Mode::Normal => (&self.tabs
-                            .get_tab_path(&self.tabs.get_focused_tab_id())
-                            .unwrap(), &None),
-                        Mode::ChoosingFocus { focusing_index, plucked } => (focusing_index, plucked),
-                    };
-                    
-                    let new_focus_index = prev_focus_index.clamped_traverse_based_on_wasd(&self.tabs, key.to_char().unwrap());
-                    
-                    self.tabs.org_swap([self.tabs.get_id_by_org_path(prev_focus_index).unwrap(), self.tabs.get_id_by_org_path(&new_focus_index).unwrap()]);
-                    
-                    // self.tabs.set_focused_tab_path(&new_focus_index);
-                    self.mode = Mode::ChoosingFocus{ focusing_index: new_focus_index, plucked: plucked.clone() };
-                    
-                    dbg!(&self.mode);
-                }
-                UIEvent::KeyPress(
-                    key,
-                    KeyModifiers {
-                        ctrl: false,
-                        alt: true,
-                        shift: true,
-                        caps_lock: false,
-                        logo: false,
-                        num_lock: false,
-                    },
-                ) if // `' '` is a placeholder for some key that isn't in tree traverse
-                    key.to_char()==Some('P') =>
-                {
-                    // Alt + Windows + P does pluck/place
-
-                    let (focusing_index, plucked) = match self.mode {
-                        Mode::Normal => {
-                            (&self.tabs
-                                .get_tab_path(&self.tabs.get_focused_tab_id())
-                                .unwrap(), None)
-                        },
-                        Mode::ChoosingFocus { ref focusing_index, ref mut plucked } => (focusing_index, plucked.take()),
-                    };
+                        dbg!(&self.mode);
+                        // dbg!(&self.focused_tab_path);
+                    } else if let Some(traversal) = self.keymap.get_tree_traversal(
+                        key,
+                        key_modifiers,
+                        KeyModifiers::ALT | KeyModifiers::LOGO,
+                    ) {
+                        // Alt + Windows + traversal key swaps position of focused and what would be the new focused
 
-                    if let Some(plucked) = plucked {
-                        // place
-                        self.tabs.org_place(plucked, self.tabs.get_id_by_org_path(focusing_index).unwrap());
-                    } else {
-                        // pluck
-                        if !focusing_index.is_root() {
-                            self.mode = Mode::ChoosingFocus {
-                                focusing_index: focusing_index.traverse_to_parent().unwrap(),
-                                plucked: self.tabs.org_pluck(&self.tabs.get_id_by_org_path(focusing_index).unwrap())
-                            };
+                        let (prev_focus_index, plucked) = match &self.mode {
+                            Mode::Normal => (
+                                &self
+                                    .tabs
+                                    .get_tab_path(&self.tabs.get_focused_tab_id())
+                                    .unwrap(),
+                                &None,
+                            ),
+                            Mode::ChoosingFocus {
+                                focusing_index,
+                                plucked,
+                            } => (focusing_index, plucked),
+                        };
+
+                        let new_focus_index =
+                            prev_focus_index.clamped_traverse(&self.tabs, traversal);
+
+                        self.tabs.org_swap([
+                            self.tabs.get_id_by_org_path(prev_focus_index).unwrap(),
+                            self.tabs.get_id_by_org_path(&new_focus_index).unwrap(),
+                        ]);
+
+                        // self.tabs.set_focused_tab_path(&new_focus_index);
+                        self.mode = Mode::ChoosingFocus {
+                            focusing_index: new_focus_index,
+                            plucked: plucked.clone(),
+                        };
+
+                        dbg!(&self.mode);
+                    } else if self
+                        .tabs
+                        .get_tab_handler(self.tabs.get_focused_tab_id())
+                        .unwrap()
+                        .is_crashed()
+                    {
+                        // a crashed tab can only be restarted or closed
+                        match self.keymap.get_action(&["crashed_tab"], key, key_modifiers) {
+                            Some("crashed_tab.restart") => {
+                                self.restart_focused_tab();
+                            }
+                            Some("crashed_tab.close") => {
+                                self.close_focused_tab(ChildrenOnClose::Close);
+                            }
+                            _ => {}
                         }
+                    } else {
+                        // forward the event to focused tab
+                        let focused_tab = self.tabs.get_focused_tab_mut();
+
+                        focused_tab
+                            .send_event(singularity_common::tab::packets::Event::UIEvent(ui_event));
                     }
                 }
-                UIEvent::KeyPress(
-                    key,
-                    KeyModifiers {
-                        ctrl: false,
-                        alt: true,
-                        shift: true,
-                        caps_lock: false,
-                        logo: false,
-                        num_lock: false,
-                    },
-                ) if // `' '` is a placeholder for some key that isn't in tree traverse
-                key.to_char()==Some('S') =>
-                {
-                    // Alt + Shift + S swaps actually focused and focusing
-                    
-                    if let Some(focuser_path) = self.mode.try_get_focusing_index().cloned() {
-                        let focusing = self.tabs.get_id_by_org_path(&focuser_path).unwrap();
-                        let actually_focused = self.tabs.get_focused_tab_id();
-
-                        self.tabs.org_swap([focusing, actually_focused]);
-
-                        // self.app_focuser_index = Some(todo!());
-                    }
-                }
-                // UIEvent::KeyPress(key, KeyModifiers::ALT) if key.raw_code == 103 => {
-                //     // Alt+ArrowUp
-                //     // TODO: figure out why Ctrl+Shift+ArrowUp specifically doesn't work...
-
-                //     // maximize focused tab
-                //     let focused_tab = self.tabs.get_focused_tab_mut();
-
-                //     focused_tab.set_area(DisplayArea::from_corner_size(
-                //         DisplayCoord::new(DisplayUnits::ZERO, DisplayUnits::ZERO),
-                //         DisplaySize::new(DisplayUnits::FULL, DisplayUnits::FULL),
-                //     ));
-                // }
-                // UIEvent::KeyPress(key, KeyModifiers::ALT) if key.raw_code == 108 => {
-                //     // Alt+ArrowDown
-                //     self.tabs.minimize_focused_tab();
-                // }
-                UIEvent::KeyPress(key, KeyModifiers::LOGO) if key.raw_code == 103 => {
-                    // LOGO+ArrowUp
-                }
-                UIEvent::KeyPress(key, KeyModifiers::LOGO) if key.to_char() == Some('=') => {
-                    // LOGO+"=" (but it represents "+")
-                    // TODO: increment tile split
-                }
-                UIEvent::KeyPress(key, KeyModifiers::LOGO) if key.to_char() == Some('t') => {
-                    // "T"ranspose selected tile's container (change horizontal vs vertical)
-                    self.tabs.transpose_focused_tile_parent();
-                }
-                UIEvent::KeyPress(key, KeyModifiers::LOGO) if key.to_char() == Some('s') => {
-                    // "S"wap selected tile's siblings
-                    self.tabs.swap_focused_tile_siblings();
+                UIEvent::WindowResized(ui_window_px) => {
+                    self.ui_window_px = ui_window_px;
                 }
-                UIEvent::KeyPress(key, KeyModifiers::CTRL) if key.to_char() == Some('w') => {
-                    println!("Deletin");
-                    self.tabs.close_focused_tab_recursively();
-                }
-                UIEvent::KeyPress(_, _) => {
-                    // forward the event to focused tab
-                    let focused_tab = self.tabs.get_focused_tab_mut();
-
-                    focused_tab
-                        .send_event(singularity_common::tab::packets::Event::UIEvent(ui_event));
-                }
-                UIEvent::WindowResized(_ui_window_px) => {
-                    // self.ui_window_px = ui_window_px;
+                UIEvent::CloseRequested => {
+                    self.request_quit();
                 }
                 UIEvent::MousePress([[click_x, click_y], [tot_width, tot_height]], container) => {
                     assert_eq!(container, DisplayArea::FULL);
 
-                    // if pressed on focused tab, then forward the click
+                    // if pressed on a minimized tab in the tray, then restore it
+                    let click_coord =
+                        DisplayCoord::new((click_x as i32).into(), (click_y as i32).into());
+                    if let Some(tray_area) = self.tray_area.filter(|tray_area| {
+                        tray_area.contains(click_coord, [tot_width as i32, tot_height as i32])
+                    }) {
+                        let minimized_tabs = self.tabs.get_overlay().get_minimized();
+                        let tray_x = tray_area.0.x.pixels(tot_width as i32);
+                        let tray_width = tray_area.1.x.pixels(tot_width as i32) - tray_x;
+                        let index = ((click_x as i32 - tray_x) as usize * minimized_tabs.len()
+                            / tray_width.max(1) as usize)
+                            .min(minimized_tabs.len().saturating_sub(1));
+
+                        if let Some(minimized_tab) = minimized_tabs.get(index).copied() {
+                            self.tabs.restore_minimized(minimized_tab.tab_id);
+                        }
+                        continue;
+                    }
+
+                    // the tab on top, if it is floating or maximized, is above everything under it
+                    let clicked_tab_id = self
+                        .tabs
+                        .get_tab_at(click_coord, [tot_width as i32, tot_height as i32]);
+                    let clicked_tiled_tab = clicked_tab_id.is_some_and(|tab_id| {
+                        self.tabs.get_overlay().get_maximized().is_none()
+                            && self.tabs.get_overlay().get_floating_area(tab_id).is_none()
+                    });
+
+                    // if pressed on a tabbed container's title, then show (and focus) that tab
+                    // (before boundaries, since a title strip can be right next to one)
+                    let clicked_title_strip = self
+                        .title_strips
+                        .iter()
+                        .find(|(title_strip_area, _)| {
+                            title_strip_area
+                                .contains(click_coord, [tot_width as i32, tot_height as i32])
+                        })
+                        .filter(|_| clicked_tiled_tab || clicked_tab_id.is_none());
+                    if let Some((title_strip_area, container_tile_id)) =
+                        clicked_title_strip.copied()
                     {
-                        let focused_tab = self
+                        let children_len = self
                             .tabs
-                            .get_tab_handler(self.tabs.get_focused_tab_id())
-                            .unwrap();
-                        if focused_tab.get_area().map_onto(container).contains(
-                            DisplayCoord::new((click_x as i32).into(), (click_y as i32).into()),
-                            [tot_width as i32, tot_height as i32],
-                        ) {
+                            .get_display_tiles()
+                            .get_tile(container_tile_id)
+                            .and_then(Tile::try_as_container)
+                            .map_or(0, |container| container.children.len());
+                        let strip_x = title_strip_area.0.x.pixels(tot_width as i32);
+                        let strip_width = title_strip_area.1.x.pixels(tot_width as i32) - strip_x;
+                        let index = ((click_x as i32 - strip_x) as usize * children_len
+                            / strip_width.max(1) as usize)
+                            .min(children_len.saturating_sub(1));
+
+                        if let Some(tab_id) = self
+                            .tabs
+                            .get_display_tiles_mut()
+                            .select_tabbed_child(container_tile_id, index)
+                        {
+                            self.tabs.set_focused_tab_id(tab_id);
+                        }
+                        continue;
+                    }
+
+                    // if pressed on the boundary between tiles, then start dragging it instead
+                    if clicked_tiled_tab || clicked_tab_id.is_none() {
+                        let tiles_height =
+                            self.get_tiles_area().1.y.pixels(tot_height as i32).max(1) as f32;
+                        self.dragged_boundary = self.tabs.get_display_tiles().find_boundary(
+                            self.to_tiles_point([click_x, click_y], [tot_width, tot_height]),
+                            [
+                                BOUNDARY_GRAB_PX / tot_width as f32,
+                                BOUNDARY_GRAB_PX / tiles_height,
+                            ],
+                            CELL_HEIGHT as f32 / tiles_height,
+                        );
+                        if self.dragged_boundary.is_some() {
+                            continue;
+                        }
+                    }
+
+                    // if pressed on focused tab, then forward the click
+                    // otherwise, make the pressed tab focused
+                    // (hidden tabs of tabbed containers still have their old area, so they don't count)
+                    match clicked_tab_id {
+                        Some(tab_id) if tab_id == self.tabs.get_focused_tab_id() => {
+                            let focused_tab = self.tabs.get_tab_handler(tab_id).unwrap();
                             focused_tab.send_event(
                                 singularity_common::tab::packets::Event::UIEvent(
                                     singularity_ui::ui_event::UIEvent::MousePress(
@@ -481,22 +1337,29 @@ impl ProjectManager {
                                 ),
                             );
                         }
-                    }
-
-                    // if pressed on unfocused tab, make that focused
-                    for tab_id in self.tabs.collect_tab_ids().iter().rev() {
-                        let tab = self.tabs.get_tab_handler(*tab_id).unwrap();
-                        let tab_area = tab.get_area();
-
-                        if tab_area.map_onto(container).contains(
-                            DisplayCoord::new((click_x as i32).into(), (click_y as i32).into()),
-                            [tot_width as i32, tot_height as i32],
-                        ) {
-                            self.tabs.set_focused_tab_id(*tab_id);
-                            break;
+                        Some(tab_id) => {
+                            self.tabs.set_focused_tab_id(tab_id);
                         }
+                        None => {}
                     }
                 }
+                UIEvent::MouseMove([[mouse_x, mouse_y], [tot_width, tot_height]], _container) => {
+                    if let Some((container_tile_id, boundary_index, container_area)) =
+                        self.dragged_boundary
+                    {
+                        let mouse_point =
+                            self.to_tiles_point([mouse_x, mouse_y], [tot_width, tot_height]);
+                        self.tabs.get_display_tiles_mut().drag_boundary(
+                            container_tile_id,
+                            boundary_index,
+                            container_area,
+                            mouse_point,
+                        );
+                    }
+                }
+                UIEvent::MouseRelease(..) => {
+                    self.dragged_boundary = None;
+                }
             }
         }
     }
@@ -532,6 +1395,43 @@ impl ProjectManager {
                                     self.tabs.num_tabs(),
                                     requestor_path.depth() + 1,
                                 ),
+                                self.manager_wakeup.clone(),
+                            ),
+                            &self.tabs.get_id_by_org_path(&requestor_path).unwrap(),
+                        );
+                    }
+                    Request::UpdateSessionData(session_data) => {
+                        self.tabs
+                            .get_mut_tab_handler(
+                                self.tabs.get_id_by_org_path(&requestor_path).unwrap(),
+                            )
+                            .unwrap()
+                            .set_session_data(session_data);
+                    }
+                    Request::CloseResponse(close_response) => {
+                        let requestor_id = self.tabs.get_id_by_org_path(&requestor_path).unwrap();
+                        self.handle_close_response(requestor_id, close_response);
+                    }
+                    Request::SessionDataUpToDate => {
+                        let requestor_id = self.tabs.get_id_by_org_path(&requestor_path).unwrap();
+                        self.awaiting_session_data
+                            .retain(|tab_id| *tab_id != requestor_id);
+                    }
+                    Request::FileSystem(fs_request) => {
+                        let requestor_id = self.tabs.get_id_by_org_path(&requestor_path).unwrap();
+                        self.handle_fs_request(requestor_id, fs_request);
+                    }
+                    Request::SpawnRegisteredChildTab(tab_data) => {
+                        self.tabs.add(
+                            TabHandler::new(
+                                self.tab_registry
+                                    .get_tab_creator_or_placeholder(&tab_data.tab_type),
+                                tab_data,
+                                Self::generate_tab_area(
+                                    self.tabs.num_tabs(),
+                                    requestor_path.depth() + 1,
+                                ),
+                                self.manager_wakeup.clone(),
                             ),
                             &self.tabs.get_id_by_org_path(&requestor_path).unwrap(),
                         );
@@ -541,20 +1441,140 @@ impl ProjectManager {
         }
     }
 
+    /// Answers right away if the tab has the permission, otherwise asks the user first
+    fn handle_fs_request(&mut self, tab_id: Id<TabHandler>, fs_request: FsRequest) {
+        let path = resolve_path(fs_request.get_path());
+        let access = fs_request.get_access();
+        let tab_handler = self.tabs.get_tab_handler(tab_id).unwrap();
+
+        let is_allowed = self
+            .file_system_grants
+            .contains(&(tab_id, path.clone(), access))
+            || self
+                .project
+                .get_subapp_settings(&tab_handler.get_tab_data().tab_type.to_lowercase())
+                .subapp_file_system_permissions
+                .map(|permissions| permissions.value)
+                .unwrap_or_default()
+                .allows(&path, access, self.project.get_project_directory());
+
+        if is_allowed {
+            self.perform_fs_request(tab_id, fs_request);
+        } else {
+            let tab_name = Self::get_tab_title(tab_handler).to_string();
+            self.permission_prompts
+                .push_back(PermissionPrompt::new(tab_id, tab_name, fs_request));
+        }
+    }
+
+    fn perform_fs_request(&self, tab_id: Id<TabHandler>, fs_request: FsRequest) {
+        // (closed while the user was asked)
+        let Some(tab_handler) = self.tabs.get_tab_handler(tab_id) else {
+            return;
+        };
+
+        let fs_result = file_system::perform(&fs_request);
+        if let (FsRequest::Watch(path), Ok(_)) = (&fs_request, &fs_result) {
+            tab_handler.watch_path(path.clone());
+        }
+        tab_handler.respond_fs(fs_result);
+    }
+
+    fn deny_fs_request(&self, tab_id: Id<TabHandler>, fs_request: &FsRequest) {
+        if let Some(tab_handler) = self.tabs.get_tab_handler(tab_id) {
+            tab_handler.respond_fs(Err(FsError::PermissionDenied(
+                fs_request.get_path().to_path_buf(),
+            )));
+        }
+    }
+
     fn answer_tab_queries(&self) {
         for tab_path in self.tabs.collect_paths_dfs() {
             let inquieror = self
                 .tabs
                 .get_tab_handler(self.tabs.get_id_by_org_path(&tab_path).unwrap())
                 .unwrap();
-            inquieror.get_respond_channels().answer_query(
+            let has_more_queries = inquieror.get_respond_channels().answer_queries(
+                MAX_QUERIES_PER_TAB,
                 move || tab_path.clone(),
                 move || inquieror.tab_name.clone(),
                 move || inquieror.get_tab_data().clone(),
+                || self.keymap.clone(),
+                move || {
+                    self.project
+                        .get_subapp_settings(&inquieror.get_tab_data().tab_type.to_lowercase())
+                },
+                || self.project.get_project_directory().to_path_buf(),
+                || {
+                    self.tabs
+                        .collect_paths_dfs()
+                        .into_iter()
+                        .filter_map(|org_path| self.get_tab_info(org_path))
+                        .collect()
+                },
+                || {
+                    let focused_path = self
+                        .tabs
+                        .get_tab_path(&self.tabs.get_focused_tab_id())
+                        .unwrap();
+                    self.get_tab_info(focused_path).unwrap()
+                },
+                |org_path| {
+                    self.tabs
+                        .get_id_by_org_path(&org_path)
+                        .and_then(|tab_id| self.tabs.get_tab_handler(tab_id))
+                        .map(|tab_handler| tab_handler.get_tab_data().clone())
+                        .ok_or(QueryError::NoSuchTab(org_path))
+                },
             );
+
+            // the rest wait for the next loop, after the other tabs had their turn
+            if has_more_queries {
+                self.manager_wakeup.notify();
+            }
         }
     }
 
+    fn get_tab_info(&self, org_path: TreeNodePath) -> Option<TabInfo> {
+        let tab_handler = self
+            .tabs
+            .get_tab_handler(self.tabs.get_id_by_org_path(&org_path)?)?;
+
+        Some(TabInfo {
+            org_path,
+            tab_name: tab_handler.tab_name.clone(),
+            tab_type: tab_handler.get_tab_data().tab_type.clone(),
+        })
+    }
+
+    /// Spawns a tab as a child of the focused tab (and focuses it)
+    fn launch_tab(&mut self, tab_data: TabData) {
+        let parent_id = self.tabs.get_focused_tab_id();
+        let parent_path = self.tabs.get_tab_path(&parent_id).unwrap();
+
+        self.tabs.add(
+            TabHandler::new(
+                self.tab_registry
+                    .get_tab_creator_or_placeholder(&tab_data.tab_type),
+                tab_data,
+                Self::generate_tab_area(self.tabs.num_tabs(), parent_path.depth() + 1),
+                self.manager_wakeup.clone(),
+            ),
+            &parent_id,
+        );
+    }
+
+    /// Starts the focused tab over from its `TabData` (for after it crashed)
+    fn restart_focused_tab(&mut self) {
+        let focused_tab = self.tabs.get_focused_tab_mut();
+
+        focused_tab.restart(
+            self.tab_registry
+                .get_tab_creator_or_placeholder(&focused_tab.get_tab_data().tab_type),
+        );
+        focused_tab.send_event(singularity_common::tab::packets::Event::Focused);
+    }
+
     /// TODO: now, with tiling, I don't need this
     fn generate_tab_area(child_index: usize, depth: usize) -> DisplayArea {
         const WIDTH: f32 = 0.5;
@@ -0,0 +1,74 @@
+//! Overlay for asking the user about a tab using files it doesn't have permission for
+//! (see `SubappFileSystemPermissions`). The tab waits until it's answered.
+
+use singularity_common::{
+    keymap::Keymap,
+    tab::{
+        file_system::{FileSystemAccess, FsRequest},
+        TabHandler,
+    },
+    utils::id_map::Id,
+};
+use singularity_ui::{
+    color::Color,
+    display_units::DisplayArea,
+    ui_element::{CharGrid, UIElement},
+    ui_event::{Key, KeyModifiers},
+};
+
+pub enum PermissionPromptOutcome {
+    AllowOnce,
+    /// for the same path and access, until the tab is closed
+    AllowAlways,
+    Deny,
+}
+
+pub struct PermissionPrompt {
+    pub tab_id: Id<TabHandler>,
+    pub fs_request: FsRequest,
+    tab_name: String,
+}
+impl PermissionPrompt {
+    pub fn new(tab_id: Id<TabHandler>, tab_name: String, fs_request: FsRequest) -> Self {
+        Self {
+            tab_id,
+            fs_request,
+            tab_name,
+        }
+    }
+
+    /// Returns None while the prompt should stay open
+    pub fn handle_key(
+        &self,
+        key: Key,
+        key_modifiers: KeyModifiers,
+        keymap: &Keymap,
+    ) -> Option<PermissionPromptOutcome> {
+        match keymap.get_action(&["permission_prompt"], &key, key_modifiers) {
+            Some("permission_prompt.allow_once") => Some(PermissionPromptOutcome::AllowOnce),
+            Some("permission_prompt.allow_always") => Some(PermissionPromptOutcome::AllowAlways),
+            Some("permission_prompt.deny") => Some(PermissionPromptOutcome::Deny),
+            _ => None,
+        }
+    }
+
+    pub fn render(&self, keymap: &Keymap) -> UIElement {
+        let access = match self.fs_request.get_access() {
+            FileSystemAccess::Read => "read",
+            FileSystemAccess::Write => "write",
+            FileSystemAccess::Execute => "execute",
+        };
+
+        UIElement::CharGrid(CharGrid::from(format!(
+            "`{}` wants to {access}:\n{}\n{}: allow once\n{}: always allow (for this tab)\n{}: deny",
+            self.tab_name,
+            self.fs_request.get_path().display(),
+            keymap.describe_shortcuts("permission_prompt.allow_once"),
+            keymap.describe_shortcuts("permission_prompt.allow_always"),
+            keymap.describe_shortcuts("permission_prompt.deny"),
+        )))
+        .fill_bg(Color::DARK_GRAY)
+        .bordered(Color::LIGHT_YELLOW)
+        .contain(DisplayArea::new((0.2, 0.35), (0.8, 0.65)))
+    }
+}
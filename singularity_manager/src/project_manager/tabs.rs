@@ -1,12 +1,46 @@
 use singularity_common::{
-    project::{project_settings::TabData, Project},
-    tab::{tile::Tiles, TabHandler},
+    project::{
+        project_settings::{default_workspace_name, TabData, Workspace},
+        Project,
+    },
+    tab::{
+        overlay::{Overlay, DEFAULT_FLOATING_AREA},
+        registry::TabRegistry,
+        tile::{LayoutPolicy, Tiles},
+        TabHandler,
+    },
     utils::{
         id_map::{Id, IdMap},
         tree::{id_tree::IdTree, tree_node_path::TreeNodePath},
     },
 };
-use singularity_ui::display_units::DisplayArea;
+use singularity_ui::{
+    display_units::{DisplayArea, DisplayCoord},
+    wakeup::Wakeup,
+};
+
+/// How far `Tabs::move_focused_tile` moves floating tabs (in proportions of the window)
+const FLOATING_STEP: f32 = 0.05;
+
+/// On screen, for moving between tiles (unlike the org tree traversals)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// What happens to the children of a closed tab (see the DEVLOG's Hierarchy Operations)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChildrenOnClose {
+    /// they are closed too
+    Close,
+    /// they are given to the closed tab's parent
+    ReattachToParent,
+    /// the first child takes the closed tab's place, and gets the other children
+    PromoteFirstChild,
+}
 
 /// NOTE: `org` prefix in front of variable stands for `ORGanizational`.
 /// REVIEW: currently, must have at least one tab. change?
@@ -29,10 +63,31 @@ pub struct Tabs {
     // /// currently, last in vec is "top" in gui
     // display_order: Vec<Uuid>,
     display_tiles: Tiles,
+    /// floating, minimized, and maximized tabs, on top of `display_tiles`
+    overlay: Overlay,
+    /// NOTE: `focused_tab`, `display_tiles`, and `overlay` are the active workspace's
+    workspace_name: String,
+    /// the other workspaces, in order (the active one goes at `active_workspace_index` among them)
+    inactive_workspaces: Vec<Workspace>,
+    active_workspace_index: usize,
+    /// the project's, see `ProjectSettings::layout_policy`
+    default_layout_policy: LayoutPolicy,
+    /// picked for this session, overrides the project's
+    chosen_layout_policy: Option<LayoutPolicy>,
 }
 impl Tabs {
-    pub fn parse_from_project(project: &Project) -> Self {
-        if let Some(open_tabs) = project.get_project_settings().open_tabs.clone() {
+    /// Tabs with types that aren't in `tab_registry` are opened as placeholders
+    pub fn parse_from_project(
+        project: &Project,
+        tab_registry: &TabRegistry,
+        manager_wakeup: &Wakeup,
+    ) -> Self {
+        let default_layout_policy = project
+            .get_project_settings()
+            .layout_policy
+            .unwrap_or_default();
+
+        if let Some(open_tabs) = project.session.clone() {
             Self {
                 tabs: open_tabs
                     .tabs
@@ -41,11 +96,12 @@ impl Tabs {
                         (
                             uuid::Uuid::from(id).into(),
                             TabHandler::new(
-                                singularity_standard_tabs::get_tab_creator_from_type(
+                                tab_registry.get_tab_creator_or_placeholder(
                                     open_tab.tab_data.tab_type.as_str(),
                                 ),
                                 open_tab.tab_data,
                                 open_tab.tab_area,
+                                manager_wakeup.clone(),
                             ),
                         )
                     })
@@ -53,6 +109,12 @@ impl Tabs {
                 org_tree: open_tabs.org_tree,
                 focused_tab: open_tabs.focused_tab,
                 display_tiles: open_tabs.display_tiles,
+                overlay: open_tabs.overlay,
+                workspace_name: open_tabs.workspace_name,
+                inactive_workspaces: open_tabs.inactive_workspaces,
+                active_workspace_index: open_tabs.active_workspace_index,
+                default_layout_policy,
+                chosen_layout_policy: open_tabs.layout_policy,
             }
         } else {
             // create new project
@@ -69,6 +131,7 @@ impl Tabs {
                         .unwrap(),
                 },
                 DisplayArea::new((0., 0.), (0.5, 1.)),
+                manager_wakeup.clone(),
             ));
 
             tabs.add(
@@ -80,9 +143,11 @@ impl Tabs {
                             .unwrap(),
                     },
                     DisplayArea::new((0.5, 0.), (1.0, 1.)),
+                    manager_wakeup.clone(),
                 ),
                 &tabs.get_root_id(),
             );
+            tabs.default_layout_policy = default_layout_policy;
 
             tabs
         }
@@ -97,6 +162,12 @@ impl Tabs {
             org_tree: IdTree::new(root_id),
             focused_tab: root_id,
             display_tiles: Tiles::new_from_root(root_id),
+            overlay: Overlay::default(),
+            workspace_name: default_workspace_name(),
+            inactive_workspaces: Vec::new(),
+            active_workspace_index: 0,
+            default_layout_policy: LayoutPolicy::default(),
+            chosen_layout_policy: None,
         }
     }
 
@@ -116,7 +187,8 @@ impl Tabs {
         // add to `tabs`
         self.tabs.insert(uuid, new_tab);
         // add to top of display order
-        self.display_tiles.give_sibling(self.focused_tab, uuid);
+        self.display_tiles
+            .add_tab(self.get_tiled_anchor(), uuid, self.get_layout_policy());
 
         // set focus to new tabs
         // REVIEW: is this bad?
@@ -125,6 +197,394 @@ impl Tabs {
         Some(uuid)
     }
 
+    pub fn get_layout_policy(&self) -> LayoutPolicy {
+        self.chosen_layout_policy
+            .unwrap_or(self.default_layout_policy)
+    }
+
+    /// Rearranges the current (tiled) tabs, and keeps using the policy for new ones
+    pub fn relayout(&mut self, layout_policy: LayoutPolicy) {
+        self.chosen_layout_policy = Some(layout_policy);
+        self.display_tiles
+            .relayout(layout_policy, self.get_tiled_anchor());
+    }
+
+    pub fn get_overlay(&self) -> &Overlay {
+        &self.overlay
+    }
+
+    fn is_tiled(&self, tab_id: Id<TabHandler>) -> bool {
+        self.display_tiles.get_leaf_tile_id(tab_id).is_some()
+    }
+
+    /// The focused tab if it is tiled, otherwise a shown tiled tab.
+    /// New tiles go next to it.
+    fn get_tiled_anchor(&self) -> Id<TabHandler> {
+        if self.is_tiled(self.focused_tab) {
+            self.focused_tab
+        } else {
+            self.display_tiles
+                .first_visible_leaf(self.display_tiles.get_root_tile())
+        }
+    }
+
+    /// Whether it is drawn at all
+    pub fn is_tab_shown(&self, tab_id: Id<TabHandler>) -> bool {
+        if let Some(maximized_tab_id) = self.overlay.get_maximized() {
+            return tab_id == maximized_tab_id;
+        }
+
+        self.overlay.get_floating_area(tab_id).is_some()
+            || self.display_tiles.is_leaf_visible(tab_id)
+    }
+
+    /// The shown tab that is on top at that point (floating tabs are above the tiles)
+    pub fn get_tab_at(&self, coord: DisplayCoord, window_px: [i32; 2]) -> Option<Id<TabHandler>> {
+        let floating_tab_ids = self
+            .overlay
+            .get_floating()
+            .iter()
+            .rev()
+            .map(|floating_tab| floating_tab.tab_id);
+        let tiled_tab_ids = self.display_tiles.collect_leaves();
+
+        self.overlay
+            .get_maximized()
+            .into_iter()
+            .chain(floating_tab_ids)
+            .chain(tiled_tab_ids)
+            .find(|tab_id| {
+                self.is_tab_shown(*tab_id)
+                    && self.tabs[tab_id].get_area().contains(coord, window_px)
+            })
+    }
+
+    /// Maximizes the focused tab, or puts it back if it already is
+    pub fn toggle_focused_maximized(&mut self) {
+        if self.overlay.get_maximized() == Some(self.focused_tab) {
+            self.overlay.set_maximized(None);
+        } else if !self.overlay.is_minimized(self.focused_tab) {
+            self.overlay.set_maximized(Some(self.focused_tab));
+        }
+    }
+
+    /// Floats the focused tab above the tiles, or puts it back into the tiles
+    pub fn toggle_focused_floating(&mut self) {
+        if self.overlay.unfloat(self.focused_tab).is_some() {
+            self.display_tiles.add_tab(
+                self.get_tiled_anchor(),
+                self.focused_tab,
+                self.get_layout_policy(),
+            );
+            self.display_tiles.show_leaf(self.focused_tab);
+        } else if self.is_tiled(self.focused_tab) {
+            if self.display_tiles.collect_leaves().len() == 1 {
+                println!("Can't float the last tiled tab");
+                return;
+            }
+
+            self.display_tiles.remove(self.focused_tab);
+            self.overlay.float(self.focused_tab, DEFAULT_FLOATING_AREA);
+        }
+    }
+
+    /// Hides the focused tab in the tray, and focuses the top one instead
+    pub fn minimize_focused(&mut self) {
+        let minimized_tab_id = self.focused_tab;
+        if self.overlay.is_minimized(minimized_tab_id) {
+            return;
+        }
+
+        let floating_area = self.overlay.unfloat(minimized_tab_id);
+        if floating_area.is_none() {
+            if self.display_tiles.collect_leaves().len() == 1 {
+                println!("Can't minimize the last tiled tab");
+                return;
+            }
+            self.display_tiles.remove(minimized_tab_id);
+        }
+        self.overlay.minimize(minimized_tab_id, floating_area);
+
+        let new_focused_tab_id = match self.overlay.get_floating().last() {
+            Some(floating_tab) => floating_tab.tab_id,
+            None => self.get_tiled_anchor(),
+        };
+        self.set_focused_tab_id(new_focused_tab_id);
+    }
+
+    /// Takes the tab out of the tray (and focuses it)
+    pub fn restore_minimized(&mut self, tab_id: Id<TabHandler>) {
+        if self.overlay.is_minimized(tab_id) {
+            self.set_focused_tab_id(tab_id);
+        }
+    }
+
+    /// The most recently minimized one
+    pub fn restore_last_minimized(&mut self) {
+        if let Some(minimized_tab) = self.overlay.get_minimized().last() {
+            self.restore_minimized(minimized_tab.tab_id);
+        }
+    }
+
+    /// Puts a minimized tab back where it was: floating, or in the tiles next to the focused tab
+    fn unminimize(&mut self, tab_id: Id<TabHandler>) {
+        let Some(minimized_tab) = self.overlay.unminimize(tab_id) else {
+            return;
+        };
+
+        match minimized_tab.floating_area {
+            Some(floating_area) => self.overlay.float(tab_id, floating_area),
+            None => {
+                self.display_tiles.add_tab(
+                    self.get_tiled_anchor(),
+                    tab_id,
+                    self.get_layout_policy(),
+                );
+            }
+        }
+    }
+
+    /// Takes the tab out of whichever workspace it is in, for closing it.
+    /// A workspace without any tabs left is removed.
+    ///
+    /// NOTE: if that was the active workspace, the focused tab is left as is (the closed one),
+    /// so that something else is focused after.
+    fn remove_from_display(&mut self, tab_id: Id<TabHandler>) {
+        if Self::workspace_contains(&self.display_tiles, &self.overlay, tab_id) {
+            if !Self::remove_from_workspace(&mut self.display_tiles, &mut self.overlay, tab_id) {
+                if self.inactive_workspaces.is_empty() {
+                    println!("Tried to remove the last tab from the tiles");
+                } else {
+                    let removed_workspace_tab_id = self.focused_tab;
+                    self.swap_in_workspace(self.get_neighbor_workspace_index(), false);
+                    self.focused_tab = removed_workspace_tab_id;
+                }
+            }
+            return;
+        }
+
+        let Some(position) = self.inactive_workspaces.iter().position(|workspace| {
+            Self::workspace_contains(&workspace.display_tiles, &workspace.overlay, tab_id)
+        }) else {
+            return;
+        };
+        let workspace = &mut self.inactive_workspaces[position];
+        if !Self::remove_from_workspace(
+            &mut workspace.display_tiles,
+            &mut workspace.overlay,
+            tab_id,
+        ) {
+            self.inactive_workspaces.remove(position);
+            if position < self.active_workspace_index {
+                self.active_workspace_index -= 1;
+            }
+        } else if workspace.focused_tab == tab_id {
+            workspace.focused_tab = workspace
+                .display_tiles
+                .first_visible_leaf(workspace.display_tiles.get_root_tile());
+        }
+    }
+
+    fn workspace_contains(
+        display_tiles: &Tiles,
+        overlay: &Overlay,
+        tab_id: Id<TabHandler>,
+    ) -> bool {
+        display_tiles.get_leaf_tile_id(tab_id).is_some() || overlay.contains(tab_id)
+    }
+
+    /// The tiles can't be empty, so if it was the last tiled tab, an overlay tab takes its place.
+    /// Returns false if it was the last tab of the workspace (so it is still there).
+    fn remove_from_workspace(
+        display_tiles: &mut Tiles,
+        overlay: &mut Overlay,
+        tab_id: Id<TabHandler>,
+    ) -> bool {
+        overlay.remove(tab_id);
+        if display_tiles.get_leaf_tile_id(tab_id).is_none() {
+            return true;
+        }
+
+        if display_tiles.collect_leaves().len() > 1 {
+            display_tiles.remove(tab_id);
+            return true;
+        }
+
+        let replacement_tab_id = overlay
+            .get_floating()
+            .last()
+            .map(|floating_tab| floating_tab.tab_id)
+            .or_else(|| {
+                overlay
+                    .get_minimized()
+                    .last()
+                    .map(|minimized_tab| minimized_tab.tab_id)
+            });
+        match replacement_tab_id {
+            Some(replacement_tab_id) => {
+                overlay.remove(replacement_tab_id);
+                display_tiles.replace_leaf(tab_id, replacement_tab_id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Every workspace's name, in order
+    pub fn get_workspace_names(&self) -> Vec<&str> {
+        let mut workspace_names: Vec<&str> = self
+            .inactive_workspaces
+            .iter()
+            .map(|workspace| workspace.name.as_str())
+            .collect();
+        workspace_names.insert(self.active_workspace_index, &self.workspace_name);
+
+        workspace_names
+    }
+
+    pub fn get_workspace_name(&self) -> &str {
+        &self.workspace_name
+    }
+
+    pub fn get_active_workspace_index(&self) -> usize {
+        self.active_workspace_index
+    }
+
+    /// The index (among all of them) of the workspace that has the tab
+    fn find_workspace_index(&self, tab_id: Id<TabHandler>) -> Option<usize> {
+        if Self::workspace_contains(&self.display_tiles, &self.overlay, tab_id) {
+            return Some(self.active_workspace_index);
+        }
+
+        let position = self.inactive_workspaces.iter().position(|workspace| {
+            Self::workspace_contains(&workspace.display_tiles, &workspace.overlay, tab_id)
+        })?;
+        Some(if position < self.active_workspace_index {
+            position
+        } else {
+            position + 1
+        })
+    }
+
+    /// The one before the active workspace (or after, if it is the first).
+    /// NOTE: there has to be more than one workspace
+    fn get_neighbor_workspace_index(&self) -> usize {
+        if self.active_workspace_index == 0 {
+            1
+        } else {
+            self.active_workspace_index - 1
+        }
+    }
+
+    /// Makes the workspace (index among all of them) active, without any focus events.
+    /// The active one is kept if `keep_active`, otherwise it is thrown away.
+    fn swap_in_workspace(&mut self, index: usize, keep_active: bool) {
+        let position = if index < self.active_workspace_index {
+            index
+        } else {
+            index - 1
+        };
+        let new_workspace = self.inactive_workspaces.remove(position);
+
+        let old_workspace = Workspace {
+            name: std::mem::replace(&mut self.workspace_name, new_workspace.name),
+            focused_tab: std::mem::replace(&mut self.focused_tab, new_workspace.focused_tab),
+            display_tiles: std::mem::replace(&mut self.display_tiles, new_workspace.display_tiles),
+            overlay: std::mem::replace(&mut self.overlay, new_workspace.overlay),
+        };
+        if keep_active {
+            let old_position = if self.active_workspace_index < index {
+                self.active_workspace_index
+            } else {
+                self.active_workspace_index - 1
+            };
+            self.inactive_workspaces.insert(old_position, old_workspace);
+            self.active_workspace_index = index;
+        } else {
+            self.active_workspace_index = position;
+        }
+    }
+
+    /// Focuses the workspace's last focused tab
+    pub fn switch_workspace(&mut self, index: usize) {
+        if index == self.active_workspace_index {
+            return;
+        }
+
+        let position = if index < self.active_workspace_index {
+            index
+        } else {
+            index - 1
+        };
+        if let Some(workspace) = self.inactive_workspaces.get(position) {
+            self.set_focused_tab_id(workspace.focused_tab);
+        }
+    }
+
+    /// Switches to the next (or previous, if `offset` is negative) workspace, wrapping around
+    pub fn cycle_workspace(&mut self, offset: isize) {
+        let workspaces_len = self.inactive_workspaces.len() as isize + 1;
+        let index = (self.active_workspace_index as isize + offset).rem_euclid(workspaces_len);
+
+        self.switch_workspace(index as usize);
+    }
+
+    /// Moves the focused tab into a new workspace after the active one, and switches to it
+    pub fn create_workspace(&mut self, name: String) {
+        let moved_tab_id = self.focused_tab;
+        if !Self::remove_from_workspace(&mut self.display_tiles, &mut self.overlay, moved_tab_id) {
+            println!("Can't move the only tab out of the workspace");
+            return;
+        }
+        self.focused_tab = self.get_tiled_anchor();
+
+        // right after the active one, so it is at `active_workspace_index + 1` among all
+        self.inactive_workspaces.insert(
+            self.active_workspace_index,
+            Workspace {
+                name,
+                focused_tab: moved_tab_id,
+                display_tiles: Tiles::new_from_root(moved_tab_id),
+                overlay: Overlay::default(),
+            },
+        );
+        self.switch_workspace(self.active_workspace_index + 1);
+    }
+
+    pub fn rename_workspace(&mut self, name: String) {
+        self.workspace_name = name;
+    }
+
+    /// Removes the active workspace and switches to the one before it (or after, if it is the first).
+    /// Its tabs aren't closed, they are moved into that workspace instead.
+    pub fn delete_workspace(&mut self) {
+        if self.inactive_workspaces.is_empty() {
+            println!("Can't delete the only workspace");
+            return;
+        }
+
+        let deleted_tiles = self.display_tiles.clone();
+        let deleted_overlay = self.overlay.clone();
+        let focused_tab_id = self.focused_tab;
+        self.swap_in_workspace(self.get_neighbor_workspace_index(), false);
+
+        for tab_id in deleted_tiles.collect_leaves() {
+            self.display_tiles
+                .add_tab(self.get_tiled_anchor(), tab_id, self.get_layout_policy());
+        }
+        for floating_tab in deleted_overlay.get_floating() {
+            self.overlay.float(floating_tab.tab_id, floating_tab.area);
+        }
+        for minimized_tab in deleted_overlay.get_minimized() {
+            self.overlay
+                .minimize(minimized_tab.tab_id, minimized_tab.floating_area);
+        }
+
+        // (nothing was unfocused)
+        let new_focused_tab_id = std::mem::replace(&mut self.focused_tab, focused_tab_id);
+        self.set_focused_tab_id(new_focused_tab_id);
+    }
+
     pub fn get_tab_handler(&self, uuid: Id<TabHandler>) -> Option<&TabHandler> {
         self.tabs.get(&uuid)
     }
@@ -141,28 +601,139 @@ impl Tabs {
         &self.display_tiles
     }
 
+    pub fn get_display_tiles_mut(&mut self) -> &mut Tiles {
+        &mut self.display_tiles
+    }
+
+    /// grows the focused tile (or shrinks it, if `delta` is negative)
+    pub fn resize_focused_tile(&mut self, delta: f32) {
+        if !self.is_tiled(self.focused_tab) {
+            return;
+        }
+        self.display_tiles.resize_leaf(self.focused_tab, delta);
+    }
+
     pub fn transpose_focused_tile_parent(&mut self) {
-        let container_tile_id = self
+        let Some(container_tile_id) = self
             .display_tiles
-            .get_parent_tile_id(
-                self.display_tiles
-                    .get_leaf_tile_id(self.focused_tab)
-                    .unwrap(),
-            )
-            .unwrap();
+            .get_leaf_tile_id(self.focused_tab)
+            .and_then(|tile_id| self.display_tiles.get_parent_tile_id(tile_id))
+        else {
+            return;
+        };
 
         self.display_tiles.transpose_container(container_tile_id);
     }
 
+    /// (see `Tiles::toggle_tabbed`)
+    pub fn toggle_focused_tile_tabbed(&mut self) {
+        if !self.is_tiled(self.focused_tab) {
+            return;
+        }
+        self.display_tiles.toggle_tabbed(self.focused_tab);
+    }
+
+    /// Focuses the next (or previous, if `offset` is negative) tab of the closest tabbed container
+    pub fn cycle_focused_tabbed(&mut self, offset: isize) {
+        if let Some(new_focused_tab_id) = self.display_tiles.cycle_tabbed(self.focused_tab, offset)
+        {
+            self.set_focused_tab_id(new_focused_tab_id);
+        }
+    }
+
+    /// The shown tab that is next to the focused one on screen, like tiling window managers.
+    ///
+    /// Tabs past the focused tab's edge count, and the closest one wins,
+    /// preferring ones that line up with the focused tab.
+    ///
+    /// With `only_tiled`, floating tabs are skipped.
+    pub fn find_tab_in_direction(
+        &self,
+        direction: Direction,
+        window_px: [u32; 2],
+        only_tiled: bool,
+    ) -> Option<Id<TabHandler>> {
+        let window_px = window_px.map(|px| px as i32);
+        // [[x0, y0], [x1, y1]] in pixels
+        let pixel_area = |tab_id: &Id<TabHandler>| {
+            let area = self.tabs[tab_id].get_area();
+            [area.0, area.1]
+                .map(|corner| [corner.x.pixels(window_px[0]), corner.y.pixels(window_px[1])])
+        };
+        let focused_area = pixel_area(&self.focused_tab);
+        let (axis, sign) = match direction {
+            Direction::Left => (0, -1),
+            Direction::Right => (0, 1),
+            Direction::Up => (1, -1),
+            Direction::Down => (1, 1),
+        };
+        let other_axis = 1 - axis;
+
+        self.tabs
+            .keys()
+            .filter(|tab_id| {
+                **tab_id != self.focused_tab
+                    && self.is_tab_shown(**tab_id)
+                    && (!only_tiled || self.is_tiled(**tab_id))
+            })
+            .filter_map(|tab_id| {
+                let area = pixel_area(tab_id);
+                // from the focused tab's edge to the other tab's near edge
+                let gap = if sign > 0 {
+                    area[0][axis] - focused_area[1][axis]
+                } else {
+                    focused_area[0][axis] - area[1][axis]
+                };
+                // REVIEW: a pixel of leeway for rounding
+                if gap < -1 {
+                    return None;
+                }
+
+                let overlap = area[1][other_axis].min(focused_area[1][other_axis])
+                    - area[0][other_axis].max(focused_area[0][other_axis]);
+                let center_distance = ((area[0][other_axis] + area[1][other_axis])
+                    - (focused_area[0][other_axis] + focused_area[1][other_axis]))
+                    .abs();
+
+                Some(((overlap <= 0, gap.max(0), center_distance), *tab_id))
+            })
+            .min_by_key(|(score, _)| *score)
+            .map(|(_, tab_id)| tab_id)
+    }
+
+    /// (see `find_tab_in_direction`)
+    pub fn focus_in_direction(&mut self, direction: Direction, window_px: [u32; 2]) {
+        if let Some(tab_id) = self.find_tab_in_direction(direction, window_px, false) {
+            self.set_focused_tab_id(tab_id);
+        }
+    }
+
+    /// Trades places with the tile next to it on screen, and stays focused.
+    /// Floating tabs are moved a bit instead.
+    pub fn move_focused_tile(&mut self, direction: Direction, window_px: [u32; 2]) {
+        if self.overlay.get_floating_area(self.focused_tab).is_some() {
+            let offset = match direction {
+                Direction::Left => [-FLOATING_STEP, 0.],
+                Direction::Right => [FLOATING_STEP, 0.],
+                Direction::Up => [0., -FLOATING_STEP],
+                Direction::Down => [0., FLOATING_STEP],
+            };
+            self.overlay.move_floating(self.focused_tab, offset);
+        } else if self.is_tiled(self.focused_tab) {
+            if let Some(tab_id) = self.find_tab_in_direction(direction, window_px, true) {
+                self.display_tiles.swap_leaves([self.focused_tab, tab_id]);
+            }
+        }
+    }
+
     pub fn swap_focused_tile_siblings(&mut self) {
-        let container_tile_id = self
+        let Some(container_tile_id) = self
             .display_tiles
-            .get_parent_tile_id(
-                self.display_tiles
-                    .get_leaf_tile_id(self.focused_tab)
-                    .unwrap(),
-            )
-            .unwrap();
+            .get_leaf_tile_id(self.focused_tab)
+            .and_then(|tile_id| self.display_tiles.get_parent_tile_id(tile_id))
+        else {
+            return;
+        };
 
         self.display_tiles.swap_children(container_tile_id);
     }
@@ -176,7 +747,24 @@ impl Tabs {
         if let Some(old_focused_tab) = self.tabs.get(&self.focused_tab) {
             old_focused_tab.send_event(singularity_common::tab::packets::Event::Unfocused);
         }
+        // it might be in another workspace
+        if let Some(index) = self.find_workspace_index(focused_tab_id) {
+            if index != self.active_workspace_index {
+                self.swap_in_workspace(index, true);
+            }
+        }
         self.focused_tab = focused_tab_id;
+        // it should be shown: out of the tray, on top if floating, and in tabbed containers
+        self.unminimize(self.focused_tab);
+        self.overlay.raise(self.focused_tab);
+        self.display_tiles.show_leaf(self.focused_tab);
+        if self
+            .overlay
+            .get_maximized()
+            .is_some_and(|maximized_tab_id| maximized_tab_id != self.focused_tab)
+        {
+            self.overlay.set_maximized(None);
+        }
         // notify new focused tab it is now focused
         self.tabs[&self.focused_tab].send_event(singularity_common::tab::packets::Event::Focused);
 
@@ -229,27 +817,103 @@ impl Tabs {
         self.org_tree.get_root_id()
     }
 
-    /// closes the tab and all its children
+    /// the tab and all its descendants
+    pub fn collect_subtree_ids(&self, id: &Id<TabHandler>) -> Vec<Id<TabHandler>> {
+        let mut subtree_ids = vec![*id];
+        for child_id in self.org_tree.get_children(id) {
+            subtree_ids.extend(self.collect_subtree_ids(child_id));
+        }
+
+        subtree_ids
+    }
+
+    /// removes the tab and all its children, and returns them so they can be closed
     ///
     /// TODO: do this with loop instead?
-    fn close_tab_recursively(&mut self, id: &Id<TabHandler>) {
+    fn remove_tab_recursively(&mut self, id: &Id<TabHandler>) -> Vec<TabHandler> {
+        let mut removed_tabs = Vec::new();
         for child_id in self.org_tree.get_children(id).clone() {
-            self.close_tab_recursively(&child_id);
+            removed_tabs.extend(self.remove_tab_recursively(&child_id));
         }
 
         if self.org_tree.pluck(id).is_some() {
-            self.tabs.remove(id);
-            self.display_tiles.remove(*id);
+            self.remove_from_display(*id);
+            removed_tabs.extend(self.tabs.remove(id));
         } else {
             println!("Tried to close root");
         }
+
+        removed_tabs
+    }
+
+    /// Whether `remove_tab_and_refocus` would do anything.
+    /// Root can only be closed by promoting a child.
+    pub fn can_remove_tab(&self, id: &Id<TabHandler>, children_on_close: ChildrenOnClose) -> bool {
+        match children_on_close {
+            ChildrenOnClose::Close | ChildrenOnClose::ReattachToParent => {
+                self.org_tree.get_parent(id).is_some()
+            }
+            ChildrenOnClose::PromoteFirstChild => !self.org_tree.get_children(id).is_empty(),
+        }
+    }
+
+    /// The tabs that `remove_tab_and_refocus` would remove
+    pub fn collect_removed_ids(
+        &self,
+        id: &Id<TabHandler>,
+        children_on_close: ChildrenOnClose,
+    ) -> Vec<Id<TabHandler>> {
+        match children_on_close {
+            ChildrenOnClose::Close => self.collect_subtree_ids(id),
+            ChildrenOnClose::ReattachToParent | ChildrenOnClose::PromoteFirstChild => vec![*id],
+        }
+    }
+
+    /// Removes the tab (and its children, depending on `children_on_close`),
+    /// and returns the removed tabs so they can be closed.
+    ///
+    /// If the focused tab was removed, then the parent (or the promoted child) is focused.
+    pub fn remove_tab_and_refocus(
+        &mut self,
+        id: &Id<TabHandler>,
+        children_on_close: ChildrenOnClose,
+    ) -> Vec<TabHandler> {
+        let parent_id = self.org_tree.get_parent(id);
+
+        let (removed_tabs, new_focus) = match children_on_close {
+            ChildrenOnClose::Close => (self.remove_tab_recursively(id), parent_id),
+            ChildrenOnClose::ReattachToParent => {
+                if self.org_tree.remove_and_reattach_children(id).is_none() {
+                    println!("Tried to close root");
+                    return Vec::new();
+                }
+                (self.remove_tab_only(id), parent_id)
+            }
+            ChildrenOnClose::PromoteFirstChild => {
+                let Some(promoted_id) = self.org_tree.remove_and_promote_first_child(id) else {
+                    println!("No child to promote");
+                    return Vec::new();
+                };
+                (self.remove_tab_only(id), Some(promoted_id))
+            }
+        };
+
+        if !self.tabs.contains_key(&self.focused_tab) {
+            self.set_focused_tab_id(new_focus.unwrap_or(self.get_root_id()));
+        }
+
+        removed_tabs
+    }
+
+    /// for after the tab was already taken out of `org_tree`
+    fn remove_tab_only(&mut self, id: &Id<TabHandler>) -> Vec<TabHandler> {
+        self.remove_from_display(*id);
+        self.tabs.remove(id).into_iter().collect()
     }
 
-    /// closes the focused tab and all its children
-    pub fn close_focused_tab_recursively(&mut self) {
-        self.close_tab_recursively(&self.get_focused_tab_id());
-        // TODO: self focused tab to parent
-        self.set_focused_tab_id(self.get_root_id());
+    /// Every tab handler, for closing them all when the manager is done with them
+    pub fn take_tab_handlers(&mut self) -> Vec<TabHandler> {
+        std::mem::take(&mut self.tabs).into_values().collect()
     }
 
     pub fn org_swap(&mut self, ids: [Id<TabHandler>; 2]) {
@@ -287,6 +951,11 @@ impl Tabs {
             org_tree: self.org_tree.clone(),
             focused_tab: self.focused_tab,
             display_tiles: self.display_tiles.clone(),
+            layout_policy: self.chosen_layout_policy,
+            overlay: self.overlay.clone(),
+            workspace_name: self.workspace_name.clone(),
+            inactive_workspaces: self.inactive_workspaces.clone(),
+            active_workspace_index: self.active_workspace_index,
         }
     }
 }
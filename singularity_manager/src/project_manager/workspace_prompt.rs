@@ -0,0 +1,93 @@
+//! Overlay for typing a workspace's name, when creating or renaming one.
+
+use singularity_common::{
+    components::{text_box::TextBox, Component},
+    keymap::Keymap,
+    tab::packets::Event,
+};
+use singularity_ui::{
+    color::Color,
+    display_units::DisplayArea,
+    ui_element::{CharGrid, UIElement},
+    ui_event::{Key, KeyModifiers, UIEvent},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkspacePromptPurpose {
+    /// a new workspace, with the focused tab moved into it
+    Create,
+    /// the active workspace
+    Rename,
+}
+
+pub enum WorkspacePromptOutcome {
+    Confirm(String),
+    Cancel,
+}
+
+pub struct WorkspacePrompt {
+    purpose: WorkspacePromptPurpose,
+    name: TextBox,
+}
+impl WorkspacePrompt {
+    pub fn new(purpose: WorkspacePromptPurpose, name: &str) -> Self {
+        let mut name = TextBox::new(name.to_string());
+        name.move_cursor_to_end();
+
+        Self { purpose, name }
+    }
+
+    pub fn get_purpose(&self) -> WorkspacePromptPurpose {
+        self.purpose
+    }
+
+    /// Returns None while the prompt should stay open
+    pub fn handle_key(
+        &mut self,
+        key: Key,
+        key_modifiers: KeyModifiers,
+        keymap: &Keymap,
+    ) -> Option<WorkspacePromptOutcome> {
+        match keymap.get_action(&["workspace_prompt"], &key, key_modifiers) {
+            Some("workspace_prompt.confirm") => {
+                let name = self.name.get_text_as_string();
+                // REVIEW: should names be unique?
+                if name.trim().is_empty() {
+                    return Some(WorkspacePromptOutcome::Cancel);
+                }
+
+                return Some(WorkspacePromptOutcome::Confirm(name.trim().to_string()));
+            }
+            Some("workspace_prompt.cancel") => {
+                return Some(WorkspacePromptOutcome::Cancel);
+            }
+            _ => {
+                // everything else is for typing the name
+                self.name
+                    .handle_event(Event::UIEvent(UIEvent::KeyPress(key, key_modifiers)));
+            }
+        }
+
+        None
+    }
+
+    pub fn render(&self) -> UIElement {
+        let mut prompt_display = CharGrid::from(
+            match self.purpose {
+                WorkspacePromptPurpose::Create => "New workspace:",
+                WorkspacePromptPurpose::Rename => "Rename workspace:",
+            }
+            .to_string(),
+        );
+        prompt_display.content.extend(
+            self.name
+                .render_grid_with_color((Color::BLACK, Color::LIGHT_YELLOW))
+                .content,
+        );
+
+        UIElement::CharGrid(prompt_display)
+            .fill_bg(Color::DARK_GRAY)
+            .bordered(Color::LIGHT_GREEN)
+            .contain(DisplayArea::new((0.3, 0.4), (0.7, 0.6)))
+    }
+}
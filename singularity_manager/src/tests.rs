@@ -1,10 +1,32 @@
 #![cfg(test)]
 
 use crate::project_manager;
-use singularity_common::project::Project;
-use std::process::Command;
+use singularity_common::{
+    project::{project_settings::OpenTabs, user_settings::UserSettings, Project},
+    tab::{
+        overlay,
+        packets::Event,
+        registry::TabRegistry,
+        tile::{LayoutPolicy, Orientation},
+        BasicTab, InitError, ManagerHandler, TabCreator, TabHandler,
+    },
+    utils::{id_map::Id, tree::tree_node_path::TreeNodePath},
+};
+use singularity_ui::{
+    color::Color,
+    headless_backend::{HeadlessDisplay, ScriptStep},
+    ui_element::UIElement,
+    ui_event::{KeyModifiers, UIEvent},
+};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
 
 #[test]
+#[allow(clippy::zombie_processes)]
 fn print_runner_directory() {
     Command::new("ls").arg("-a").spawn().unwrap();
 }
@@ -12,7 +34,7 @@ fn print_runner_directory() {
 #[test]
 fn run_demo() {
     // create demo manager
-    let manager = project_manager::ProjectManager::new("../examples/root-project");
+    let manager = project_manager::ProjectManager::new("../examples/root-project").unwrap();
 
     // manager.tabs.add(
     //     TabHandler::new(
@@ -46,7 +68,1486 @@ fn run_demo() {
     manager.run().unwrap();
 }
 
+/// `ProjectManager::new`, but without the user's own settings and keymap,
+/// so that the tests don't depend on whoever runs them
+fn new_manager<P>(project_directory: P) -> project_manager::ProjectManager
+where
+    P: 'static + AsRef<Path> + Clone + Send,
+    PathBuf: From<P>,
+{
+    let mut tab_registry = TabRegistry::new();
+    singularity_standard_tabs::register_standard_tabs(&mut tab_registry);
+
+    new_manager_with_registry(project_directory, tab_registry)
+}
+
+/// Same as `new_manager`, for `Project::new`
+fn load_project<P>(project_directory: P) -> Project
+where
+    P: AsRef<Path>,
+    PathBuf: From<P>,
+{
+    Project::new_with_user_settings(project_directory, &UserSettings::default())
+}
+
+/// Same as `new_manager`, for `ProjectManager::new_with_registry`
+fn new_manager_with_registry<P>(
+    project_directory: P,
+    tab_registry: TabRegistry,
+) -> project_manager::ProjectManager
+where
+    P: 'static + AsRef<Path> + Clone + Send,
+    PathBuf: From<P>,
+{
+    project_manager::ProjectManager::new_with_user_config(
+        project_directory,
+        tab_registry,
+        &UserSettings::default(),
+        None,
+    )
+    .unwrap()
+}
+
+/// A directory in the temp directory, removed when dropped (so even when a test fails)
+struct TempDirectory(PathBuf);
+impl TempDirectory {
+    fn new(prefix: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("{prefix}-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+}
+impl std::ops::Deref for TempDirectory {
+    type Target = PathBuf;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl Drop for TempDirectory {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Copies the example project into a temporary directory,
+/// because the manager saves into the project when it closes
+fn copy_example_project() -> TempDirectory {
+    fn copy_dir_recursive(from: &Path, to: &Path) {
+        std::fs::create_dir_all(to).unwrap();
+        for entry in std::fs::read_dir(from).unwrap() {
+            let entry = entry.unwrap();
+            if entry.file_type().unwrap().is_dir() {
+                copy_dir_recursive(&entry.path(), &to.join(entry.file_name()));
+            } else {
+                std::fs::copy(entry.path(), to.join(entry.file_name())).unwrap();
+            }
+        }
+    }
+
+    let project_directory = TempDirectory::new("singularity-test");
+    copy_dir_recursive(Path::new("../examples/root-project"), &project_directory);
+
+    // the session's tabs point at the example project (relative to the repo root),
+    // which would be outside of the copy's file system permissions.
+    // The copy starts from the template as if it had been opened before
+    // (and not from the example's own `session.json`, if the demo has been run).
+    let template_path = project_directory.join(".project/session.template.json");
+    let session = std::fs::read_to_string(&template_path).unwrap().replace(
+        "\"examples/root-project\"",
+        &serde_json::to_string(&*project_directory).unwrap(),
+    );
+    std::fs::write(&template_path, &session).unwrap();
+    std::fs::write(project_directory.join(".project/session.json"), session).unwrap();
+
+    project_directory
+}
+
+#[test]
+fn run_headless_demo() {
+    const ALT_ENTER: fn() -> ScriptStep = || ScriptStep::key_press(28, None, KeyModifiers::ALT);
+
+    let project_directory = copy_example_project();
+    let manager = new_manager(project_directory.clone());
+
+    let headless_display = HeadlessDisplay::new(
+        [100, 30],
+        [
+            // give the tabs some time to start
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+            // open the tab focuser
+            ALT_ENTER(),
+            ScriptStep::Snapshot,
+            // close it
+            ALT_ENTER(),
+            ScriptStep::Snapshot,
+            // Ctrl+Q
+            ScriptStep::key_press(16, Some('q'), KeyModifiers::CTRL),
+        ],
+    );
+    let snapshots = headless_display.snapshots();
+
+    // should actually finish, unlike `run_demo`
+    manager.run_headless(headless_display).unwrap();
+
+    let snapshots = snapshots.lock().unwrap();
+    assert_eq!(snapshots.len(), 3);
+
+    // the focuser is bordered and contained in (0.4, 0.4) to (0.6, 0.6), which is cell (40, 12)
+    let focuser_corner =
+        |snapshot_index: usize| *snapshots[snapshot_index].get_cell(40, 12).unwrap();
+    assert_eq!(focuser_corner(1).character, '┌');
+    assert_eq!(focuser_corner(1).fg, Color::LIGHT_GREEN);
+    assert_ne!(focuser_corner(0), focuser_corner(1));
+    assert_ne!(focuser_corner(2), focuser_corner(1));
+
+    // closing should have saved the session
+    assert!(load_project(project_directory.clone()).session.is_some());
+}
+
+#[test]
+fn launch_tab_headless() {
+    const DOWN: fn() -> ScriptStep = || ScriptStep::key_press(108, None, KeyModifiers::NONE);
+
+    let project_directory = copy_example_project();
+    let manager = new_manager(project_directory.clone());
+
+    let headless_display = HeadlessDisplay::new(
+        [100, 30],
+        [
+            ScriptStep::WaitForIdle,
+            // Ctrl+T opens the launcher
+            ScriptStep::key_press(20, Some('t'), KeyModifiers::CTRL),
+            ScriptStep::Snapshot,
+            // select the time manager, keeping the project directory as the path
+            DOWN(),
+            DOWN(),
+            DOWN(),
+            ScriptStep::key_press(28, None, KeyModifiers::NONE),
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+            ScriptStep::key_press(16, Some('q'), KeyModifiers::CTRL),
+        ],
+    );
+    let snapshots = headless_display.snapshots();
+
+    manager.run_headless(headless_display).unwrap();
+
+    // the launcher is contained in (0.3, 0.3) to (0.7, 0.7), which is cell (30, 9)
+    let snapshots = snapshots.lock().unwrap();
+    assert_eq!(snapshots[0].get_cell(30, 9).unwrap().character, '┌');
+    assert!(snapshots[0].to_string().contains("Time Manager"));
+    assert!(!snapshots[1].to_string().contains("Open tab:"));
+
+    let open_tabs = load_project(project_directory.clone()).session.unwrap();
+    // launched tabs get focused
+    // NOTE: the example project already has a time manager, so look at the focused tab
+    let time_manager = &open_tabs.tabs[&uuid::Uuid::from(open_tabs.focused_tab).into()];
+    assert_eq!(time_manager.tab_data.tab_type, "TIME_MANAGER");
+    assert_eq!(
+        time_manager.tab_data.session_data,
+        serde_json::to_value(project_directory.to_string_lossy()).unwrap()
+    );
+}
+
+#[test]
+fn unknown_tab_type_survives_save() {
+    let project_directory = copy_example_project();
+
+    // pretend the time manager was from a crate that isn't there anymore
+    let session_path = project_directory.join(".project").join("session.json");
+    let session = std::fs::read_to_string(&session_path).unwrap();
+    std::fs::write(
+        &session_path,
+        session.replace("\"TIME_MANAGER\"", "\"NOT_A_TAB_TYPE\""),
+    )
+    .unwrap();
+
+    let manager = new_manager(project_directory.clone());
+    let headless_display = HeadlessDisplay::new(
+        [100, 30],
+        [
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+            ScriptStep::key_press(16, Some('q'), KeyModifiers::CTRL),
+        ],
+    );
+    let snapshots = headless_display.snapshots();
+
+    // shouldn't panic
+    manager.run_headless(headless_display).unwrap();
+
+    assert!(snapshots.lock().unwrap()[0]
+        .to_string()
+        .contains("Missing tab type"));
+
+    let open_tabs = load_project(project_directory.clone()).session.unwrap();
+    let missing_tab = open_tabs
+        .tabs
+        .values()
+        .find(|open_tab| open_tab.tab_data.tab_type == "NOT_A_TAB_TYPE")
+        .expect("the unknown tab should still be saved");
+    assert_eq!(
+        missing_tab.tab_data.session_data,
+        serde_json::to_value(&*project_directory).unwrap()
+    );
+}
+
+/// Panics when it first starts, and on any key press after that
+struct PanickingTab;
+static PANICKING_TAB_STARTS: AtomicUsize = AtomicUsize::new(0);
+impl BasicTab for PanickingTab {
+    fn initialize_tab(_manager_handler: &ManagerHandler) -> Result<Self, InitError> {
+        if PANICKING_TAB_STARTS.fetch_add(1, Ordering::SeqCst) == 0 {
+            panic!("first start");
+        }
+        Ok(Self)
+    }
+
+    fn render_tab(&mut self, _manager_handler: &ManagerHandler) -> Option<UIElement> {
+        Some(UIElement::CharGrid("Running".to_string().into()))
+    }
+
+    fn handle_tab_event(&mut self, event: Event, _manager_handler: &ManagerHandler) {
+        if let Event::UIEvent(singularity_ui::ui_event::UIEvent::KeyPress(..)) = event {
+            panic!("pressed a key");
+        }
+    }
+}
+
+#[test]
+fn crashed_tab_restart_and_close() {
+    let project_directory = copy_example_project();
+    let mut tab_registry = TabRegistry::new();
+    tab_registry.register_basic_tab::<PanickingTab>("PANICKING", "Panicking");
+    // NOTE: the example project's tabs are placeholders with this registry
+    let manager = new_manager_with_registry(project_directory.clone(), tab_registry);
+
+    let headless_display = HeadlessDisplay::new(
+        [100, 30],
+        [
+            ScriptStep::WaitForIdle,
+            // launch the panicking tab (the only type)
+            ScriptStep::key_press(20, Some('t'), KeyModifiers::CTRL),
+            ScriptStep::key_press(28, None, KeyModifiers::NONE),
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+            // r restarts it
+            ScriptStep::key_press(19, Some('r'), KeyModifiers::NONE),
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+            // crash it again
+            ScriptStep::key_press(45, Some('x'), KeyModifiers::NONE),
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+            // c closes it
+            ScriptStep::key_press(46, Some('c'), KeyModifiers::NONE),
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+            ScriptStep::key_press(16, Some('q'), KeyModifiers::CTRL),
+        ],
+    );
+    let snapshots = headless_display.snapshots();
+
+    // the manager should survive all of it
+    manager.run_headless(headless_display).unwrap();
+
+    let snapshots = snapshots.lock().unwrap();
+    let snapshots: Vec<String> = snapshots
+        .iter()
+        .map(|snapshot| snapshot.to_string())
+        .collect();
+    assert!(snapshots[0].contains("crashed"));
+    assert!(snapshots[0].contains("first start"));
+    assert!(snapshots[1].contains("Running"));
+    assert!(!snapshots[1].contains("crashed"));
+    assert!(snapshots[2].contains("pressed a key"));
+    assert!(!snapshots[3].contains("crashed"));
+    assert_eq!(PANICKING_TAB_STARTS.load(Ordering::SeqCst), 2);
+
+    let open_tabs = load_project(project_directory.clone()).session.unwrap();
+    assert!(open_tabs
+        .tabs
+        .values()
+        .all(|open_tab| open_tab.tab_data.tab_type != "PANICKING"));
+}
+
+/// Counts key presses, and keeps the count in its session data
+struct CountingTab {
+    count: u64,
+}
+impl BasicTab for CountingTab {
+    fn initialize_tab(manager_handler: &ManagerHandler) -> Result<Self, InitError> {
+        let query_channels = manager_handler.get_query_channels();
+        let tab_data = singularity_common::ask_query!(query_channels, TabData)?;
+
+        Ok(Self {
+            count: tab_data.session_data.as_u64().unwrap_or(0),
+        })
+    }
+
+    fn render_tab(&mut self, _manager_handler: &ManagerHandler) -> Option<UIElement> {
+        Some(UIElement::CharGrid(format!("Count: {}", self.count).into()))
+    }
+
+    fn handle_tab_event(&mut self, event: Event, _manager_handler: &ManagerHandler) {
+        if let Event::UIEvent(singularity_ui::ui_event::UIEvent::KeyPress(..)) = event {
+            self.count += 1;
+        }
+    }
+
+    fn session_data(&self) -> Option<serde_json::Value> {
+        Some(self.count.into())
+    }
+}
+
+#[test]
+fn session_data_survives_restart() {
+    const X: fn() -> ScriptStep = || ScriptStep::key_press(45, Some('x'), KeyModifiers::NONE);
+
+    let project_directory = copy_example_project();
+    let new_manager = || {
+        let mut tab_registry = TabRegistry::new();
+        tab_registry.register_basic_tab::<CountingTab>("COUNTING", "Counting");
+        new_manager_with_registry(project_directory.clone(), tab_registry)
+    };
+    let saved_count = || {
+        load_project(project_directory.clone())
+            .session
+            .unwrap()
+            .tabs
+            .values()
+            .find(|open_tab| open_tab.tab_data.tab_type == "COUNTING")
+            .unwrap()
+            .tab_data
+            .session_data
+            .clone()
+    };
+
+    // launch a counting tab and press x 3 times
+    new_manager()
+        .run_headless(HeadlessDisplay::new(
+            [100, 30],
+            [
+                ScriptStep::WaitForIdle,
+                ScriptStep::key_press(20, Some('t'), KeyModifiers::CTRL),
+                ScriptStep::key_press(28, None, KeyModifiers::NONE),
+                ScriptStep::WaitForIdle,
+                X(),
+                X(),
+                X(),
+                ScriptStep::key_press(16, Some('q'), KeyModifiers::CTRL),
+            ],
+        ))
+        .unwrap();
+    assert_eq!(saved_count(), serde_json::Value::from(3));
+
+    // it should start from 3 (and the launched tab is still focused)
+    let headless_display = HeadlessDisplay::new(
+        [100, 30],
+        [
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+            X(),
+            ScriptStep::key_press(16, Some('q'), KeyModifiers::CTRL),
+        ],
+    );
+    let snapshots = headless_display.snapshots();
+    new_manager().run_headless(headless_display).unwrap();
+
+    assert!(snapshots.lock().unwrap()[0]
+        .to_string()
+        .contains("Count: 3"));
+    assert_eq!(saved_count(), serde_json::Value::from(4));
+}
+
+/// Has unsaved changes after any key press
+struct UnsavedTab {
+    has_unsaved_changes: bool,
+}
+static UNSAVED_TAB_SAVES: AtomicUsize = AtomicUsize::new(0);
+static UNSAVED_TAB_DROPS: AtomicUsize = AtomicUsize::new(0);
+impl BasicTab for UnsavedTab {
+    fn initialize_tab(_manager_handler: &ManagerHandler) -> Result<Self, InitError> {
+        Ok(Self {
+            has_unsaved_changes: false,
+        })
+    }
+
+    fn render_tab(&mut self, _manager_handler: &ManagerHandler) -> Option<UIElement> {
+        Some(UIElement::CharGrid("Unsaved".to_string().into()))
+    }
+
+    fn handle_tab_event(&mut self, event: Event, _manager_handler: &ManagerHandler) {
+        if let Event::UIEvent(singularity_ui::ui_event::UIEvent::KeyPress(..)) = event {
+            self.has_unsaved_changes = true;
+        }
+    }
+
+    fn has_unsaved_changes(&mut self) -> bool {
+        self.has_unsaved_changes
+    }
+
+    fn save(&mut self, _manager_handler: &ManagerHandler) {
+        UNSAVED_TAB_SAVES.fetch_add(1, Ordering::SeqCst);
+        self.has_unsaved_changes = false;
+    }
+}
+impl Drop for UnsavedTab {
+    fn drop(&mut self) {
+        UNSAVED_TAB_DROPS.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn quitting_asks_to_save() {
+    const CTRL_Q: fn() -> ScriptStep = || ScriptStep::key_press(16, Some('q'), KeyModifiers::CTRL);
+
+    let project_directory = copy_example_project();
+    let mut tab_registry = TabRegistry::new();
+    tab_registry.register_basic_tab::<UnsavedTab>("UNSAVED", "Unsaved");
+    let manager = new_manager_with_registry(project_directory.clone(), tab_registry);
+
+    let headless_display = HeadlessDisplay::new(
+        [100, 30],
+        [
+            ScriptStep::WaitForIdle,
+            ScriptStep::key_press(20, Some('t'), KeyModifiers::CTRL),
+            ScriptStep::key_press(28, None, KeyModifiers::NONE),
+            ScriptStep::WaitForIdle,
+            // make a change, then try to quit
+            ScriptStep::key_press(45, Some('x'), KeyModifiers::NONE),
+            CTRL_Q(),
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+            // Escape cancels quitting
+            ScriptStep::key_press(1, None, KeyModifiers::NONE),
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+            // y saves, then it quits
+            CTRL_Q(),
+            ScriptStep::WaitForIdle,
+            ScriptStep::key_press(21, Some('y'), KeyModifiers::NONE),
+            ScriptStep::WaitForIdle,
+            // (the manager should have quit by itself)
+            ScriptStep::Snapshot,
+        ],
+    );
+    let snapshots = headless_display.snapshots();
+
+    manager.run_headless(headless_display).unwrap();
+
+    let snapshots = snapshots.lock().unwrap();
+    assert!(snapshots[0].to_string().contains("Save unsaved changes?"));
+    assert!(!snapshots[1].to_string().contains("Save unsaved changes?"));
+    assert!(snapshots[1].to_string().contains("Unsaved"));
+    // the display stops with the manager, before the last snapshot
+    assert_eq!(snapshots.len(), 2);
+
+    assert_eq!(UNSAVED_TAB_SAVES.load(Ordering::SeqCst), 1);
+    // got `Event::Close` and finished
+    assert_eq!(UNSAVED_TAB_DROPS.load(Ordering::SeqCst), 1);
+}
+
+/// Always has unsaved changes, so closing it always prompts
+struct AlwaysUnsavedTab;
+impl BasicTab for AlwaysUnsavedTab {
+    fn initialize_tab(_manager_handler: &ManagerHandler) -> Result<Self, InitError> {
+        Ok(Self)
+    }
+
+    fn render_tab(&mut self, _manager_handler: &ManagerHandler) -> Option<UIElement> {
+        Some(UIElement::CharGrid("Always unsaved".to_string().into()))
+    }
+
+    fn handle_tab_event(&mut self, _event: Event, _manager_handler: &ManagerHandler) {}
+
+    fn has_unsaved_changes(&mut self) -> bool {
+        true
+    }
+}
+
+#[test]
+fn closing_window_asks_to_save() {
+    let project_directory = copy_example_project();
+    let mut tab_registry = TabRegistry::new();
+    tab_registry.register_basic_tab::<AlwaysUnsavedTab>("ALWAYS_UNSAVED", "Always Unsaved");
+    let manager = new_manager_with_registry(project_directory.clone(), tab_registry);
+
+    let headless_display = HeadlessDisplay::new(
+        [100, 30],
+        [
+            ScriptStep::WaitForIdle,
+            ScriptStep::key_press(20, Some('t'), KeyModifiers::CTRL),
+            ScriptStep::key_press(28, None, KeyModifiers::NONE),
+            ScriptStep::WaitForIdle,
+            // same as Ctrl+Q
+            ScriptStep::UIEvent(UIEvent::CloseRequested),
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+            // n doesn't save, then it quits
+            ScriptStep::key_press(49, Some('n'), KeyModifiers::NONE),
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+        ],
+    );
+    let snapshots = headless_display.snapshots();
+
+    manager.run_headless(headless_display).unwrap();
+
+    let snapshots = snapshots.lock().unwrap();
+    assert!(snapshots[0].to_string().contains("Save unsaved changes?"));
+    // the display stops with the manager, before the last snapshot
+    assert_eq!(snapshots.len(), 1);
+}
+
+/// Ignores everything, including `Event::Close`
+struct HungTab;
+impl TabCreator for HungTab {
+    fn create_tab(&mut self, _manager_handler: ManagerHandler) {
+        loop {
+            std::thread::sleep(Duration::from_secs(60));
+        }
+    }
+}
+
+#[test]
+fn hung_tab_is_left_behind() {
+    let project_directory = copy_example_project();
+    let mut tab_registry = TabRegistry::new();
+    tab_registry.register("HUNG", "Hung", || Box::new(HungTab));
+    let manager = new_manager_with_registry(project_directory.clone(), tab_registry);
+
+    let headless_display = HeadlessDisplay::new(
+        [100, 30],
+        [
+            ScriptStep::WaitForIdle,
+            ScriptStep::key_press(20, Some('t'), KeyModifiers::CTRL),
+            ScriptStep::key_press(28, None, KeyModifiers::NONE),
+            ScriptStep::WaitForIdle,
+            // Ctrl+W, which times out waiting for an answer, and then for the thread
+            ScriptStep::key_press(17, Some('w'), KeyModifiers::CTRL),
+            ScriptStep::Wait(Duration::from_millis(2500)),
+            ScriptStep::key_press(16, Some('q'), KeyModifiers::CTRL),
+        ],
+    );
+
+    manager.run_headless(headless_display).unwrap();
+
+    let open_tabs = load_project(project_directory.clone()).session.unwrap();
+    assert!(open_tabs
+        .tabs
+        .values()
+        .all(|open_tab| open_tab.tab_data.tab_type != "HUNG"));
+}
+
+struct PlainTab;
+impl BasicTab for PlainTab {
+    fn initialize_tab(_manager_handler: &ManagerHandler) -> Result<Self, InitError> {
+        Ok(Self)
+    }
+
+    fn render_tab(&mut self, _manager_handler: &ManagerHandler) -> Option<UIElement> {
+        Some(UIElement::CharGrid("Plain".to_string().into()))
+    }
+
+    fn handle_tab_event(&mut self, _event: Event, _manager_handler: &ManagerHandler) {}
+}
+
+#[test]
+fn closing_tab_keeps_children() {
+    const ALT_ENTER: fn() -> ScriptStep = || ScriptStep::key_press(28, None, KeyModifiers::ALT);
+    const OPEN_PLAIN_TAB: fn() -> [ScriptStep; 3] = || {
+        [
+            ScriptStep::key_press(20, Some('t'), KeyModifiers::CTRL),
+            ScriptStep::key_press(28, None, KeyModifiers::NONE),
+            ScriptStep::WaitForIdle,
+        ]
+    };
+
+    let project_directory = copy_example_project();
+    let open_tabs_before = load_project(project_directory.clone()).session.unwrap();
+    let mut tab_registry = TabRegistry::new();
+    tab_registry.register_basic_tab::<PlainTab>("PLAIN", "Plain");
+    let manager = new_manager_with_registry(project_directory.clone(), tab_registry);
+
+    let headless_display = HeadlessDisplay::new(
+        [100, 30],
+        [
+            vec![ScriptStep::WaitForIdle],
+            // focused -> parent -> child
+            OPEN_PLAIN_TAB().to_vec(),
+            OPEN_PLAIN_TAB().to_vec(),
+            vec![
+                // focus the parent
+                ALT_ENTER(),
+                ScriptStep::key_press(30, Some('a'), KeyModifiers::ALT),
+                ALT_ENTER(),
+                // Ctrl+Shift+W
+                ScriptStep::key_press(17, Some('w'), KeyModifiers::CTRL | KeyModifiers::SHIFT),
+                ScriptStep::WaitForIdle,
+                ScriptStep::key_press(16, Some('q'), KeyModifiers::CTRL),
+            ],
+        ]
+        .concat(),
+    );
+
+    manager.run_headless(headless_display).unwrap();
+
+    let open_tabs = load_project(project_directory.clone()).session.unwrap();
+    assert_eq!(open_tabs.tabs.len(), open_tabs_before.tabs.len() + 1);
+    let plain_tab_ids: Vec<Id<TabHandler>> = open_tabs
+        .tabs
+        .iter()
+        .filter(|(_, open_tab)| open_tab.tab_data.tab_type == "PLAIN")
+        .map(|(id, _)| uuid::Uuid::from(*id).into())
+        .collect();
+    assert_eq!(plain_tab_ids.len(), 1);
+    // the child was given to its grandparent
+    assert_eq!(
+        open_tabs.org_tree.get_parent(&plain_tab_ids[0]),
+        Some(open_tabs_before.focused_tab)
+    );
+}
+
+#[test]
+fn resizing_tiles() {
+    const SIZE: [usize; 2] = [100, 30];
+
+    let project_directory = copy_example_project();
+    let manager = new_manager(project_directory.clone());
+
+    let headless_display = HeadlessDisplay::new(
+        SIZE,
+        [
+            ScriptStep::WaitForIdle,
+            // the example project is split horizontally at y = 15 cells,
+            // so drag it down to 21
+            ScriptStep::click_cell([50, 15], SIZE),
+            ScriptStep::drag_to_cell([50, 18], SIZE),
+            ScriptStep::drag_to_cell([50, 21], SIZE),
+            ScriptStep::release_cell([50, 21], SIZE),
+            // moving without holding anything does nothing
+            ScriptStep::drag_to_cell([50, 3], SIZE),
+            ScriptStep::WaitForIdle,
+            ScriptStep::key_press(16, Some('q'), KeyModifiers::CTRL),
+        ],
+    );
+
+    manager.run_headless(headless_display).unwrap();
+
+    let display_tiles = load_project(project_directory.clone())
+        .session
+        .unwrap()
+        .display_tiles;
+    let split = display_tiles
+        .get_tile(display_tiles.get_root_tile())
+        .unwrap()
+        .try_as_container()
+        .unwrap()
+        .shares()[0];
+    // the middle of cell 21 is 21.5/30
+    assert!((split - 21.5 / 30.).abs() < 0.01, "split is {split}");
+
+    // then shrink the focused tab with the keyboard
+    let manager = new_manager(project_directory.clone());
+    let headless_display = HeadlessDisplay::new(
+        SIZE,
+        [
+            ScriptStep::WaitForIdle,
+            ScriptStep::key_press(12, Some('-'), KeyModifiers::LOGO),
+            ScriptStep::key_press(12, Some('-'), KeyModifiers::LOGO),
+            ScriptStep::key_press(13, Some('='), KeyModifiers::LOGO),
+            ScriptStep::WaitForIdle,
+            ScriptStep::key_press(16, Some('q'), KeyModifiers::CTRL),
+        ],
+    );
+
+    manager.run_headless(headless_display).unwrap();
+
+    let open_tabs = load_project(project_directory.clone()).session.unwrap();
+    let display_tiles = open_tabs.display_tiles;
+    let root_container = display_tiles
+        .get_tile(display_tiles.get_root_tile())
+        .unwrap()
+        .try_as_container()
+        .unwrap();
+    let (children, shrunk_split) = (&root_container.children, root_container.shares()[0]);
+    // the split is how much the first tile gets
+    let focused_tile = display_tiles
+        .get_leaf_tile_id(open_tabs.focused_tab)
+        .unwrap();
+    let expected_change = if children[0] == focused_tile {
+        -0.05
+    } else {
+        0.05
+    };
+    assert!((shrunk_split - (split + expected_change)).abs() < 1e-4);
+}
+
+#[test]
+fn tabbed_tiles() {
+    const SIZE: [usize; 2] = [100, 30];
+    const OPEN_PLAIN_TAB: fn() -> [ScriptStep; 3] = || {
+        [
+            ScriptStep::key_press(20, Some('t'), KeyModifiers::CTRL),
+            ScriptStep::key_press(28, None, KeyModifiers::NONE),
+            ScriptStep::WaitForIdle,
+        ]
+    };
+
+    let project_directory = copy_example_project();
+    let open_tabs_before = load_project(project_directory.clone()).session.unwrap();
+    // the example project is split horizontally in half
+    let first_focused_tab = open_tabs_before.focused_tab;
+    let root_container = open_tabs_before
+        .display_tiles
+        .get_tile(open_tabs_before.display_tiles.get_root_tile())
+        .unwrap()
+        .try_as_container()
+        .unwrap();
+    let title_strip_row = if root_container.children[0]
+        == open_tabs_before
+            .display_tiles
+            .get_leaf_tile_id(first_focused_tab)
+            .unwrap()
+    {
+        0
+    } else {
+        SIZE[1] / 2
+    };
+
+    let mut tab_registry = TabRegistry::new();
+    tab_registry.register_basic_tab::<PlainTab>("PLAIN", "Plain");
+    let manager = new_manager_with_registry(project_directory.clone(), tab_registry);
+
+    let headless_display = HeadlessDisplay::new(
+        SIZE,
+        [
+            vec![ScriptStep::WaitForIdle],
+            OPEN_PLAIN_TAB().to_vec(),
+            vec![
+                // Logo+W
+                ScriptStep::key_press(17, Some('w'), KeyModifiers::LOGO),
+                ScriptStep::WaitForIdle,
+            ],
+            // goes into the tabbed container
+            OPEN_PLAIN_TAB().to_vec(),
+            vec![
+                ScriptStep::Snapshot,
+                // the first of the 3 titles
+                ScriptStep::click_cell([5, title_strip_row], SIZE),
+                ScriptStep::WaitForIdle,
+                ScriptStep::Snapshot,
+                // Logo+Shift+Tab wraps around to the last
+                ScriptStep::key_press(15, None, KeyModifiers::LOGO | KeyModifiers::SHIFT),
+                ScriptStep::WaitForIdle,
+                ScriptStep::key_press(16, Some('q'), KeyModifiers::CTRL),
+            ],
+        ]
+        .concat(),
+    );
+    let snapshots = headless_display.snapshots();
+
+    manager.run_headless(headless_display).unwrap();
+
+    let snapshots = snapshots.lock().unwrap();
+    let title_strip = |snapshot_index: usize| {
+        snapshots[snapshot_index]
+            .to_string()
+            .lines()
+            .nth(title_strip_row)
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(title_strip(0).matches("PLAIN").count(), 2);
+    // the plain tabs are hidden
+    assert!(!snapshots[1].to_string().contains("Plain"));
+
+    let open_tabs = load_project(project_directory.clone()).session.unwrap();
+    let tabbed_tile = open_tabs
+        .display_tiles
+        .get_leaf_tile_id(first_focused_tab)
+        .unwrap();
+    let tabbed_container_id = open_tabs
+        .display_tiles
+        .get_parent_tile_id(tabbed_tile)
+        .unwrap();
+    let tabbed_container = open_tabs
+        .display_tiles
+        .get_tile(tabbed_container_id)
+        .unwrap()
+        .try_as_container()
+        .unwrap();
+    assert_eq!(tabbed_container.children.len(), 3);
+    assert_eq!(tabbed_container.get_active(), Some(2));
+    assert_eq!(
+        open_tabs.tabs[&uuid::Uuid::from(open_tabs.focused_tab).into()]
+            .tab_data
+            .tab_type,
+        "PLAIN"
+    );
+}
+
+#[test]
+fn layout_policies() {
+    const SIZE: [usize; 2] = [100, 30];
+    const OPEN_PLAIN_TAB: fn() -> [ScriptStep; 3] = || {
+        [
+            ScriptStep::key_press(20, Some('t'), KeyModifiers::CTRL),
+            ScriptStep::key_press(28, None, KeyModifiers::NONE),
+            ScriptStep::WaitForIdle,
+        ]
+    };
+    let run = |project_directory: &PathBuf, steps: Vec<ScriptStep>| {
+        let mut tab_registry = TabRegistry::new();
+        tab_registry.register_basic_tab::<PlainTab>("PLAIN", "Plain");
+        let manager = new_manager_with_registry(project_directory.clone(), tab_registry);
+
+        let steps = [
+            vec![ScriptStep::WaitForIdle],
+            steps,
+            vec![ScriptStep::key_press(16, Some('q'), KeyModifiers::CTRL)],
+        ]
+        .concat();
+        manager
+            .run_headless(HeadlessDisplay::new(SIZE, steps))
+            .unwrap();
+
+        load_project(project_directory.clone()).session.unwrap()
+    };
+    let root_container = |open_tabs: &OpenTabs| {
+        open_tabs
+            .display_tiles
+            .get_tile(open_tabs.display_tiles.get_root_tile())
+            .unwrap()
+            .try_as_container()
+            .unwrap()
+            .clone()
+    };
+
+    let project_directory = copy_example_project();
+    let mut project = load_project(project_directory.clone());
+    project.project_settings.layout_policy = Some(LayoutPolicy::MasterStack);
+    project.save_to_file();
+
+    // the example project's 2 tabs, and 2 new ones
+    let open_tabs = run(
+        &project_directory,
+        [OPEN_PLAIN_TAB(), OPEN_PLAIN_TAB()].concat(),
+    );
+    assert_eq!(open_tabs.display_tiles.collect_leaves().len(), 4);
+    let root = root_container(&open_tabs);
+    assert_eq!(root.orientation, Orientation::Vertical);
+    let stack = open_tabs.display_tiles.get_tile(root.children[1]).unwrap();
+    assert_eq!(stack.try_as_container().unwrap().children.len(), 3);
+    // only saved when picked
+    assert_eq!(open_tabs.layout_policy, None);
+
+    // Logo+L, to the next one
+    let open_tabs = run(
+        &project_directory,
+        vec![
+            ScriptStep::key_press(38, Some('l'), KeyModifiers::LOGO),
+            ScriptStep::WaitForIdle,
+        ],
+    );
+    assert_eq!(open_tabs.layout_policy, Some(LayoutPolicy::Grid));
+    let root = root_container(&open_tabs);
+    assert_eq!(root.orientation, Orientation::Horizontal);
+    assert_eq!(root.children.len(), 2);
+}
+
+#[test]
+fn directional_focus() {
+    const SIZE: [usize; 2] = [100, 30];
+    const LOGO_ARROW: fn(u32, KeyModifiers) -> [ScriptStep; 2] = |raw_code, modifiers| {
+        [
+            ScriptStep::key_press(raw_code, None, KeyModifiers::LOGO | modifiers),
+            ScriptStep::WaitForIdle,
+        ]
+    };
+    const LEFT: u32 = 105;
+    const RIGHT: u32 = 106;
+    const UP: u32 = 103;
+    const DOWN: u32 = 108;
+
+    let project_directory = copy_example_project();
+    let open_tabs_before = load_project(project_directory.clone()).session.unwrap();
+    // the example project is split horizontally in half
+    let first_focused_tab = open_tabs_before.focused_tab;
+    let root_container = open_tabs_before
+        .display_tiles
+        .get_tile(open_tabs_before.display_tiles.get_root_tile())
+        .unwrap()
+        .try_as_container()
+        .unwrap();
+    let other_tab = open_tabs_before
+        .display_tiles
+        .collect_leaves()
+        .into_iter()
+        .find(|tab_id| *tab_id != first_focused_tab)
+        .unwrap();
+    let other_tab_direction = if root_container.children[0]
+        == open_tabs_before
+            .display_tiles
+            .get_leaf_tile_id(first_focused_tab)
+            .unwrap()
+    {
+        DOWN
+    } else {
+        UP
+    };
+
+    let mut tab_registry = TabRegistry::new();
+    tab_registry.register_basic_tab::<PlainTab>("PLAIN", "Plain");
+    let manager = new_manager_with_registry(project_directory.clone(), tab_registry);
+
+    let headless_display = HeadlessDisplay::new(
+        SIZE,
+        [
+            vec![
+                ScriptStep::WaitForIdle,
+                // the new tab goes to the right of the focused one
+                ScriptStep::key_press(20, Some('t'), KeyModifiers::CTRL),
+                ScriptStep::key_press(28, None, KeyModifiers::NONE),
+                ScriptStep::WaitForIdle,
+            ],
+            // nothing further right
+            LOGO_ARROW(RIGHT, KeyModifiers::NONE).to_vec(),
+            LOGO_ARROW(LEFT, KeyModifiers::NONE).to_vec(),
+            // trade places with the plain tab
+            LOGO_ARROW(RIGHT, KeyModifiers::SHIFT).to_vec(),
+            // the other half (which is under/over both)
+            LOGO_ARROW(other_tab_direction, KeyModifiers::NONE).to_vec(),
+            vec![ScriptStep::key_press(16, Some('q'), KeyModifiers::CTRL)],
+        ]
+        .concat(),
+    );
+
+    manager.run_headless(headless_display).unwrap();
+
+    let open_tabs = load_project(project_directory.clone()).session.unwrap();
+    assert_eq!(open_tabs.focused_tab, other_tab);
+
+    let moved_tile = open_tabs
+        .display_tiles
+        .get_leaf_tile_id(first_focused_tab)
+        .unwrap();
+    let split_container = open_tabs
+        .display_tiles
+        .get_tile(
+            open_tabs
+                .display_tiles
+                .get_parent_tile_id(moved_tile)
+                .unwrap(),
+        )
+        .unwrap()
+        .try_as_container()
+        .unwrap();
+    assert_eq!(split_container.orientation, Orientation::Vertical);
+    assert_eq!(split_container.children[1], moved_tile);
+    assert_eq!(
+        open_tabs.tabs[&uuid::Uuid::from(
+            open_tabs
+                .display_tiles
+                .get_tile(split_container.children[0])
+                .unwrap()
+                .try_as_tab()
+                .unwrap()
+        )
+        .into()]
+            .tab_data
+            .tab_type,
+        "PLAIN"
+    );
+}
+
+#[test]
+fn floating_maximized_and_minimized_tabs() {
+    const SIZE: [usize; 2] = [100, 30];
+    let run = |project_directory: &PathBuf, steps: Vec<ScriptStep>| {
+        let mut tab_registry = TabRegistry::new();
+        tab_registry.register_basic_tab::<PlainTab>("PLAIN", "Plain");
+        let manager = new_manager_with_registry(project_directory.clone(), tab_registry);
+
+        let headless_display = HeadlessDisplay::new(
+            SIZE,
+            [
+                vec![ScriptStep::WaitForIdle],
+                steps,
+                vec![ScriptStep::key_press(16, Some('q'), KeyModifiers::CTRL)],
+            ]
+            .concat(),
+        );
+        let snapshots = headless_display.snapshots();
+        manager.run_headless(headless_display).unwrap();
+
+        let snapshots = snapshots.lock().unwrap().clone();
+        let open_tabs = load_project(project_directory.clone()).session.unwrap();
+        (snapshots, open_tabs)
+    };
+
+    let project_directory = copy_example_project();
+    let (snapshots, open_tabs) = run(
+        &project_directory,
+        vec![
+            ScriptStep::key_press(20, Some('t'), KeyModifiers::CTRL),
+            ScriptStep::key_press(28, None, KeyModifiers::NONE),
+            ScriptStep::WaitForIdle,
+            // Logo+Shift+Space
+            ScriptStep::key_press(57, Some(' '), KeyModifiers::LOGO | KeyModifiers::SHIFT),
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+            // Logo+F
+            ScriptStep::key_press(33, Some('f'), KeyModifiers::LOGO),
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+            ScriptStep::key_press(33, Some('f'), KeyModifiers::LOGO),
+            // Logo+M
+            ScriptStep::key_press(50, Some('m'), KeyModifiers::LOGO),
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+        ],
+    );
+    // floating on top of the (placeholder) tiles
+    assert!(snapshots[0].to_string().contains("Plain"));
+    assert!(snapshots[0].to_string().contains("Missing tab type"));
+    // covers them
+    assert!(snapshots[1].to_string().contains("Plain"));
+    assert!(!snapshots[1].to_string().contains("Missing tab type"));
+    // only its title in the tray
+    assert!(!snapshots[2].to_string().contains("Plain"));
+    assert!(snapshots[2]
+        .to_string()
+        .lines()
+        .last()
+        .unwrap()
+        .contains("PLAIN"));
+
+    let minimized_tab = open_tabs.overlay.get_minimized()[0];
+    assert_eq!(open_tabs.overlay.get_minimized().len(), 1);
+    assert_eq!(
+        minimized_tab.floating_area,
+        Some(overlay::DEFAULT_FLOATING_AREA)
+    );
+    assert_ne!(open_tabs.focused_tab, minimized_tab.tab_id);
+    assert_eq!(open_tabs.display_tiles.collect_leaves().len(), 2);
+
+    // clicking it in the tray floats it again
+    let (_, open_tabs) = run(
+        &project_directory,
+        vec![
+            ScriptStep::click_cell([5, SIZE[1] - 1], SIZE),
+            ScriptStep::WaitForIdle,
+        ],
+    );
+    assert!(open_tabs.overlay.get_minimized().is_empty());
+    assert_eq!(
+        open_tabs.overlay.get_floating_area(minimized_tab.tab_id),
+        Some(overlay::DEFAULT_FLOATING_AREA)
+    );
+    assert_eq!(open_tabs.focused_tab, minimized_tab.tab_id);
+}
+
+#[test]
+fn workspaces() {
+    const SIZE: [usize; 2] = [100, 30];
+    let run = |project_directory: &PathBuf, steps: Vec<ScriptStep>| {
+        let mut tab_registry = TabRegistry::new();
+        tab_registry.register_basic_tab::<PlainTab>("PLAIN", "Plain");
+        let manager = new_manager_with_registry(project_directory.clone(), tab_registry);
+
+        let headless_display = HeadlessDisplay::new(
+            SIZE,
+            [
+                vec![ScriptStep::WaitForIdle],
+                steps,
+                vec![ScriptStep::key_press(16, Some('q'), KeyModifiers::CTRL)],
+            ]
+            .concat(),
+        );
+        let snapshots = headless_display.snapshots();
+        manager.run_headless(headless_display).unwrap();
+
+        let snapshots: Vec<String> = snapshots
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|snapshot| snapshot.to_string())
+            .collect();
+        let open_tabs = load_project(project_directory.clone()).session.unwrap();
+        (snapshots, open_tabs)
+    };
+    let type_text = |text: &str| {
+        text.chars()
+            .map(|character| {
+                let raw_code = match character {
+                    'p' => 25,
+                    'l' => 38,
+                    'a' => 30,
+                    'n' => 49,
+                    'x' => 45,
+                    _ => unreachable!(),
+                };
+                ScriptStep::key_press(raw_code, Some(character), KeyModifiers::NONE)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let project_directory = copy_example_project();
+    let (snapshots, open_tabs) = run(
+        &project_directory,
+        [
+            vec![
+                ScriptStep::key_press(20, Some('t'), KeyModifiers::CTRL),
+                ScriptStep::key_press(28, None, KeyModifiers::NONE),
+                ScriptStep::WaitForIdle,
+                // Logo+N, for a new workspace with the plain tab
+                ScriptStep::key_press(49, Some('n'), KeyModifiers::LOGO),
+            ],
+            type_text("plan"),
+            vec![
+                ScriptStep::key_press(28, None, KeyModifiers::NONE),
+                ScriptStep::WaitForIdle,
+                ScriptStep::Snapshot,
+                // Logo+PageUp
+                ScriptStep::key_press(104, None, KeyModifiers::LOGO),
+                ScriptStep::WaitForIdle,
+                ScriptStep::Snapshot,
+                // Logo+PageDown
+                ScriptStep::key_press(109, None, KeyModifiers::LOGO),
+                ScriptStep::WaitForIdle,
+                // Logo+R
+                ScriptStep::key_press(19, Some('r'), KeyModifiers::LOGO),
+            ],
+            vec![ScriptStep::key_press(14, None, KeyModifiers::NONE); 4],
+            type_text("x"),
+            vec![
+                ScriptStep::key_press(28, None, KeyModifiers::NONE),
+                ScriptStep::WaitForIdle,
+            ],
+        ]
+        .concat(),
+    );
+    assert!(snapshots[0].contains("Plain"));
+    assert!(!snapshots[0].contains("Missing tab type"));
+    let bottom_bar = snapshots[0].lines().last().unwrap().to_string();
+    assert!(bottom_bar.contains("1:main") && bottom_bar.contains("2:plan"));
+    assert!(!snapshots[1].contains("Plain"));
+    assert!(snapshots[1].contains("Missing tab type"));
+
+    assert_eq!(open_tabs.workspace_name, "x");
+    assert_eq!(open_tabs.active_workspace_index, 1);
+    assert_eq!(
+        open_tabs.display_tiles.collect_leaves(),
+        vec![open_tabs.focused_tab]
+    );
+    let plain_tab_id = open_tabs.focused_tab;
+    assert_eq!(open_tabs.inactive_workspaces.len(), 1);
+    assert_eq!(open_tabs.inactive_workspaces[0].name, "main");
+    assert_eq!(
+        open_tabs.inactive_workspaces[0]
+            .display_tiles
+            .collect_leaves()
+            .len(),
+        2
+    );
+
+    // the active one is restored, and deleting it keeps its tabs
+    let (snapshots, open_tabs) = run(
+        &project_directory,
+        vec![
+            ScriptStep::Snapshot,
+            // Logo+Delete
+            ScriptStep::key_press(111, None, KeyModifiers::LOGO),
+            ScriptStep::WaitForIdle,
+        ],
+    );
+    assert!(snapshots[0].contains("Plain"));
+    assert!(open_tabs.inactive_workspaces.is_empty());
+    assert_eq!(open_tabs.workspace_name, "main");
+    assert_eq!(open_tabs.display_tiles.collect_leaves().len(), 3);
+    assert!(open_tabs.display_tiles.is_leaf_visible(plain_tab_id));
+    assert_ne!(open_tabs.focused_tab, plain_tab_id);
+}
+
 #[test]
 fn project_parse() {
-    Project::new("../examples/root-project");
+    load_project("../examples/root-project");
+}
+
+#[test]
+fn session_is_separate_from_settings() {
+    let quit = |project_directory: &Path| {
+        let manager = new_manager(project_directory.to_path_buf());
+        manager
+            .run_headless(HeadlessDisplay::new(
+                [100, 30],
+                [
+                    ScriptStep::WaitForIdle,
+                    ScriptStep::key_press(16, Some('q'), KeyModifiers::CTRL),
+                ],
+            ))
+            .unwrap();
+    };
+
+    let project_directory = copy_example_project();
+    let core_path = project_directory.join(".project").join("core.json");
+    let session_path = project_directory.join(".project").join("session.json");
+
+    // without a session, the project starts from the template,
+    // and quitting only writes the session (not the settings or the template)
+    let template_path = project_directory.join(".project/session.template.json");
+    let core_before = std::fs::read_to_string(&core_path).unwrap();
+    let template_before = std::fs::read_to_string(&template_path).unwrap();
+    std::fs::remove_file(&session_path).unwrap();
+    let template_session = load_project(project_directory.clone()).session.unwrap();
+    assert_eq!(template_session.tabs.len(), 2);
+    quit(&project_directory);
+    assert_eq!(std::fs::read_to_string(&core_path).unwrap(), core_before);
+    assert_eq!(
+        std::fs::read_to_string(&template_path).unwrap(),
+        template_before
+    );
+    assert!(session_path.exists());
+
+    // sessions from before `session.json` are still in `core.json` (and come before the template)
+    let session: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&session_path).unwrap()).unwrap();
+    let mut core: serde_json::Value = serde_json::from_str(&core_before).unwrap();
+    core["open_tabs"] = session.clone();
+    std::fs::write(&core_path, serde_json::to_string_pretty(&core).unwrap()).unwrap();
+    std::fs::remove_file(&session_path).unwrap();
+    let legacy_session = load_project(project_directory.clone()).session.unwrap();
+    assert_eq!(
+        serde_json::to_value(&legacy_session.tabs).unwrap(),
+        session["tabs"]
+    );
+
+    // and move into `session.json` on the next quit
+    quit(&project_directory);
+    assert!(session_path.exists());
+}
+
+/// Reads `OUTSIDE_FILE` on any key press, and shows what happened
+struct ReadingTab {
+    reads: Vec<String>,
+}
+static OUTSIDE_FILE: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+impl BasicTab for ReadingTab {
+    fn initialize_tab(_manager_handler: &ManagerHandler) -> Result<Self, InitError> {
+        Ok(Self { reads: Vec::new() })
+    }
+
+    fn render_tab(&mut self, _manager_handler: &ManagerHandler) -> Option<UIElement> {
+        Some(UIElement::CharGrid(self.reads.join("\n").into()))
+    }
+
+    fn handle_tab_event(&mut self, event: Event, manager_handler: &ManagerHandler) {
+        if let Event::UIEvent(singularity_ui::ui_event::UIEvent::KeyPress(..)) = event {
+            let read = match manager_handler.read_file_to_string(OUTSIDE_FILE.get().unwrap()) {
+                Ok(contents) => format!("Read {}: {contents}", self.reads.len()),
+                Err(err) => format!("Failed {}: {err}", self.reads.len()),
+            };
+            self.reads.push(read);
+        }
+    }
+}
+
+#[test]
+fn file_access_outside_project_asks() {
+    let key = |key_code, c| ScriptStep::key_press(key_code, Some(c), KeyModifiers::NONE);
+
+    let project_directory = copy_example_project();
+    let outside_directory = TempDirectory::new("singularity-outside");
+    let outside_file = outside_directory.join("outside.txt");
+    std::fs::write(&outside_file, "hello").unwrap();
+    OUTSIDE_FILE.set(outside_file.clone()).unwrap();
+
+    let mut tab_registry = TabRegistry::new();
+    tab_registry.register_basic_tab::<ReadingTab>("READING", "Reading");
+    let manager = new_manager_with_registry(project_directory.clone(), tab_registry);
+    let headless_display = HeadlessDisplay::new(
+        [100, 30],
+        [
+            ScriptStep::WaitForIdle,
+            ScriptStep::key_press(20, Some('t'), KeyModifiers::CTRL),
+            ScriptStep::key_press(28, None, KeyModifiers::NONE),
+            ScriptStep::WaitForIdle,
+            key(19, 'r'),
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+            // deny
+            key(49, 'n'),
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+            // allow once, so it asks again after
+            key(19, 'r'),
+            ScriptStep::WaitForIdle,
+            key(21, 'y'),
+            ScriptStep::WaitForIdle,
+            key(19, 'r'),
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+            // always allow, so it doesn't ask again
+            key(30, 'a'),
+            ScriptStep::WaitForIdle,
+            key(19, 'r'),
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+            ScriptStep::key_press(16, Some('q'), KeyModifiers::CTRL),
+        ],
+    );
+    let snapshots = headless_display.snapshots();
+    manager.run_headless(headless_display).unwrap();
+
+    let snapshots: Vec<String> = snapshots
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|snapshot| snapshot.to_string())
+        .collect();
+    assert!(snapshots[0].contains("`READING` wants to read"));
+    assert!(snapshots[1].contains("Failed 0: no permission"));
+    assert!(!snapshots[1].contains("wants to read"));
+    // (the prompt covers the tab)
+    assert!(snapshots[2].contains("wants to read"));
+    assert!(snapshots[3].contains("Read 1: hello"));
+    assert!(snapshots[3].contains("Read 2: hello"));
+    assert!(snapshots[3].contains("Read 3: hello"));
+    assert!(!snapshots[3].contains("wants to read"));
+}
+
+/// Shows what it can find out about the other tabs
+struct QueryingTab {
+    answers: String,
+}
+impl BasicTab for QueryingTab {
+    fn initialize_tab(_manager_handler: &ManagerHandler) -> Result<Self, InitError> {
+        Ok(Self {
+            answers: String::new(),
+        })
+    }
+
+    fn render_tab(&mut self, _manager_handler: &ManagerHandler) -> Option<UIElement> {
+        Some(UIElement::CharGrid(self.answers.clone().into()))
+    }
+
+    fn handle_tab_event(&mut self, event: Event, manager_handler: &ManagerHandler) {
+        if let Event::UIEvent(singularity_ui::ui_event::UIEvent::KeyPress(..)) = event {
+            let query_channels = manager_handler.get_query_channels();
+
+            let tab_types = singularity_common::ask_query!(query_channels, TabList)
+                .unwrap()
+                .into_iter()
+                .map(|tab_info| tab_info.tab_type)
+                .collect::<Vec<_>>();
+            let focused_tab = singularity_common::ask_query!(query_channels, FocusedTab).unwrap();
+            let own_tab_data =
+                singularity_common::ask_query!(query_channels, TabDataAt, focused_tab.org_path)
+                    .unwrap()
+                    .unwrap();
+            let missing_tab =
+                singularity_common::ask_query!(query_channels, TabDataAt, TreeNodePath::from([99]))
+                    .unwrap();
+            let project_directory =
+                singularity_common::ask_query!(query_channels, ProjectDirectory).unwrap();
+
+            self.answers = format!(
+                "Tabs: {}\nFocused: {}\nOwn: {}\nMissing: {}\nProject: {}",
+                tab_types.join(", "),
+                focused_tab.tab_type,
+                own_tab_data.tab_type,
+                missing_tab.unwrap_err(),
+                project_directory.file_name().unwrap().to_string_lossy(),
+            );
+        }
+    }
+}
+
+#[test]
+fn tab_queries() {
+    let project_directory = copy_example_project();
+
+    let mut tab_registry = TabRegistry::new();
+    tab_registry.register_basic_tab::<QueryingTab>("QUERYING", "Querying");
+    let manager = new_manager_with_registry(project_directory.clone(), tab_registry);
+    let headless_display = HeadlessDisplay::new(
+        [100, 30],
+        [
+            ScriptStep::WaitForIdle,
+            ScriptStep::key_press(20, Some('t'), KeyModifiers::CTRL),
+            ScriptStep::key_press(28, None, KeyModifiers::NONE),
+            ScriptStep::WaitForIdle,
+            ScriptStep::key_press(19, Some('r'), KeyModifiers::NONE),
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+            ScriptStep::key_press(16, Some('q'), KeyModifiers::CTRL),
+        ],
+    );
+    let snapshots = headless_display.snapshots();
+    manager.run_headless(headless_display).unwrap();
+
+    let snapshot = snapshots.lock().unwrap()[0].to_string();
+    assert!(snapshot.contains("Tabs: TASK_ORGANIZER, TIME_MANAGER, QUERYING"));
+    assert!(snapshot.contains("Focused: QUERYING"));
+    assert!(snapshot.contains("Own: QUERYING"));
+    assert!(snapshot.contains("Missing: no tab at"));
+    // (the rest of the temporary directory's name doesn't fit)
+    assert!(snapshot.contains("Project: singularity-test-"));
+}
+
+#[test]
+fn newer_file_is_shown_not_overwritten() {
+    let project_directory = copy_example_project();
+    let blocks_path = project_directory.join(".project/blocks.json");
+    let newer_blocks = format!(
+        r#"{{ "schema_version": {}, "blocks": [] }}"#,
+        singularity_common::project::schema::BLOCKS_SCHEMA.current_version() + 1
+    );
+    std::fs::write(&blocks_path, &newer_blocks).unwrap();
+
+    let manager = new_manager(project_directory.clone());
+    let headless_display = HeadlessDisplay::new(
+        [100, 30],
+        [
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+            ScriptStep::key_press(16, Some('q'), KeyModifiers::CTRL),
+        ],
+    );
+    let snapshots = headless_display.snapshots();
+    manager.run_headless(headless_display).unwrap();
+
+    // the time manager can't start, but the rest of the project still opens
+    let snapshot = snapshots.lock().unwrap()[0].to_string();
+    assert!(snapshot.contains("Failed to start:"));
+    assert_eq!(std::fs::read_to_string(&blocks_path).unwrap(), newer_blocks);
+}
+
+#[test]
+fn launching_editor_on_directory_shows_error() {
+    let project_directory = copy_example_project();
+
+    let manager = new_manager(project_directory.clone());
+    let headless_display = HeadlessDisplay::new(
+        [100, 30],
+        [
+            ScriptStep::WaitForIdle,
+            // the editor is the first type, and the path starts as the project directory
+            ScriptStep::key_press(20, Some('t'), KeyModifiers::CTRL),
+            ScriptStep::key_press(28, None, KeyModifiers::NONE),
+            ScriptStep::WaitForIdle,
+            ScriptStep::Snapshot,
+            ScriptStep::key_press(16, Some('q'), KeyModifiers::CTRL),
+        ],
+    );
+    let snapshots = headless_display.snapshots();
+    manager.run_headless(headless_display).unwrap();
+
+    let snapshot = snapshots.lock().unwrap()[0].to_string();
+    assert!(snapshot.contains("Failed to start:"));
+    assert!(!snapshot.contains("crashed"));
 }
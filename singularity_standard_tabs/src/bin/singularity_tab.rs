@@ -0,0 +1,23 @@
+//! Runs one of the standard tabs as its own process, for `ExternalTab`.
+//!
+//! Mostly an example of an external tab, eg: in `core.json`:
+//! `"external_tabs": { "EXTERNAL_EDITOR": { "program": "singularity_tab", "args": ["EDITOR"] } }`
+//!
+//! Usage: `singularity_tab <TAB_TYPE>`
+
+use singularity_common::tab::{external::run_tab_over_stdio, registry::TabRegistry};
+
+fn main() {
+    let tab_type = std::env::args()
+        .nth(1)
+        .expect("usage: singularity_tab <TAB_TYPE>");
+
+    let mut tab_registry = TabRegistry::new();
+    singularity_standard_tabs::register_standard_tabs(&mut tab_registry);
+
+    let tab_creator = tab_registry
+        .get_tab_creator(&tab_type)
+        .unwrap_or_else(|| panic!("unknown tab type `{tab_type}`"));
+
+    run_tab_over_stdio(tab_creator);
+}
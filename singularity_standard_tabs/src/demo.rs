@@ -1,4 +1,6 @@
 #![cfg(test)]
+// still uses the deprecated `remap_event`
+#![allow(deprecated)]
 
 use singularity_common::{
     components::{button::Button, remap_event, text_box::TextBox, Component},
@@ -147,19 +149,26 @@ pub fn run_test() {
 
     let root_element = Arc::new(Mutex::new(test_widget.render()));
     let ui_event_queue = Arc::new(Mutex::new(Vec::new()));
+    let ui_event_wakeup = singularity_ui::wakeup::Wakeup::new();
     let is_running = Arc::new(AtomicBool::new(true));
 
     let ui_event_queue_clone = ui_event_queue.clone();
+    let ui_event_wakeup_clone = ui_event_wakeup.clone();
     let is_running_clone = is_running.clone();
     let ui_thread_handle = std::thread::spawn(move || {
         singularity_ui::UIDisplay::run_display(
             root_element,
+            // the demo draws only once
+            singularity_ui::wakeup::Wakeup::new(),
             ui_event_queue_clone,
+            ui_event_wakeup_clone,
             is_running_clone,
         );
     });
 
     while is_running.load(std::sync::atomic::Ordering::Relaxed) {
+        ui_event_wakeup.wait();
+
         for ui_event in std::mem::take(&mut *(ui_event_queue.lock().unwrap())) {
             use singularity_ui::ui_event::{KeyModifiers, UIEvent};
             match ui_event {
@@ -169,11 +178,16 @@ pub fn run_test() {
                     is_running.store(false, std::sync::atomic::Ordering::Relaxed);
                     return;
                 }
+                UIEvent::CloseRequested => {
+                    dbg!("Ending demo");
+                    is_running.store(false, std::sync::atomic::Ordering::Relaxed);
+                    return;
+                }
                 UIEvent::KeyPress(_, _) => {
                     test_widget
                         .handle_event(singularity_common::tab::packets::Event::UIEvent(ui_event));
                 }
-                UIEvent::WindowResized(_) => {}
+                UIEvent::WindowResized(_) | UIEvent::MouseMove(..) | UIEvent::MouseRelease(..) => {}
                 UIEvent::MousePress([[click_x, click_y], [tot_width, tot_height]], container) => {
                     test_widget.handle_event(singularity_common::tab::packets::Event::UIEvent(
                         singularity_ui::ui_event::UIEvent::MousePress(
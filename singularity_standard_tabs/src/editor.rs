@@ -1,14 +1,36 @@
+use serde::{Deserialize, Serialize};
 use singularity_common::{
     ask_query,
     components::{text_box::TextBox, Component},
+    keymap::Keymap,
     tab::{
         packets::{Event, Request},
-        BasicTab, ManagerHandler,
+        BasicTab, InitError, ManagerHandler,
     },
 };
-use singularity_ui::{color::Color, ui_element::UIElement, ui_event::KeyModifiers};
+use singularity_ui::{color::Color, ui_element::UIElement};
 use std::path::PathBuf;
 
+/// What the editor saves as its `TabData.session_data`
+#[derive(Serialize, Deserialize)]
+struct EditorSession {
+    file_path: PathBuf,
+    /// (x, y) or (col, row)
+    cursor: (usize, usize),
+}
+impl EditorSession {
+    /// NOTE: editors are opened with just the file path (eg: by the file manager)
+    fn from_session_data(session_data: serde_json::Value) -> Result<Self, serde_json::Error> {
+        match serde_json::from_value::<PathBuf>(session_data.clone()) {
+            Ok(file_path) => Ok(Self {
+                file_path,
+                cursor: (0, 0),
+            }),
+            Err(_) => serde_json::from_value(session_data),
+        }
+    }
+}
+
 /// Currently Just treats everything like plaintext.
 /// This is just the textbox but with a wrapper to work with files.
 ///
@@ -31,39 +53,43 @@ pub struct Editor {
     file_path: PathBuf,
 
     text_box: TextBox,
+    /// the text as it was last saved, to know if there are unsaved changes
+    saved_text: String,
+
+    keymap: Keymap,
 
     /// debug purpose
     /// TODO remove
     save_to_temp: bool,
 }
 impl Editor {
-    pub fn new<P>(file_path: P, manager_handler: &ManagerHandler) -> Self
+    pub fn new<P>(file_path: P, manager_handler: &ManagerHandler) -> Result<Self, InitError>
     where
         P: AsRef<std::path::Path>,
         PathBuf: std::convert::From<P>,
     {
-        let text_box = TextBox::new(Self::get_content(&file_path));
+        // (eg: the launcher's path is a directory)
+        let text_box = TextBox::new(
+            manager_handler
+                .read_file_to_string(file_path.as_ref())
+                .map_err(|err| format!("couldn't open the file: {err}"))?,
+        );
         let file_path = PathBuf::from(file_path);
 
         manager_handler.send_request(Request::ChangeName(
             file_path.file_name().unwrap().to_str().unwrap().to_string(),
         ));
 
-        Self {
+        Ok(Self {
             file_path,
+            saved_text: text_box.get_text_as_string(),
             text_box,
+            keymap: ask_query!(manager_handler.get_query_channels(), Keymap)?,
             save_to_temp: false,
-        }
+        })
     }
 
-    fn get_content<P>(file_path: P) -> String
-    where
-        P: AsRef<std::path::Path>,
-    {
-        std::fs::read_to_string(&file_path).unwrap()
-    }
-
-    fn save_to_file(&self) {
+    fn save_to_file(&mut self, manager_handler: &ManagerHandler) {
         let new_path = if self.save_to_temp {
             self.file_path.to_str().unwrap().to_string()
                 + ".temp"
@@ -76,17 +102,40 @@ impl Editor {
             self.file_path.to_str().unwrap().to_string()
         };
 
-        std::fs::write(new_path, self.text_box.get_text_as_string()).unwrap();
+        let text = self.text_box.get_text_as_string();
+        // (still unsaved if it didn't work, eg: the user didn't allow it)
+        match manager_handler.write_file(new_path, text.clone()) {
+            Ok(()) => self.saved_text = text,
+            Err(err) => println!("Failed to save `{}`: {err}", self.file_path.display()),
+        }
     }
 }
 impl BasicTab for Editor {
-    fn initialize_tab(manager_handler: &ManagerHandler) -> Self {
-        Self::new(
-            serde_json::from_value::<String>(
-                ask_query!(manager_handler.get_query_channels(), TabData).session_data,
-            )
+    fn initialize_tab(manager_handler: &ManagerHandler) -> Result<Self, InitError> {
+        let session = EditorSession::from_session_data(
+            ask_query!(manager_handler.get_query_channels(), TabData)?.session_data,
+        )?;
+
+        let mut editor = Self::new(session.file_path, manager_handler)?;
+        editor.text_box.set_cursor(session.cursor);
+        Ok(editor)
+    }
+
+    fn has_unsaved_changes(&mut self) -> bool {
+        self.text_box.get_text_as_string() != self.saved_text
+    }
+
+    fn save(&mut self, manager_handler: &ManagerHandler) {
+        self.save_to_file(manager_handler);
+    }
+
+    fn session_data(&self) -> Option<serde_json::Value> {
+        Some(
+            serde_json::to_value(EditorSession {
+                file_path: self.file_path.clone(),
+                cursor: self.text_box.get_cursor(),
+            })
             .unwrap(),
-            manager_handler,
         )
     }
 
@@ -106,21 +155,25 @@ impl BasicTab for Editor {
         )
     }
 
-    fn handle_tab_event(&mut self, event: Event, _manager_handler: &ManagerHandler) {
+    fn handle_tab_event(&mut self, event: Event, manager_handler: &ManagerHandler) {
         match event {
-            Event::UIEvent(ref ui_event) => match ui_event {
-                singularity_ui::ui_event::UIEvent::KeyPress(key, KeyModifiers::CTRL)
-                    if key.raw_code == 31 =>
-                {
-                    self.save_to_file();
-                }
-                _ => {
-                    self.text_box.handle_event(event);
+            Event::UIEvent(ref ui_event) => {
+                match self.keymap.get_action_from_event(&["tab"], ui_event) {
+                    Some("tab.save") => {
+                        self.save_to_file(manager_handler);
+                    }
+                    _ => {
+                        self.text_box.handle_event(event);
+                    }
                 }
-            },
+            }
             Event::Focused => {}
             Event::Unfocused => {}
             Event::Resize(_) => {}
+            Event::SaveSession
+            | Event::RequestClose
+            | Event::CloseCancelled
+            | Event::FileChanged(_) => {}
             Event::Close => panic!("Event::Close should not have been forwarded"),
         }
     }
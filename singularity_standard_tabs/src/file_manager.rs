@@ -1,37 +1,87 @@
+use serde::{Deserialize, Serialize};
 use singularity_common::{
     ask_query,
+    keymap::Keymap,
     project::project_settings::TabData,
     tab::{
-        packets::{Event, Request},
-        BasicTab, ManagerHandler,
+        packets::{Event, QueryError, Request},
+        BasicTab, InitError, ManagerHandler,
     },
     utils::tree::{
         rooted_tree::RootedTree,
-        tree_node_path::{TraversableTree, TreeNodePath, TREE_TRAVERSE_KEYS},
+        tree_node_path::{TraversableTree, TreeNodePath},
     },
 };
-use std::path::PathBuf;
+use std::{collections::HashSet, path::PathBuf};
+
+/// What the file manager saves as its `TabData.session_data`
+#[derive(Serialize, Deserialize)]
+struct FileManagerSession {
+    root_directory_path: PathBuf,
+    selected_path: TreeNodePath,
+}
+impl FileManagerSession {
+    /// NOTE: file managers are opened with just the directory (the project's if there's none)
+    fn from_session_data(
+        session_data: serde_json::Value,
+        project_directory: impl FnOnce() -> Result<PathBuf, QueryError>,
+    ) -> Result<Self, QueryError> {
+        match serde_json::from_value::<Option<PathBuf>>(session_data.clone()) {
+            Ok(root_directory_path) => Ok(Self {
+                root_directory_path: match root_directory_path
+                    .filter(|root_directory_path| !root_directory_path.as_os_str().is_empty())
+                {
+                    Some(root_directory_path) => root_directory_path,
+                    None => project_directory()?,
+                },
+                selected_path: TreeNodePath::new_root(),
+            }),
+            Err(_) => Ok(serde_json::from_value(session_data).unwrap()),
+        }
+    }
+}
 
 pub struct FileManager {
     directory_tree: RootedTree<PathBuf>,
+    /// which nodes of `directory_tree` are directories
+    directories: HashSet<PathBuf>,
     selected_path: TreeNodePath,
+
+    keymap: Keymap,
 }
 impl FileManager {
-    pub fn new<P>(root_directory_path: P, manager_handler: &ManagerHandler) -> Self
+    pub fn new<P>(
+        root_directory_path: P,
+        manager_handler: &ManagerHandler,
+    ) -> Result<Self, InitError>
     where
         PathBuf: std::convert::From<P>,
     {
+        let root_directory_path = PathBuf::from(root_directory_path);
+        let (directory_tree, directories) =
+            Self::generate_directory_tree(root_directory_path.clone(), manager_handler);
         let file_manager = Self {
-            directory_tree: Self::generate_directory_tree(PathBuf::from(root_directory_path)),
+            directory_tree,
+            directories,
             selected_path: TreeNodePath::new_root(),
+            keymap: ask_query!(manager_handler.get_query_channels(), Keymap)?,
         };
 
         manager_handler.send_request(Request::ChangeName(file_manager.get_directory_name()));
+        // NOTE: only notices changes directly in the root directory for now
+        if let Err(err) = manager_handler.watch(root_directory_path) {
+            println!("Not watching the directory: {err}");
+        }
 
-        file_manager
+        Ok(file_manager)
     }
 
-    fn generate_directory_tree(root_directory_path: PathBuf) -> RootedTree<PathBuf> {
+    /// Also returns which paths are directories
+    fn generate_directory_tree(
+        root_directory_path: PathBuf,
+        manager_handler: &ManagerHandler,
+    ) -> (RootedTree<PathBuf>, HashSet<PathBuf>) {
+        let mut directories = HashSet::from([root_directory_path.clone()]);
         let mut directory_tree = RootedTree::from_root(root_directory_path);
 
         // means the directory is added but its children arent
@@ -41,14 +91,20 @@ impl FileManager {
         while !unvisited_directories.is_empty() {
             let mut new_unvisited_directories = Vec::new();
             for directory_path in unvisited_directories {
-                for child in directory_tree[&directory_path].read_dir().unwrap() {
-                    let child_path = child.unwrap().path();
-
+                // (eg: the user didn't allow it, so it just looks empty)
+                let children = manager_handler
+                    .list_dir(&directory_tree[&directory_path])
+                    .unwrap_or_else(|err| {
+                        println!("Failed to list a directory: {err}");
+                        Vec::new()
+                    });
+                for child in children {
                     let child_tree_path = directory_tree
-                        .add_node(child_path.clone(), &directory_path)
+                        .add_node(child.path.clone(), &directory_path)
                         .unwrap();
 
-                    if child_path.is_dir() {
+                    if child.is_dir {
+                        directories.insert(child.path);
                         new_unvisited_directories.push(child_tree_path);
                     }
                 }
@@ -57,7 +113,7 @@ impl FileManager {
             unvisited_directories = new_unvisited_directories;
         }
 
-        directory_tree
+        (directory_tree, directories)
     }
 
     fn get_directory_name(&self) -> String {
@@ -70,13 +126,31 @@ impl FileManager {
     }
 }
 impl BasicTab for FileManager {
-    fn initialize_tab(manager_handler: &ManagerHandler) -> Self {
-        Self::new(
-            serde_json::from_value::<String>(
-                ask_query!(manager_handler.get_query_channels(), TabData).session_data,
-            )
+    fn initialize_tab(manager_handler: &ManagerHandler) -> Result<Self, InitError> {
+        let query_channels = manager_handler.get_query_channels();
+        let session = FileManagerSession::from_session_data(
+            ask_query!(query_channels, TabData)?.session_data,
+            || ask_query!(query_channels, ProjectDirectory),
+        )?;
+
+        let mut file_manager = Self::new(session.root_directory_path, manager_handler)?;
+        // the directory might have changed since
+        if file_manager
+            .directory_tree
+            .exists_at(&session.selected_path)
+        {
+            file_manager.selected_path = session.selected_path;
+        }
+        Ok(file_manager)
+    }
+
+    fn session_data(&self) -> Option<serde_json::Value> {
+        Some(
+            serde_json::to_value(FileManagerSession {
+                root_directory_path: self.directory_tree[&TreeNodePath::new_root()].clone(),
+                selected_path: self.selected_path.clone(),
+            })
             .unwrap(),
-            manager_handler,
         )
     }
 
@@ -124,26 +198,18 @@ impl BasicTab for FileManager {
     }
 
     fn handle_tab_event(&mut self, event: Event, manager_handler: &ManagerHandler) {
-        use singularity_ui::ui_event::{KeyModifiers, KeyTrait, UIEvent};
+        use singularity_ui::ui_event::{KeyModifiers, UIEvent};
         match event {
             Event::UIEvent(ui_event) => match ui_event {
-                UIEvent::KeyPress(key, KeyModifiers::NONE)
-                    if key.to_char() == Some('\n')
-                    // `' '` is a placeholder for some key that isn't in tree traverse
-                    || TREE_TRAVERSE_KEYS.contains(&key.to_char().unwrap_or(' ')) =>
-                {
-                    self.selected_path = self.selected_path.clamped_traverse_based_on_wasd(
-                        &self.directory_tree,
-                        key.to_char().unwrap(),
-                    );
-                }
-                UIEvent::KeyPress(key, KeyModifiers::NONE)
-                    if matches!(key.to_char(), Some('f')) =>
+                UIEvent::KeyPress(key, key_modifiers)
+                    if self
+                        .keymap
+                        .is_action("file_manager.open_file", &key, key_modifiers) =>
                 {
                     // `f` stands for open selected *F*ile
 
                     let selected_element = &self.directory_tree[&self.selected_path];
-                    if selected_element.is_file() {
+                    if !self.directories.contains(selected_element) {
                         use crate::editor::Editor;
                         manager_handler.send_request(Request::SpawnChildTab(
                             Box::new(Editor::new_tab_creator()),
@@ -156,12 +222,34 @@ impl BasicTab for FileManager {
                     }
                     // if selected path isn't a file, then don't do anything
                 }
+                UIEvent::KeyPress(key, key_modifiers) => {
+                    if let Some(traversal) =
+                        self.keymap
+                            .get_tree_traversal(&key, key_modifiers, KeyModifiers::NONE)
+                    {
+                        self.selected_path = self
+                            .selected_path
+                            .clamped_traverse(&self.directory_tree, traversal);
+                    }
+                }
 
                 _ => {}
             },
             Event::Focused => {}
             Event::Unfocused => {}
             Event::Resize(_) => {}
+            Event::FileChanged(_) => {
+                let (directory_tree, directories) = Self::generate_directory_tree(
+                    self.directory_tree[&TreeNodePath::new_root()].clone(),
+                    manager_handler,
+                );
+                self.directory_tree = directory_tree;
+                self.directories = directories;
+                if !self.directory_tree.exists_at(&self.selected_path) {
+                    self.selected_path = TreeNodePath::new_root();
+                }
+            }
+            Event::SaveSession | Event::RequestClose | Event::CloseCancelled => {}
             Event::Close => panic!("Event::Close should not have been forwarded"),
         }
     }
@@ -1,20 +1,20 @@
 pub mod demo;
 pub mod editor;
 pub mod file_manager;
+// still uses the deprecated `ComposeComponents`
+#[allow(deprecated)]
 pub mod task_organizer;
 pub mod time_manager;
 
-/// FIXME
-pub fn get_tab_creator_from_type(tab_type: &str) -> Box<dyn singularity_common::tab::TabCreator> {
-    use singularity_common::tab::BasicTab;
-    match tab_type {
-        "EDITOR" => Box::new(editor::Editor::new_tab_creator()),
-        "FILE_MANAGER" => Box::new(file_manager::FileManager::new_tab_creator()),
-        "TASK_ORGANIZER" => Box::new(task_organizer::TaskOrganizer::new_tab_creator()),
-        "TIME_MANAGER" => Box::new(time_manager::TimeManager::new_tab_creator()),
-        // "DEMO" => Box::new(demo::Test::new_tab_creator(
-        //     serde_json::from_value::<String>(tab_data).unwrap(),
-        // )),
-        _ => panic!(),
-    }
+/// Registers every tab in this crate.
+///
+/// NOTE: the editor takes a file path (as a string) for its session data, and the file manager a directory
+/// (an empty one is the project). The others use the project's files.
+/// An editor whose file can't be read shows the error instead.
+pub fn register_standard_tabs(tab_registry: &mut singularity_common::tab::registry::TabRegistry) {
+    tab_registry.register_basic_tab::<editor::Editor>("EDITOR", "Editor");
+    tab_registry.register_basic_tab::<file_manager::FileManager>("FILE_MANAGER", "File Manager");
+    tab_registry
+        .register_basic_tab::<task_organizer::TaskOrganizer>("TASK_ORGANIZER", "Task Organizer");
+    tab_registry.register_basic_tab::<time_manager::TimeManager>("TIME_MANAGER", "Time Manager");
 }
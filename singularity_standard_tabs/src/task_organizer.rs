@@ -2,13 +2,14 @@ use serde::{Deserialize, Serialize};
 use singularity_common::{
     ask_query,
     components::{button::ToggleButton, text_box::TextBox, timer_widget::TimerWidget, Component},
+    keymap::Keymap,
+    project::schema::{SchemaError, TASKS_SCHEMA},
+    tab::file_system::FsError,
     tab::packets::Event,
+    tab::InitError,
     utils::{
         timer::Timer,
-        tree::{
-            recursive_tree::RecursiveTreeNode,
-            tree_node_path::{TreeNodePath, TREE_TRAVERSE_KEYS},
-        },
+        tree::{recursive_tree::RecursiveTreeNode, tree_node_path::TreeNodePath},
     },
 };
 use singularity_macros::ComposeComponents;
@@ -40,6 +41,20 @@ impl Default for IndividualTask {
     }
 }
 
+/// What the task organizer saves as its `TabData.session_data`
+///
+/// NOTE: the project comes from `ProjectDirectory`, older sessions also have a `project_path` (ignored)
+#[derive(Default, Serialize, Deserialize)]
+struct TaskOrganizerSession {
+    focused_task: Option<TreeNodePath>,
+}
+impl TaskOrganizerSession {
+    /// NOTE: task organizers used to be opened with just the project path, and the launcher still gives one
+    fn from_session_data(session_data: serde_json::Value) -> Self {
+        serde_json::from_value(session_data).unwrap_or_default()
+    }
+}
+
 #[derive(ComposeComponents)]
 struct IndividualTaskWidget {
     task_path: TreeNodePath,
@@ -126,6 +141,10 @@ impl Component for IndividualTaskWidget {
             Event::Focused => {}
             Event::Unfocused => {}
             Event::Resize(_) => {}
+            Event::SaveSession
+            | Event::RequestClose
+            | Event::CloseCancelled
+            | Event::FileChanged(_) => {}
             Event::Close => panic!("Event::Close should not have been forwarded"),
         }
     }
@@ -163,12 +182,17 @@ pub struct TaskOrganizer {
 
     /// If editing mode, there should be Some focused task
     mode: Mode,
+
+    /// the tasks as they were last written to the file, to know if there are unsaved changes
+    saved_tasks: String,
+
+    keymap: Keymap,
 }
 impl TaskOrganizer {
     pub fn new_from_project<P>(
         project_path: P,
         manager_handler: &singularity_common::tab::ManagerHandler,
-    ) -> Self
+    ) -> Result<Self, InitError>
     where
         P: AsRef<std::path::Path>,
         PathBuf: std::convert::From<P>,
@@ -183,23 +207,56 @@ impl TaskOrganizer {
     pub fn new<P>(
         task_file_path: P,
         manager_handler: &singularity_common::tab::ManagerHandler,
-    ) -> Self
+    ) -> Result<Self, InitError>
     where
         P: AsRef<std::path::Path>,
         PathBuf: std::convert::From<P>,
     {
-        let tasks =
-            serde_json::from_str(&std::fs::read_to_string(&task_file_path).unwrap()).unwrap();
+        // a missing task file is an empty task tree (it's created on the first save)
+        let tasks: RecursiveTreeNode<IndividualTask> =
+            match TASKS_SCHEMA.load_through(manager_handler, task_file_path.as_ref()) {
+                Ok(tasks) => tasks,
+                Err(SchemaError::Fs(FsError::NotFound(_))) => {
+                    RecursiveTreeNode::from_value(IndividualTask {
+                        title: "Root".to_string(),
+                        body: String::new(),
+                        is_complete: false,
+                        timer: None,
+                    })
+                }
+                // (including one from a newer build, which would be overwritten on the next save)
+                Err(err) => return Err(format!("couldn't load the task file: {err}").into()),
+            };
 
         manager_handler.send_request(singularity_common::tab::packets::Request::ChangeName(
             "Task Organizer".to_string(),
         ));
 
-        Self {
+        Ok(Self {
             task_file_path: PathBuf::from(task_file_path),
+            saved_tasks: TASKS_SCHEMA.to_string(&tasks),
             tasks,
             focused_task_widget: None,
             mode: Mode::Viewing,
+            keymap: ask_query!(manager_handler.get_query_channels(), Keymap)?,
+        })
+    }
+
+    /// Also saves the focused task's changes into `tasks`
+    fn serialize_tasks(&mut self) -> String {
+        if let Some(focused_task) = &self.focused_task_widget {
+            focused_task.save_into(&mut self.tasks);
+        }
+
+        TASKS_SCHEMA.to_string(&self.tasks)
+    }
+
+    fn save_to_file(&mut self, manager_handler: &singularity_common::tab::ManagerHandler) {
+        let serialized_tasks = self.serialize_tasks();
+        // (still unsaved if it didn't work, eg: the user didn't allow it)
+        match manager_handler.write_file(&self.task_file_path, serialized_tasks.clone()) {
+            Ok(()) => self.saved_tasks = serialized_tasks,
+            Err(err) => println!("Failed to save the tasks: {err}"),
         }
     }
 
@@ -278,13 +335,44 @@ impl TaskOrganizer {
     }
 }
 impl singularity_common::tab::BasicTab for TaskOrganizer {
-    fn initialize_tab(manager_handler: &singularity_common::tab::ManagerHandler) -> Self {
-        Self::new_from_project(
-            serde_json::from_value::<String>(
-                ask_query!(manager_handler.get_query_channels(), TabData).session_data,
-            )
-            .unwrap(),
+    fn initialize_tab(
+        manager_handler: &singularity_common::tab::ManagerHandler,
+    ) -> Result<Self, InitError> {
+        let query_channels = manager_handler.get_query_channels();
+        let session = TaskOrganizerSession::from_session_data(
+            ask_query!(query_channels, TabData)?.session_data,
+        );
+
+        let mut task_organizer = Self::new_from_project(
+            ask_query!(query_channels, ProjectDirectory)?,
             manager_handler,
+        )?;
+        // the tasks might have changed since
+        if let Some(focused_task) = session.focused_task {
+            if task_organizer.tasks.safe_get(&focused_task).is_some() {
+                task_organizer.set_focused_task(&focused_task);
+            }
+        }
+        Ok(task_organizer)
+    }
+
+    fn has_unsaved_changes(&mut self) -> bool {
+        self.serialize_tasks() != self.saved_tasks
+    }
+
+    fn save(&mut self, manager_handler: &singularity_common::tab::ManagerHandler) {
+        self.save_to_file(manager_handler);
+    }
+
+    fn session_data(&self) -> Option<serde_json::Value> {
+        Some(
+            serde_json::to_value(TaskOrganizerSession {
+                focused_task: self
+                    .focused_task_widget
+                    .as_ref()
+                    .map(|focused_task_widget| focused_task_widget.task_path.clone()),
+            })
+            .unwrap(),
         )
     }
 
@@ -353,62 +441,83 @@ impl singularity_common::tab::BasicTab for TaskOrganizer {
     fn handle_tab_event(
         &mut self,
         event: singularity_common::tab::packets::Event,
-        _manager_handler: &singularity_common::tab::ManagerHandler,
+        manager_handler: &singularity_common::tab::ManagerHandler,
     ) {
         use singularity_common::tab::packets::Event;
-        use singularity_ui::ui_event::{KeyModifiers, KeyTrait, UIEvent};
+        use singularity_ui::ui_event::{KeyModifiers, UIEvent};
 
         match self.mode {
             Mode::Viewing => match event {
                 Event::UIEvent(ui_event) => match ui_event {
-                    UIEvent::KeyPress(key, KeyModifiers::SHIFT) if key.to_char() == Some('+') => {
+                    UIEvent::KeyPress(key, key_modifiers)
+                        if self.keymap.is_action(
+                            "task_organizer.add_task",
+                            &key,
+                            key_modifiers,
+                        ) =>
+                    {
                         // add a placeholder root task & focus on it
 
-                        let prev_focused_path = self.focused_task_widget.as_ref().map_or(
-                            TreeNodePath::new_root(),
-                            |focused_task_widget| focused_task_widget.task_path.clone());
+                        let prev_focused_path = self
+                            .focused_task_widget
+                            .as_ref()
+                            .map_or(TreeNodePath::new_root(), |focused_task_widget| {
+                                focused_task_widget.task_path.clone()
+                            });
+
+                        self.tasks
+                            .safe_get_mut(&prev_focused_path)
+                            .unwrap()
+                            .push_child_node(RecursiveTreeNode::from_value(
+                                IndividualTask::default(),
+                            ));
 
-                        self.tasks.safe_get_mut(&prev_focused_path).unwrap().push_child_node(
-                                RecursiveTreeNode::from_value(IndividualTask::default()),
+                        self.set_focused_task(
+                            &prev_focused_path
+                                .traverse_to_last_child(&self.tasks)
+                                .unwrap(),
                         );
-
-                        self.set_focused_task(&prev_focused_path.traverse_to_last_child(&self.tasks).unwrap());
                     }
-                    UIEvent::KeyPress(key, KeyModifiers::CTRL) if key.to_char() == Some('s') => {
-                        // save body
-                        if let Some(focused_task) = &self.focused_task_widget {
-                            focused_task.save_into(&mut self.tasks);
-                        }
-
-                        // save to file
-                        std::fs::write(
-                            &self.task_file_path,
-                            serde_json::to_string_pretty(&self.tasks).unwrap(),
-                        )
-                        .unwrap();
+                    UIEvent::KeyPress(key, key_modifiers)
+                        if self.keymap.is_action("tab.save", &key, key_modifiers) =>
+                    {
+                        self.save_to_file(manager_handler);
                     }
-                    UIEvent::KeyPress(key, KeyModifiers::NONE) if key.to_char() == Some('\n') => {
+                    UIEvent::KeyPress(key, key_modifiers)
+                        if self.keymap.is_action(
+                            "task_organizer.start_editing",
+                            &key,
+                            key_modifiers,
+                        ) =>
+                    {
                         // NOTE: Enter+CONTROL doesn't work as an event for some reason
                         // enter edit mode
 
                         self.set_mode(Mode::Editing);
                     }
-                    UIEvent::KeyPress(traverse_key, KeyModifiers::NONE)
-                    // `' '` is a placeholder for some key that isn't in tree traverse
-                    if TREE_TRAVERSE_KEYS.contains(&traverse_key.to_char().unwrap_or(' ')) =>
+                    UIEvent::KeyPress(key, key_modifiers)
+                        if self
+                            .keymap
+                            .get_tree_traversal(&key, key_modifiers, KeyModifiers::NONE)
+                            .is_some() =>
                     {
-                        if let Some(IndividualTaskWidget { task_path, .. },) = &self.focused_task_widget
+                        let traversal = self
+                            .keymap
+                            .get_tree_traversal(&key, key_modifiers, KeyModifiers::NONE)
+                            .unwrap();
+                        if let Some(IndividualTaskWidget { task_path, .. }) =
+                            &self.focused_task_widget
                         {
-                            self.set_focused_task(&task_path.clamped_traverse_based_on_wasd(
-                                &self.tasks,
-                                traverse_key.to_char().unwrap(),
-                            ));
+                            self.set_focused_task(
+                                &task_path.clamped_traverse(&self.tasks, traversal),
+                            );
                         } else {
                             self.set_focused_task(&TreeNodePath::new_root());
                         }
                     }
                     UIEvent::MousePress(..) => {
-                        let forward_result = self.forward_events_to_focused(Event::UIEvent(ui_event.clone()));
+                        let forward_result =
+                            self.forward_events_to_focused(Event::UIEvent(ui_event.clone()));
 
                         // even if focused_task_widget is none, forward events just checks if mouseclick is within area
                         // FIXME fix ^
@@ -418,7 +527,8 @@ impl singularity_common::tab::BasicTab for TaskOrganizer {
                                 self.set_mode(Mode::Editing);
 
                                 // re-forward the event
-                                self.forward_events_to_focused(Event::UIEvent(ui_event)).unwrap();
+                                self.forward_events_to_focused(Event::UIEvent(ui_event))
+                                    .unwrap();
                             }
                         }
                     }
@@ -427,30 +537,34 @@ impl singularity_common::tab::BasicTab for TaskOrganizer {
                 Event::Focused => {}
                 Event::Unfocused => {}
                 Event::Resize(_) => {}
+                Event::SaveSession
+                | Event::RequestClose
+                | Event::CloseCancelled
+                | Event::FileChanged(_) => {}
                 Event::Close => panic!("Event::Close should not have been forwarded"),
             },
             Mode::Editing => match &event {
-                Event::UIEvent(UIEvent::KeyPress(key, KeyModifiers::CTRL))
-                    if key.to_char() == Some('s') =>
+                Event::UIEvent(UIEvent::KeyPress(key, key_modifiers))
+                    if self.keymap.is_action("tab.save", key, *key_modifiers) =>
                 {
-                    // save body
-                    if let Some(focused_task) = &self.focused_task_widget {
-                        focused_task.save_into(&mut self.tasks);
-                    }
-
-                    // save to file
-                    std::fs::write(
-                        &self.task_file_path,
-                        serde_json::to_string_pretty(&self.tasks).unwrap(),
-                    )
-                    .unwrap();
+                    self.save_to_file(manager_handler);
                 }
-                Event::UIEvent(UIEvent::KeyPress(key, KeyModifiers::NONE)) if key.raw_code == 1 => {
-                    // ESCAPE KEY, switch to viewing mode
+                Event::UIEvent(UIEvent::KeyPress(key, key_modifiers))
+                    if self.keymap.is_action(
+                        "task_organizer.stop_editing",
+                        key,
+                        *key_modifiers,
+                    ) =>
+                {
+                    // switch to viewing mode
 
                     self.set_mode(Mode::Viewing);
                 }
                 Event::Resize(_) => {}
+                Event::SaveSession
+                | Event::RequestClose
+                | Event::CloseCancelled
+                | Event::FileChanged(_) => {}
                 Event::Close => panic!("Event::Close should not have been forwarded"),
                 _ => {
                     if self.forward_events_to_focused(event.clone()).is_err() {
@@ -2,7 +2,9 @@ use serde::{Deserialize, Serialize};
 use singularity_common::{
     ask_query,
     components::{text_box::TextBox, Component},
-    tab::packets::Event,
+    keymap::Keymap,
+    project::schema::{BlocksFile, SchemaError, BLOCKS_SCHEMA},
+    tab::{packets::Event, InitError},
 };
 use singularity_ui::{
     color::Color,
@@ -55,6 +57,8 @@ pub struct TimeManager {
 
     title_editor: TextBox,
     body_editor: TextBox,
+
+    keymap: Keymap,
 }
 impl TimeManager {
     const TITLE_EDITOR_AREA: DisplayArea = DisplayArea::new_proportional([[0.5, 0.0], [1.0, 0.3]]);
@@ -65,7 +69,7 @@ impl TimeManager {
     pub fn new_from_project<P>(
         project_path: P,
         manager_handler: &singularity_common::tab::ManagerHandler,
-    ) -> Self
+    ) -> Result<Self, InitError>
     where
         P: AsRef<std::path::Path>,
         PathBuf: std::convert::From<P>,
@@ -80,19 +84,26 @@ impl TimeManager {
     pub fn new<P>(
         blocks_file_path: P,
         manager_handler: &singularity_common::tab::ManagerHandler,
-    ) -> Self
+    ) -> Result<Self, InitError>
     where
         P: AsRef<std::path::Path>,
         PathBuf: std::convert::From<P>,
     {
-        let blocks = Self::parse_blocks(&blocks_file_path).unwrap_or_default();
+        // a missing file is no blocks yet, but a newer one shouldn't be overwritten on the next save
+        let blocks = match BLOCKS_SCHEMA
+            .load_through::<BlocksFile<Block>>(manager_handler, blocks_file_path.as_ref())
+        {
+            Ok(blocks_file) => blocks_file.blocks,
+            Err(err @ SchemaError::TooNew { .. }) => return Err(err.into()),
+            Err(_) => Blocks::default(),
+        };
         let num_blocks = blocks.len();
 
         manager_handler.send_request(singularity_common::tab::packets::Request::ChangeName(
             "Time Manager".to_string(),
         ));
 
-        Self {
+        Ok(Self {
             blocks_file_path: blocks_file_path.into(),
             blocks,
             mode: Mode::Idle,
@@ -100,22 +111,17 @@ impl TimeManager {
             focus: Focus::Timer,
             title_editor: TextBox::new(format!("Block {}", num_blocks)),
             body_editor: TextBox::default(),
-        }
-    }
 
-    fn parse_blocks<P>(blocks_file_path: &P) -> Option<Blocks>
-    where
-        P: AsRef<std::path::Path>,
-    {
-        serde_json::from_str(&std::fs::read_to_string(blocks_file_path).ok()?).ok()
+            keymap: ask_query!(manager_handler.get_query_channels(), Keymap)?,
+        })
     }
 
-    fn save_to_file(&self) {
-        std::fs::write(
-            &self.blocks_file_path,
-            serde_json::to_string_pretty(&self.blocks).unwrap(),
-        )
-        .unwrap();
+    fn save_to_file(&self, manager_handler: &singularity_common::tab::ManagerHandler) {
+        let serialized_blocks =
+            BLOCKS_SCHEMA.to_string(&serde_json::json!({ "blocks": self.blocks }));
+        if let Err(err) = manager_handler.write_file(&self.blocks_file_path, serialized_blocks) {
+            println!("Failed to save the blocks: {err}");
+        }
     }
 
     /// TODO: this should be more abstract and widespread
@@ -131,12 +137,11 @@ impl TimeManager {
     }
 }
 impl singularity_common::tab::BasicTab for TimeManager {
-    fn initialize_tab(manager_handler: &singularity_common::tab::ManagerHandler) -> Self {
+    fn initialize_tab(
+        manager_handler: &singularity_common::tab::ManagerHandler,
+    ) -> Result<Self, InitError> {
         Self::new_from_project(
-            serde_json::from_value::<String>(
-                ask_query!(manager_handler.get_query_channels(), TabData).session_data,
-            )
-            .unwrap(),
+            ask_query!(manager_handler.get_query_channels(), ProjectDirectory)?,
             manager_handler,
         )
     }
@@ -184,15 +189,23 @@ impl singularity_common::tab::BasicTab for TimeManager {
         )
     }
 
+    fn rerender_interval(&self) -> Option<Duration> {
+        match self.mode {
+            // the elapsed time needs to keep updating
+            Mode::Timing { start_time: _ } => Some(Duration::from_millis(100)),
+            Mode::Idle => None,
+        }
+    }
+
     fn handle_tab_event(
         &mut self,
         event: singularity_common::tab::packets::Event,
-        _manager_handler: &singularity_common::tab::ManagerHandler,
+        manager_handler: &singularity_common::tab::ManagerHandler,
     ) {
-        use singularity_ui::ui_event::{KeyModifiers, KeyTrait, UIEvent};
-        if let Event::UIEvent(UIEvent::KeyPress(ref key, KeyModifiers::CTRL)) = event {
-            if key.to_char() == Some('s') {
-                self.save_to_file();
+        use singularity_ui::ui_event::UIEvent;
+        if let Event::UIEvent(ref ui_event) = event {
+            if let Some("tab.save") = self.keymap.get_action_from_event(&["tab"], ui_event) {
+                self.save_to_file(manager_handler);
                 return;
             }
         }
@@ -232,11 +245,11 @@ impl singularity_common::tab::BasicTab for TimeManager {
             Focus::Timer => {
                 let was_clicked = match remapped_event {
                     Event::UIEvent(UIEvent::MousePress(..)) => true,
-                    Event::UIEvent(UIEvent::KeyPress(key, KeyModifiers::NONE))
-                        if key.to_char() == Some(' ') =>
-                    {
-                        true
-                    }
+                    Event::UIEvent(ref ui_event) => matches!(
+                        self.keymap
+                            .get_action_from_event(&["time_manager"], ui_event),
+                        Some("time_manager.toggle_timer")
+                    ),
                     _ => false,
                 };
 
@@ -0,0 +1,161 @@
+//! Runs tabs as their own process (with the `singularity_tab` binary), like the manager would.
+
+use singularity_common::{
+    keymap::Keymap,
+    project::project_settings::{MergedSubappSettings, TabData},
+    tab::{
+        external::ExternalTab,
+        file_system,
+        packets::{QueryError, Request, TabInfo},
+        TabCreator, TabHandler,
+    },
+    utils::tree::tree_node_path::TreeNodePath,
+};
+use singularity_ui::{cell_buffer::CellBuffer, display_units::DisplayArea, wakeup::Wakeup};
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// Does the manager's job for a single tab (in the example project) until `is_done` (or a timeout),
+/// and returns what the tab displays
+fn run_until<F: 'static + TabCreator>(
+    tab_creator: F,
+    tab_data: TabData,
+    is_done: impl Fn(&TabHandler, &str) -> bool,
+) -> (TabHandler, String) {
+    let manager_wakeup = Wakeup::new();
+    let mut tab_handler = TabHandler::new(
+        tab_creator,
+        tab_data.clone(),
+        DisplayArea::FULL,
+        manager_wakeup.clone(),
+    );
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let tab_info = TabInfo {
+            org_path: TreeNodePath::new_root(),
+            tab_name: tab_handler.tab_name.clone(),
+            tab_type: tab_data.tab_type.clone(),
+        };
+        tab_handler.get_respond_channels().answer_queries(
+            usize::MAX,
+            TreeNodePath::new_root,
+            || "External".to_string(),
+            || tab_data.clone(),
+            Keymap::default,
+            MergedSubappSettings::default,
+            || PathBuf::from("../examples/root-project"),
+            || vec![tab_info.clone()],
+            || tab_info.clone(),
+            |org_path| {
+                if org_path == TreeNodePath::new_root() {
+                    Ok(tab_data.clone())
+                } else {
+                    Err(QueryError::NoSuchTab(org_path))
+                }
+            },
+        );
+        for request in tab_handler.collect_requests() {
+            match request {
+                Request::ChangeName(new_name) => tab_handler.tab_name = new_name,
+                // (no permissions to check here)
+                Request::FileSystem(fs_request) => {
+                    tab_handler.respond_fs(file_system::perform(&fs_request))
+                }
+                _ => {}
+            }
+        }
+
+        let mut frame = CellBuffer::new(100, 30);
+        frame.draw_element(&tab_handler.get_ui_element(), DisplayArea::FULL);
+        let displayed = frame.to_string();
+
+        if is_done(&tab_handler, &displayed) {
+            return (tab_handler, displayed);
+        }
+        assert!(
+            Instant::now() < deadline,
+            "timed out, displaying:\n{displayed}"
+        );
+
+        manager_wakeup.wait_timeout(Duration::from_millis(50));
+    }
+}
+
+#[test]
+fn external_time_manager() {
+    let (tab_handler, _) = run_until(
+        ExternalTab::new(
+            env!("CARGO_BIN_EXE_singularity_tab"),
+            vec!["TIME_MANAGER".to_string()],
+        ),
+        TabData {
+            tab_type: "EXTERNAL_TIME_MANAGER".to_string(),
+            // (the project comes from `ProjectDirectory`)
+            session_data: serde_json::Value::Null,
+        },
+        |tab_handler, displayed| {
+            tab_handler.tab_name == "Time Manager" && displayed.contains("Idle - Click")
+        },
+    );
+
+    tab_handler.send_event(singularity_common::tab::packets::Event::Close);
+}
+
+#[test]
+fn external_tab_exiting() {
+    run_until(
+        ExternalTab::new("false", Vec::new()),
+        TabData {
+            tab_type: "FALSE".to_string(),
+            session_data: serde_json::Value::Null,
+        },
+        |_, displayed| displayed.contains("`false` exited"),
+    );
+}
+
+#[test]
+fn external_tab_missing_program() {
+    run_until(
+        ExternalTab::new("./not-a-real-program", Vec::new()),
+        TabData {
+            tab_type: "MISSING".to_string(),
+            session_data: serde_json::Value::Null,
+        },
+        |_, displayed| displayed.contains("Failed to start"),
+    );
+}
+
+#[test]
+fn external_tab_breaking_protocol() {
+    // prints text instead of a handshake
+    run_until(
+        ExternalTab::new("echo", vec!["hello world".to_string()]),
+        TabData {
+            tab_type: "ECHO".to_string(),
+            session_data: serde_json::Value::Null,
+        },
+        |_, displayed| displayed.contains("`echo` broke the protocol"),
+    );
+}
+
+#[test]
+fn external_tab_never_handshaking() {
+    // keeps running without ever writing anything
+    let (tab_handler, _) = run_until(
+        ExternalTab::new("sleep", vec!["30".to_string()]),
+        TabData {
+            tab_type: "SLEEP".to_string(),
+            session_data: serde_json::Value::Null,
+        },
+        |_, _| true,
+    );
+
+    tab_handler.send_event(singularity_common::tab::packets::Event::Close);
+    assert!(
+        tab_handler.join(Instant::now() + Duration::from_secs(5)),
+        "closing a tab that never handshook hung"
+    );
+}
@@ -0,0 +1,229 @@
+//! A grid of characters that a `UIElement` can be drawn onto.
+//!
+//! The rest of singularity thinks in pixels (the wayland backend's font is 12px tall and 6px wide),
+//! so this pretends that each cell is `CELL_WIDTH` by `CELL_HEIGHT` pixels.
+//! That way, tabs that use pixel units (like the task organizer's tree) look about the same on every backend.
+
+use crate::{
+    color::Color,
+    display_units::{DisplayArea, DisplayCoord, DisplayUnits},
+    ui_element::{CharCell, UIElement},
+};
+
+/// How many "pixels" wide a cell is, same as the wayland backend's character width
+pub const CELL_WIDTH: i32 = 6;
+/// How many "pixels" tall a cell is, same as the wayland backend's font size
+pub const CELL_HEIGHT: i32 = 12;
+
+const EMPTY_CELL: CharCell = CharCell {
+    character: ' ',
+    fg: Color::TRANSPARENT,
+    bg: Color::TRANSPARENT,
+};
+
+/// What each cell of the display should look like.
+///
+/// Used by the backends that can only draw characters (terminal and headless).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellBuffer {
+    width: usize,
+    height: usize,
+    lines: Vec<Vec<CharCell>>,
+}
+impl CellBuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            lines: vec![vec![EMPTY_CELL; width]; height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn lines(&self) -> &[Vec<CharCell>] {
+        &self.lines
+    }
+
+    pub fn get_cell(&self, x: usize, y: usize) -> Option<&CharCell> {
+        self.lines.get(y)?.get(x)
+    }
+
+    /// The size of the buffer in "pixels"
+    pub fn window_px(&self) -> [u32; 2] {
+        [
+            self.width as u32 * CELL_WIDTH as u32,
+            self.height as u32 * CELL_HEIGHT as u32,
+        ]
+    }
+
+    /// Converts the area into cells, as `([x0, y0], [x1, y1])` where the end is exclusive
+    fn area_cells(&self, area: DisplayArea) -> ([usize; 2], [usize; 2]) {
+        let [width_px, height_px] = self.window_px().map(|px| px as i32);
+
+        let to_cell = |units: DisplayUnits, container_px: i32, cell_px: i32, max: usize| {
+            (units.pixels(container_px).max(0) / cell_px).min(max as i32) as usize
+        };
+
+        (
+            [
+                to_cell(area.0.x, width_px, CELL_WIDTH, self.width),
+                to_cell(area.0.y, height_px, CELL_HEIGHT, self.height),
+            ],
+            [
+                to_cell(area.1.x, width_px, CELL_WIDTH, self.width),
+                to_cell(area.1.y, height_px, CELL_HEIGHT, self.height),
+            ],
+        )
+    }
+
+    /// transparent backgrounds keep whatever was behind them
+    fn set_cell(&mut self, x: usize, y: usize, CharCell { character, fg, bg }: CharCell) {
+        if let Some(cell) = self.lines.get_mut(y).and_then(|line| line.get_mut(x)) {
+            cell.character = character;
+            cell.fg = fg;
+            if bg != Color::TRANSPARENT {
+                cell.bg = bg;
+            }
+        }
+    }
+
+    fn fill_rect(&mut self, area: DisplayArea, color: Color) {
+        let ([x0, y0], [x1, y1]) = self.area_cells(area);
+        for y in y0..y1 {
+            for x in x0..x1 {
+                self.set_cell(
+                    x,
+                    y,
+                    CharCell {
+                        character: ' ',
+                        fg: Color::TRANSPARENT,
+                        bg: color,
+                    },
+                );
+            }
+        }
+    }
+
+    fn draw_border(&mut self, area: DisplayArea, color: Color) {
+        let ([x0, y0], [x1, y1]) = self.area_cells(area);
+        if x1 <= x0 || y1 <= y0 {
+            return;
+        }
+        let (x1, y1) = (x1 - 1, y1 - 1);
+
+        let border_cell = |character| CharCell {
+            character,
+            fg: color,
+            bg: Color::TRANSPARENT,
+        };
+
+        for x in x0..=x1 {
+            self.set_cell(x, y0, border_cell('─'));
+            self.set_cell(x, y1, border_cell('─'));
+        }
+        for y in y0..=y1 {
+            self.set_cell(x0, y, border_cell('│'));
+            self.set_cell(x1, y, border_cell('│'));
+        }
+        self.set_cell(x0, y0, border_cell('┌'));
+        self.set_cell(x1, y0, border_cell('┐'));
+        self.set_cell(x0, y1, border_cell('└'));
+        self.set_cell(x1, y1, border_cell('┘'));
+    }
+
+    pub fn draw_element(&mut self, element: &UIElement, container_area: DisplayArea) {
+        match element {
+            UIElement::Container(children) => {
+                for ui_element in children {
+                    // draw the inner widget
+                    self.draw_element(ui_element, container_area);
+                }
+            }
+            UIElement::Contained(inner_element, area) => {
+                self.draw_element(inner_element, area.map_onto(container_area));
+            }
+            UIElement::Bordered(inner_element, border_color) => {
+                self.draw_border(container_area, *border_color);
+
+                // the border takes up an entire cell on each side
+                let inner_area = DisplayArea(
+                    DisplayCoord::new(CELL_WIDTH.into(), CELL_HEIGHT.into()),
+                    DisplayCoord::new(
+                        DisplayUnits::from_mixed(-CELL_WIDTH, 1.0),
+                        DisplayUnits::from_mixed(-CELL_HEIGHT, 1.0),
+                    ),
+                )
+                .map_onto(container_area);
+
+                // draw the inner widget
+                self.draw_element(inner_element, inner_area);
+            }
+            UIElement::Backgrounded(inner_element, bg_color) => {
+                self.fill_rect(container_area, *bg_color);
+
+                // draw the inner widget
+                self.draw_element(inner_element, container_area);
+            }
+            UIElement::Text(text) => {
+                let ([x0, y0], [x1, y1]) = self.area_cells(container_area);
+                if y0 >= y1 {
+                    return;
+                }
+
+                for (x, character) in (x0..x1).zip(text.chars()) {
+                    self.set_cell(
+                        x,
+                        y0,
+                        CharCell {
+                            character,
+                            fg: Color::CYAN,
+                            bg: Color::TRANSPARENT,
+                        },
+                    );
+                }
+            }
+            UIElement::CharGrid(char_grid) => {
+                let ([x0, y0], [x1, y1]) = self.area_cells(container_area);
+
+                for (line_index, line) in char_grid.content.iter().enumerate() {
+                    let y = y0 + line_index;
+                    if y >= y1 {
+                        break;
+                    }
+
+                    for (col_index, char_cell) in line.iter().enumerate() {
+                        let x = x0 + col_index;
+                        if x >= x1 {
+                            break;
+                        }
+
+                        self.set_cell(x, y, *char_cell);
+                    }
+                }
+            }
+            UIElement::Nothing => {}
+        }
+    }
+}
+impl std::fmt::Display for CellBuffer {
+    /// Just the characters, one line per row
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (y, line) in self.lines.iter().enumerate() {
+            if y != 0 {
+                writeln!(f)?;
+            }
+            for cell in line {
+                write!(f, "{}", cell.character)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+//! Renders into memory instead of a window, so the manager can be tested without a compositor.
+//!
+//! Unlike the other backends, this one is always compiled, because it doesn't need any dependencies.
+//!
+//! Usage: make a `HeadlessDisplay` with a script, grab `snapshots()`, then run it however `UIDisplay` would be run.
+
+use crate::{
+    cell_buffer::CellBuffer,
+    display_units::DisplayArea,
+    ui_element::UIElement,
+    ui_event::{Key, KeyModifiers, UIEvent},
+    wakeup::Wakeup,
+};
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+pub const FRAME_RATE: f32 = 30.;
+pub const FRAME_DELTA_SECONDS: f32 = 1. / FRAME_RATE;
+/// How long the tabs get to answer the close at the end of the script, before closing again (which doesn't wait for them)
+const CLOSE_AGAIN_TIMEOUT: Duration = Duration::from_secs(3);
+/// How many frames without a redraw count as idle, for `ScriptStep::WaitForIdle`
+const IDLE_FRAMES: u32 = 10;
+/// When `ScriptStep::WaitForIdle` gives up, if the manager keeps redrawing (eg: a timer)
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub enum ScriptStep {
+    /// Push the event into the event queue, as if the user did it
+    UIEvent(UIEvent),
+    /// Keep rendering without doing anything (for timeouts; use `WaitForIdle` to let the manager respond)
+    Wait(Duration),
+    /// Keep rendering until nothing has been redrawn for `IDLE_FRAMES` frames
+    /// (eg: the tabs have started, or are done with the last event), or until `IDLE_TIMEOUT`
+    WaitForIdle,
+    /// Save what is currently rendered into the snapshots
+    Snapshot,
+}
+impl ScriptStep {
+    pub fn key_press(raw_code: u32, key_char: Option<char>, key_modifiers: KeyModifiers) -> Self {
+        Self::UIEvent(UIEvent::KeyPress(
+            Key::new(raw_code, key_char),
+            key_modifiers,
+        ))
+    }
+
+    /// Clicks in the middle of the cell at `[x, y]`
+    pub fn click_cell(cell: [usize; 2], size: [usize; 2]) -> Self {
+        Self::UIEvent(UIEvent::MousePress(
+            Self::cell_mouse_location(cell, size),
+            DisplayArea::FULL,
+        ))
+    }
+
+    /// Drags (with the button still held) to the middle of the cell at `[x, y]`
+    pub fn drag_to_cell(cell: [usize; 2], size: [usize; 2]) -> Self {
+        Self::UIEvent(UIEvent::MouseMove(
+            Self::cell_mouse_location(cell, size),
+            DisplayArea::FULL,
+        ))
+    }
+
+    /// Lets go of the button in the middle of the cell at `[x, y]`
+    pub fn release_cell(cell: [usize; 2], size: [usize; 2]) -> Self {
+        Self::UIEvent(UIEvent::MouseRelease(
+            Self::cell_mouse_location(cell, size),
+            DisplayArea::FULL,
+        ))
+    }
+
+    /// `[mouse location, window size]` in pixels, like the mouse events want
+    fn cell_mouse_location([x, y]: [usize; 2], size: [usize; 2]) -> [[u32; 2]; 2] {
+        use crate::cell_buffer::{CELL_HEIGHT, CELL_WIDTH};
+
+        let (cell_width, cell_height) = (CELL_WIDTH as u32, CELL_HEIGHT as u32);
+        [
+            [
+                x as u32 * cell_width + cell_width / 2,
+                y as u32 * cell_height + cell_height / 2,
+            ],
+            [size[0] as u32 * cell_width, size[1] as u32 * cell_height],
+        ]
+    }
+}
+
+pub struct HeadlessDisplay {
+    /// in cells, `[width, height]`
+    size: [usize; 2],
+    script: VecDeque<ScriptStep>,
+    snapshots: Arc<Mutex<Vec<CellBuffer>>>,
+}
+impl HeadlessDisplay {
+    pub fn new(size: [usize; 2], script: impl IntoIterator<Item = ScriptStep>) -> Self {
+        Self {
+            size,
+            script: script.into_iter().collect(),
+            snapshots: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Every `ScriptStep::Snapshot` gets pushed here, in order.
+    ///
+    /// NOTE: needs to be cloned before `run_display`, since that consumes the display
+    pub fn snapshots(&self) -> Arc<Mutex<Vec<CellBuffer>>> {
+        self.snapshots.clone()
+    }
+
+    pub fn render(&self, root_element: &UIElement) -> CellBuffer {
+        let mut frame = CellBuffer::new(self.size[0], self.size[1]);
+        frame.draw_element(root_element, DisplayArea::FULL);
+        frame
+    }
+
+    /// Same signature as `UIDisplay::run_display` (except for self).
+    ///
+    /// When the script is done, it closes like a window would (`UIEvent::CloseRequested`),
+    /// and again if the manager is still running after `CLOSE_AGAIN_TIMEOUT` (eg: a prompt no one answers).
+    ///
+    /// Returns when `is_running` is set to false, or after closing again.
+    pub fn run_display(
+        mut self,
+        root_element: Arc<Mutex<UIElement>>,
+        redraw_wakeup: Wakeup,
+        ui_event_queue: Arc<Mutex<Vec<UIEvent>>>,
+        ui_event_wakeup: Wakeup,
+        is_running: Arc<AtomicBool>,
+    ) {
+        let frame_delta = Duration::from_secs_f32(FRAME_DELTA_SECONDS);
+
+        // every backend tells the manager the size at the start
+        let window_px = CellBuffer::new(self.size[0], self.size[1]).window_px();
+        ui_event_queue
+            .lock()
+            .unwrap()
+            .push(UIEvent::WindowResized(window_px));
+        ui_event_wakeup.notify();
+
+        while is_running.load(Ordering::Relaxed) {
+            // one frame between each step, so that the manager has a chance to respond
+            thread::sleep(frame_delta);
+
+            let Some(script_step) = self.script.pop_front() else {
+                // script is over, so act like the window was closed
+                ui_event_queue.lock().unwrap().push(UIEvent::CloseRequested);
+                ui_event_wakeup.notify();
+
+                let deadline = Instant::now() + CLOSE_AGAIN_TIMEOUT;
+                while is_running.load(Ordering::Relaxed) && Instant::now() < deadline {
+                    thread::sleep(frame_delta);
+                }
+                if is_running.load(Ordering::Relaxed) {
+                    ui_event_queue.lock().unwrap().push(UIEvent::CloseRequested);
+                    ui_event_wakeup.notify();
+                }
+                break;
+            };
+
+            match script_step {
+                ScriptStep::UIEvent(ui_event) => {
+                    ui_event_queue.lock().unwrap().push(ui_event);
+                    ui_event_wakeup.notify();
+                }
+                ScriptStep::Wait(duration) => {
+                    thread::sleep(duration);
+                }
+                ScriptStep::WaitForIdle => {
+                    let deadline = Instant::now() + IDLE_TIMEOUT;
+                    // NOTE: the redraws from before this step count too, which is fine (it just waits a bit longer)
+                    while redraw_wakeup.wait_timeout(frame_delta * IDLE_FRAMES)
+                        && Instant::now() < deadline
+                    {}
+                }
+                ScriptStep::Snapshot => {
+                    let frame = self.render(&root_element.lock().unwrap());
+                    self.snapshots.lock().unwrap().push(frame);
+                }
+            }
+        }
+
+        println!("Graciously ending display loop.");
+    }
+}
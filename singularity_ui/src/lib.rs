@@ -1,16 +1,24 @@
+pub mod cell_buffer;
 pub mod color;
 pub mod display_units;
+pub mod headless_backend;
 pub mod task_logger;
 pub mod ui_element;
+pub mod ui_event;
+pub mod wakeup;
 
-#[cfg(feature = "wayland_backend")]
+// the terminal backend is never default, so if it is enabled, it was asked for
+#[cfg(all(feature = "wayland_backend", not(feature = "terminal_backend")))]
 mod wayland_backend;
-#[cfg(feature = "wayland_backend")]
-pub use wayland_backend::ui_event;
-#[cfg(feature = "wayland_backend")]
+#[cfg(all(feature = "wayland_backend", not(feature = "terminal_backend")))]
 pub use wayland_backend::UIDisplay;
 
-#[cfg(not(any(feature = "wayland_backend")))]
+#[cfg(feature = "terminal_backend")]
+mod terminal_backend;
+#[cfg(feature = "terminal_backend")]
+pub use terminal_backend::UIDisplay;
+
+#[cfg(not(any(feature = "wayland_backend", feature = "terminal_backend")))]
 compile_error!("need to choose a gui backend");
 
 #[cfg(test)]
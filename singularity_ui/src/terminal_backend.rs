@@ -0,0 +1,362 @@
+//! Renders to an ANSI terminal, so singularity can be used over ssh or in a plain linux console.
+//!
+//! See `cell_buffer` for how pixels are turned into terminal cells.
+//!
+//! NOTE: terminals only report Logo (Super) and tell Ctrl+Shift+letter apart from Ctrl+letter
+//! if they support the kitty keyboard protocol (kitty, foot, wezterm, ghostty, recent alacritty, ...).
+//! In the others, the `Logo+...` shortcuts can't be pressed, so they need other bindings in `keymap.json`,
+//! eg: `{ "manager.tile.focus_left": ["Logo+Left", "Ctrl+Alt+Left"] }`
+
+use crate::{
+    cell_buffer::{CellBuffer, CELL_HEIGHT, CELL_WIDTH},
+    ui_element::UIElement,
+    ui_event::UIEvent,
+    wakeup::Wakeup,
+};
+use crossterm::{cursor, event, execute, terminal};
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+pub const FRAME_RATE: f32 = 30.;
+pub const FRAME_DELTA_SECONDS: f32 = 1. / FRAME_RATE;
+
+pub struct UIDisplay {
+    root_element: Arc<Mutex<UIElement>>,
+    /// notified by the manager whenever `root_element` changed
+    redraw_wakeup: Wakeup,
+
+    ui_event_queue: Arc<Mutex<Vec<UIEvent>>>,
+    /// notified whenever an event is pushed or the display closes
+    ui_event_wakeup: Wakeup,
+
+    is_running: Arc<AtomicBool>,
+    /// in cells
+    width: u16,
+    /// in cells
+    height: u16,
+    /// What is currently on the terminal, so only the changed lines need to be redrawn.
+    /// None means everything needs to be redrawn.
+    previous_frame: Option<CellBuffer>,
+}
+impl UIDisplay {
+    /// Returns when display is closed.
+    pub fn run_display(
+        root_element: Arc<Mutex<UIElement>>,
+        redraw_wakeup: Wakeup,
+        ui_event_queue: Arc<Mutex<Vec<UIEvent>>>,
+        ui_event_wakeup: Wakeup,
+        is_running: Arc<AtomicBool>,
+    ) {
+        // reverts the terminal when dropped, even on panic
+        let _terminal_guard = TerminalGuard::enter().expect("failed to set up the terminal");
+
+        let (width, height) = terminal::size().expect("failed to get terminal size");
+
+        let mut ui_display = UIDisplay {
+            root_element,
+            redraw_wakeup,
+            ui_event_queue,
+            ui_event_wakeup,
+            is_running,
+            width,
+            height,
+            previous_frame: None,
+        };
+
+        ui_display.push_ui_event(UIEvent::WindowResized(ui_display.window_px()));
+
+        let mut stdout = io::stdout();
+        while ui_display.is_running.load(Ordering::Relaxed) {
+            // only when the manager changed something (or a resize cleared the screen)
+            if ui_display.redraw_wakeup.wait_timeout(Duration::ZERO)
+                || ui_display.previous_frame.is_none()
+            {
+                ui_display
+                    .draw(&mut stdout)
+                    .expect("failed to draw to terminal");
+            }
+
+            // wait at most one frame for input, then take everything that is ready
+            let mut timeout = Duration::from_secs_f32(FRAME_DELTA_SECONDS);
+            while event::poll(timeout).expect("failed to poll terminal events") {
+                let terminal_event = event::read().expect("failed to read terminal event");
+                ui_display.handle_terminal_event(terminal_event);
+
+                timeout = Duration::ZERO;
+            }
+        }
+        drop(_terminal_guard);
+
+        println!("Graciously ending display loop.");
+    }
+
+    /// The size of the terminal in "pixels"
+    fn window_px(&self) -> [u32; 2] {
+        [
+            self.width as u32 * CELL_WIDTH as u32,
+            self.height as u32 * CELL_HEIGHT as u32,
+        ]
+    }
+
+    fn push_ui_event(&self, ui_event: UIEvent) {
+        self.ui_event_queue.lock().unwrap().push(ui_event);
+        self.ui_event_wakeup.notify();
+    }
+
+    fn handle_terminal_event(&mut self, terminal_event: event::Event) {
+        use crate::display_units::DisplayArea;
+        use event::{KeyEventKind, MouseEventKind};
+
+        match terminal_event {
+            event::Event::Key(key_event) => {
+                if key_event.kind == KeyEventKind::Release {
+                    return;
+                }
+
+                if let Some((key, key_modifiers)) = input_conversion::convert_key_event(key_event) {
+                    self.push_ui_event(UIEvent::KeyPress(key, key_modifiers));
+                }
+            }
+            event::Event::Mouse(mouse_event) => {
+                // in the middle of the cell
+                let mouse_x = mouse_event.column as u32 * CELL_WIDTH as u32 + CELL_WIDTH as u32 / 2;
+                let mouse_y = mouse_event.row as u32 * CELL_HEIGHT as u32 + CELL_HEIGHT as u32 / 2;
+                let mouse_location = [[mouse_x, mouse_y], self.window_px()];
+
+                match mouse_event.kind {
+                    MouseEventKind::Down(_) => {
+                        self.push_ui_event(UIEvent::MousePress(mouse_location, DisplayArea::FULL));
+                    }
+                    MouseEventKind::Drag(_) => {
+                        self.push_ui_event(UIEvent::MouseMove(mouse_location, DisplayArea::FULL));
+                    }
+                    MouseEventKind::Up(_) => {
+                        self.push_ui_event(UIEvent::MouseRelease(
+                            mouse_location,
+                            DisplayArea::FULL,
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+            event::Event::Resize(width, height) => {
+                self.width = width;
+                self.height = height;
+                self.previous_frame = None;
+
+                self.push_ui_event(UIEvent::WindowResized(self.window_px()));
+            }
+            event::Event::FocusGained | event::Event::FocusLost | event::Event::Paste(_) => {}
+        }
+    }
+}
+
+/// Puts the terminal into raw mode and the alternate screen, and reverts it on drop
+struct TerminalGuard {
+    /// whether the keyboard enhancement flags were pushed, so they need to be popped
+    keyboard_enhanced: bool,
+}
+impl TerminalGuard {
+    fn enter() -> io::Result<Self> {
+        // needed for Logo and shifted shortcuts (see the module docs)
+        // NOTE: asked before the alternate screen, so that the warning is still there after exiting
+        let keyboard_enhanced = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if !keyboard_enhanced {
+            eprintln!("The terminal doesn't support the kitty keyboard protocol, so Logo shortcuts won't work");
+        }
+
+        terminal::enable_raw_mode()?;
+        execute!(
+            io::stdout(),
+            terminal::EnterAlternateScreen,
+            event::EnableMouseCapture,
+            cursor::Hide
+        )?;
+
+        // (the alternate screen has its own flags, so they are popped before leaving it)
+        if keyboard_enhanced {
+            execute!(
+                io::stdout(),
+                event::PushKeyboardEnhancementFlags(
+                    event::KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                        | event::KeyboardEnhancementFlags::REPORT_ALTERNATE_KEYS
+                )
+            )?;
+        }
+
+        Ok(Self { keyboard_enhanced })
+    }
+}
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        // can't do anything about errors here
+        if self.keyboard_enhanced {
+            let _ = execute!(io::stdout(), event::PopKeyboardEnhancementFlags);
+        }
+        let _ = execute!(
+            io::stdout(),
+            cursor::Show,
+            event::DisableMouseCapture,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+mod drawing_impls {
+    use super::UIDisplay;
+    use crate::{cell_buffer::CellBuffer, color::Color, display_units::DisplayArea};
+    use crossterm::{cursor, queue, style};
+    use std::io::{self, Write};
+
+    /// REVIEW: not sure how well terminals that only have 256 colors handle this
+    fn terminal_color(color: Color) -> style::Color {
+        if color.0[3] == 0 {
+            style::Color::Reset
+        } else {
+            style::Color::Rgb {
+                r: color.0[0],
+                g: color.0[1],
+                b: color.0[2],
+            }
+        }
+    }
+
+    /// Writes every line of `frame` that isn't the same as in `previous`
+    fn write_changes(
+        frame: &CellBuffer,
+        previous: Option<&CellBuffer>,
+        writer: &mut impl Write,
+    ) -> io::Result<()> {
+        for (y, line) in frame.lines().iter().enumerate() {
+            if previous.is_some_and(|previous| previous.lines().get(y) == Some(line)) {
+                continue;
+            }
+
+            queue!(writer, cursor::MoveTo(0, y as u16))?;
+
+            let mut current_colors = None;
+            for cell in line {
+                if current_colors != Some((cell.fg, cell.bg)) {
+                    queue!(
+                        writer,
+                        style::SetForegroundColor(terminal_color(cell.fg)),
+                        style::SetBackgroundColor(terminal_color(cell.bg))
+                    )?;
+                    current_colors = Some((cell.fg, cell.bg));
+                }
+
+                queue!(writer, style::Print(cell.character))?;
+            }
+        }
+
+        queue!(writer, style::ResetColor)?;
+        writer.flush()
+    }
+
+    impl UIDisplay {
+        pub fn draw(&mut self, writer: &mut impl Write) -> io::Result<()> {
+            let mut frame = CellBuffer::new(self.width as usize, self.height as usize);
+            frame.draw_element(&self.root_element.lock().unwrap(), DisplayArea::FULL);
+
+            if self.previous_frame.as_ref() == Some(&frame) {
+                // nothing changed
+                return Ok(());
+            }
+
+            write_changes(&frame, self.previous_frame.as_ref(), writer)?;
+            self.previous_frame = Some(frame);
+
+            Ok(())
+        }
+    }
+}
+
+/// Converting crossterm's input into our `UIEvent`s
+pub(crate) mod input_conversion {
+    use crate::ui_event::{Key, KeyModifiers};
+    use crossterm::event::{KeyCode, KeyEvent};
+
+    /// Layout of a US keyboard, in the order of the linux (evdev) keycodes
+    const UNSHIFTED_CHARS: &str = "1234567890-=qwertyuiop[]asdfghjkl;'`\\zxcvbnm,./";
+    const SHIFTED_CHARS: &str = "!@#$%^&*()_+QWERTYUIOP{}ASDFGHJKL:\"~|ZXCVBNM<>?";
+    const CHAR_RAW_CODES: [u32; 47] = [
+        2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, // number row
+        16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, // top row
+        30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, // home row
+        43, // backslash
+        44, 45, 46, 47, 48, 49, 50, 51, 52, 53, // bottom row
+    ];
+
+    /// Terminals only give characters, so guess the keycode from a US layout.
+    ///
+    /// Also returns whether shift needs to be held for the character,
+    /// because terminals don't reliably report shift for symbols.
+    fn char_raw_code(character: char) -> Option<(u32, bool)> {
+        if character == ' ' {
+            return Some((57, false));
+        }
+
+        if let Some(index) = UNSHIFTED_CHARS.chars().position(|c| c == character) {
+            Some((CHAR_RAW_CODES[index], false))
+        } else {
+            SHIFTED_CHARS
+                .chars()
+                .position(|c| c == character)
+                .map(|index| (CHAR_RAW_CODES[index], true))
+        }
+    }
+
+    pub fn convert_key_event(key_event: KeyEvent) -> Option<(Key, KeyModifiers)> {
+        use crossterm::event::KeyModifiers as TerminalModifiers;
+
+        let mut key_modifiers = KeyModifiers {
+            ctrl: key_event.modifiers.contains(TerminalModifiers::CONTROL),
+            alt: key_event.modifiers.contains(TerminalModifiers::ALT),
+            shift: key_event.modifiers.contains(TerminalModifiers::SHIFT),
+            caps_lock: false,
+            logo: key_event.modifiers.contains(TerminalModifiers::SUPER),
+            num_lock: false,
+        };
+
+        let key = match key_event.code {
+            KeyCode::Char(character) => {
+                // non-US characters still get typed, just without a meaningful raw code
+                let (raw_code, needs_shift) = char_raw_code(character).unwrap_or((0, false));
+                key_modifiers.shift |= needs_shift;
+
+                Key::new(raw_code, Some(character))
+            }
+            KeyCode::Esc => Key::new(1, None),
+            KeyCode::Backspace => Key::new(14, None),
+            KeyCode::Tab => Key::new(15, Some('\t')),
+            KeyCode::BackTab => {
+                key_modifiers.shift = true;
+                Key::new(15, Some('\t'))
+            }
+            KeyCode::Enter => Key::new(28, None),
+            KeyCode::Home => Key::new(102, None),
+            KeyCode::Up => Key::new(103, None),
+            KeyCode::PageUp => Key::new(104, None),
+            KeyCode::Left => Key::new(105, None),
+            KeyCode::Right => Key::new(106, None),
+            KeyCode::End => Key::new(107, None),
+            KeyCode::Down => Key::new(108, None),
+            KeyCode::PageDown => Key::new(109, None),
+            KeyCode::Insert => Key::new(110, None),
+            KeyCode::Delete => Key::new(111, None),
+            KeyCode::F(n @ 1..=10) => Key::new(58 + n as u32, None),
+            KeyCode::F(11) => Key::new(87, None),
+            KeyCode::F(12) => Key::new(88, None),
+            _ => return None,
+        };
+
+        Some((key, key_modifiers))
+    }
+}
@@ -10,3 +10,84 @@ pub fn units_mapping_test() {
         DisplayUnits::Pixels(19)
     );
 }
+
+#[test]
+pub fn cell_buffer_test() {
+    use crate::{
+        cell_buffer::CellBuffer, color::Color, display_units::DisplayArea, ui_element::UIElement,
+    };
+
+    let mut cell_buffer = CellBuffer::new(6, 3);
+    cell_buffer.draw_element(
+        &UIElement::Text("hi".to_string()).bordered(Color::LIGHT_GREEN),
+        DisplayArea::FULL,
+    );
+
+    assert_eq!(cell_buffer.to_string(), "┌────┐\n│hi  │\n└────┘");
+}
+
+#[cfg(feature = "terminal_backend")]
+#[test]
+pub fn terminal_key_conversion_test() {
+    use crate::{
+        terminal_backend::input_conversion::convert_key_event,
+        ui_event::{Key, KeyModifiers},
+    };
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers as TerminalModifiers};
+
+    let convert = |code, modifiers| convert_key_event(KeyEvent::new(code, modifiers));
+
+    // terminals don't always say that shift was held for symbols
+    assert_eq!(
+        convert(KeyCode::Char('!'), TerminalModifiers::NONE),
+        Some((Key::new(2, Some('!')), KeyModifiers::SHIFT))
+    );
+    assert_eq!(
+        convert(
+            KeyCode::Char('W'),
+            TerminalModifiers::CONTROL | TerminalModifiers::SHIFT
+        ),
+        Some((
+            Key::new(17, Some('W')),
+            KeyModifiers::CTRL | KeyModifiers::SHIFT
+        ))
+    );
+    assert_eq!(
+        convert(KeyCode::Char('w'), TerminalModifiers::NONE),
+        Some((Key::new(17, Some('w')), KeyModifiers::NONE))
+    );
+    // only reported with the keyboard enhancement flags
+    assert_eq!(
+        convert(KeyCode::Char('t'), TerminalModifiers::SUPER),
+        Some((Key::new(20, Some('t')), KeyModifiers::LOGO))
+    );
+
+    // Shift+Tab
+    assert_eq!(
+        convert(KeyCode::BackTab, TerminalModifiers::SHIFT),
+        Some((Key::new(15, Some('\t')), KeyModifiers::SHIFT))
+    );
+    assert_eq!(
+        convert(KeyCode::BackTab, TerminalModifiers::NONE),
+        Some((Key::new(15, Some('\t')), KeyModifiers::SHIFT))
+    );
+
+    // F11 and F12 aren't after F10 in the keycodes
+    assert_eq!(
+        convert(KeyCode::F(1), TerminalModifiers::NONE),
+        Some((Key::new(59, None), KeyModifiers::NONE))
+    );
+    assert_eq!(
+        convert(KeyCode::F(10), TerminalModifiers::NONE),
+        Some((Key::new(68, None), KeyModifiers::NONE))
+    );
+    assert_eq!(
+        convert(KeyCode::F(11), TerminalModifiers::NONE),
+        Some((Key::new(87, None), KeyModifiers::NONE))
+    );
+    assert_eq!(
+        convert(KeyCode::F(12), TerminalModifiers::NONE),
+        Some((Key::new(88, None), KeyModifiers::NONE))
+    );
+    assert_eq!(convert(KeyCode::F(13), TerminalModifiers::NONE), None);
+}
@@ -0,0 +1,158 @@
+use crate::display_units::DisplayArea;
+
+/// TODO: also, figure out a way to easily match keypresses and shortcuts
+///
+/// TODO: figure out a standard way of "forwarding" events to child
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum UIEvent {
+    KeyPress(Key, KeyModifiers),
+    WindowResized([u32; 2]),
+    /// ([mouse location [x, y], window size [w h]], container)
+    ///
+    /// REVIEW: definitely redundant, but might be helpful?
+    ///
+    /// NOTE: container should always be FULL for the outermost, but is helpful when trying to forward it to children:
+    /// the forwarded area should be: `child_area.map_onto(parent_area)`
+    MousePress([[u32; 2]; 2], DisplayArea),
+    /// Same as `MousePress`
+    ///
+    /// NOTE: backends only send this while a button is held, since it is only used for dragging
+    MouseMove([[u32; 2]; 2], DisplayArea),
+    /// Same as `MousePress`
+    MouseRelease([[u32; 2]; 2], DisplayArea),
+    /// The window's close button (or the like).
+    ///
+    /// NOTE: the display keeps running, the manager decides when to stop it (after asking the tabs)
+    CloseRequested,
+}
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct KeyModifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub caps_lock: bool,
+    pub logo: bool,
+    pub num_lock: bool,
+}
+
+/// Backend agnostic key.
+///
+/// This used to just be smithay's `KeyEvent`, but the terminal backend can't make those.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Key {
+    /// The linux (evdev) keycode, which is what wayland gives.
+    /// Other backends should translate to this.
+    ///
+    /// Eg: 1 is escape, 16 is Q, 28 is enter
+    pub raw_code: u32,
+    /// The character that the key represents (after modifiers like shift), if any
+    pub key_char: Option<char>,
+}
+impl Key {
+    pub const fn new(raw_code: u32, key_char: Option<char>) -> Self {
+        Self { raw_code, key_char }
+    }
+}
+
+pub trait KeyTrait {
+    fn to_alphabet(&self) -> Option<char>;
+    fn to_digit(&self) -> Option<u8>;
+    fn to_char(&self) -> Option<char>;
+}
+impl KeyTrait for Key {
+    fn to_alphabet(&self) -> Option<char> {
+        let c = self.to_char()?;
+        if c.is_ascii() {
+            Some(c)
+        } else {
+            None
+        }
+    }
+
+    fn to_digit(&self) -> Option<u8> {
+        let c = self.to_char()?;
+        c.to_digit(10).map(|c| c as u8)
+    }
+
+    fn to_char(&self) -> Option<char> {
+        if self.raw_code == 28 {
+            return Some('\n');
+        }
+        self.key_char
+    }
+}
+
+impl KeyModifiers {
+    pub const NONE: Self = KeyModifiers {
+        ctrl: false,
+        alt: false,
+        shift: false,
+        caps_lock: false,
+        logo: false,
+        num_lock: false,
+    };
+
+    pub const CTRL: Self = KeyModifiers {
+        ctrl: true,
+        alt: false,
+        shift: false,
+        caps_lock: false,
+        logo: false,
+        num_lock: false,
+    };
+
+    pub const ALT: Self = KeyModifiers {
+        ctrl: false,
+        alt: true,
+        shift: false,
+        caps_lock: false,
+        logo: false,
+        num_lock: false,
+    };
+
+    pub const SHIFT: Self = KeyModifiers {
+        ctrl: false,
+        alt: false,
+        shift: true,
+        caps_lock: false,
+        logo: false,
+        num_lock: false,
+    };
+
+    pub const LOGO: Self = KeyModifiers {
+        ctrl: false,
+        alt: false,
+        shift: false,
+        caps_lock: false,
+        logo: true,
+        num_lock: false,
+    };
+}
+impl std::ops::BitOr for KeyModifiers {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self {
+            ctrl: self.ctrl | rhs.ctrl,
+            alt: self.alt | rhs.alt,
+            shift: self.shift | rhs.shift,
+            caps_lock: self.caps_lock | rhs.caps_lock,
+            logo: self.logo | rhs.logo,
+            num_lock: self.num_lock | rhs.num_lock,
+        }
+    }
+}
+impl std::ops::BitAnd for KeyModifiers {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self {
+            ctrl: self.ctrl & rhs.ctrl,
+            alt: self.alt & rhs.alt,
+            shift: self.shift & rhs.shift,
+            caps_lock: self.caps_lock & rhs.caps_lock,
+            logo: self.logo & rhs.logo,
+            num_lock: self.num_lock & rhs.num_lock,
+        }
+    }
+}
@@ -0,0 +1,44 @@
+//! TODO: move this somewhere else (it's here because both the displays and the tabs need it)
+
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+
+/// Lets a thread sleep until some other thread has something for it to do.
+///
+/// Clones all share the same wakeup.
+/// Notifying before anyone waits isn't lost: the next wait just returns immediately.
+#[derive(Debug, Clone, Default)]
+pub struct Wakeup(Arc<(Mutex<bool>, Condvar)>);
+impl Wakeup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn notify(&self) {
+        let (notified, condvar) = &*self.0;
+        *notified.lock().unwrap() = true;
+        condvar.notify_all();
+    }
+
+    /// Blocks until notified
+    pub fn wait(&self) {
+        let (notified, condvar) = &*self.0;
+        let mut notified = condvar
+            .wait_while(notified.lock().unwrap(), |notified| !*notified)
+            .unwrap();
+        *notified = false;
+    }
+
+    /// Blocks until notified or until the timeout runs out.
+    ///
+    /// Returns whether it was notified.
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let (notified, condvar) = &*self.0;
+        let (mut notified, _) = condvar
+            .wait_timeout_while(notified.lock().unwrap(), timeout, |notified| !*notified)
+            .unwrap();
+        std::mem::take(&mut *notified)
+    }
+}
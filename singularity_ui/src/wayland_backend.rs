@@ -1,4 +1,6 @@
 use crate::ui_element::UIElement;
+use crate::ui_event::{KeyModifiers, UIEvent};
+use crate::wakeup::Wakeup;
 use font_kit::{font::Font, source::SystemSource};
 use smithay_client_toolkit::{
     activation::{ActivationState, RequestData},
@@ -29,7 +31,6 @@ use std::{
     },
     time::Duration,
 };
-use ui_event::{KeyModifiers, UIEvent};
 use wayland_client::{
     globals::registry_queue_init,
     protocol::{wl_keyboard, wl_pointer},
@@ -41,8 +42,16 @@ pub const FRAME_DELTA_SECONDS: f32 = 1. / FRAME_RATE;
 
 pub struct UIDisplay {
     root_element: Arc<Mutex<UIElement>>,
+    /// notified by the manager whenever `root_element` changed
+    redraw_wakeup: Wakeup,
+    /// set by `redraw_wakeup` and resizes, cleared by `draw`
+    needs_redraw: bool,
+    /// a frame callback was requested and hasn't come yet, so drawing waits for it
+    frame_pending: bool,
 
     ui_event_queue: Arc<Mutex<Vec<UIEvent>>>,
+    /// notified whenever an event is pushed or the display closes
+    ui_event_wakeup: Wakeup,
 
     registry_state: RegistryState,
     seat_state: SeatState,
@@ -62,6 +71,8 @@ pub struct UIDisplay {
     keyboard: Option<wl_keyboard::WlKeyboard>,
     key_modifiers: KeyModifiers,
     pointer: Option<wl_pointer::WlPointer>,
+    /// how many pointer buttons are held, motion is only sent while dragging
+    pressed_buttons: u32,
     loop_handle: LoopHandle<'static, UIDisplay>,
     font: Font,
 }
@@ -69,7 +80,9 @@ impl UIDisplay {
     /// Returns when display is closed.
     pub fn run_display(
         root_element: Arc<Mutex<UIElement>>,
+        redraw_wakeup: Wakeup,
         ui_event_queue: Arc<Mutex<Vec<UIEvent>>>,
+        ui_event_wakeup: Wakeup,
         is_running: Arc<AtomicBool>,
     ) {
         // All Wayland apps start by connecting the compositor (server).
@@ -135,7 +148,11 @@ impl UIDisplay {
 
         let mut ui_display = UIDisplay {
             root_element,
+            redraw_wakeup,
+            needs_redraw: true,
+            frame_pending: false,
             ui_event_queue,
+            ui_event_wakeup,
 
             // Seats and outputs may be hotplugged at runtime, therefore we need to setup a registry state to
             // listen for seats and outputs.
@@ -156,6 +173,7 @@ impl UIDisplay {
             keyboard: None,
             key_modifiers: KeyModifiers::default(),
             pointer: None,
+            pressed_buttons: 0,
             loop_handle: event_loop.handle(),
             font: SystemSource::new()
                 .select_best_match(
@@ -176,9 +194,23 @@ impl UIDisplay {
                     &mut ui_display,
                 )
                 .unwrap();
+
+            // only redraw when the manager changed something, not on every frame
+            if ui_display.redraw_wakeup.wait_timeout(Duration::ZERO) {
+                ui_display.needs_redraw = true;
+            }
+            // otherwise `frame` draws once the compositor is ready for it
+            if ui_display.needs_redraw && !ui_display.frame_pending && !ui_display.first_configure {
+                ui_display.draw(&conn, &qh);
+            }
         }
         println!("Graciously ending display loop.");
     }
+
+    fn push_ui_event(&self, ui_event: UIEvent) {
+        self.ui_event_queue.lock().unwrap().push(ui_event);
+        self.ui_event_wakeup.notify();
+    }
 }
 mod drawing_impls {
     use super::UIDisplay;
@@ -409,10 +441,12 @@ mod drawing_impls {
                 .wl_surface()
                 .damage_buffer(0, 0, self.width as i32, self.height as i32);
 
-            // Request our next frame
+            // so that the next redraw waits until the compositor has shown this one
             self.window
                 .wl_surface()
                 .frame(qh, self.window.wl_surface().clone());
+            self.frame_pending = true;
+            self.needs_redraw = false;
 
             // Attach and commit to present.
             buffer
@@ -423,11 +457,9 @@ mod drawing_impls {
     }
 }
 mod ui_display_wayland_impls {
-    use super::{
-        ui_event::{Key, KeyModifiers},
-        UIDisplay,
-    };
+    use super::UIDisplay;
     use crate::display_units::DisplayArea;
+    use crate::ui_event::{KeyModifiers, UIEvent};
     use smithay_client_toolkit::{
         activation::{ActivationHandler, RequestData},
         compositor::CompositorHandler,
@@ -438,7 +470,7 @@ mod ui_display_wayland_impls {
         registry::{ProvidesRegistryState, RegistryState},
         registry_handlers,
         seat::{
-            keyboard::{KeyboardHandler, Keysym, Modifiers},
+            keyboard::{KeyEvent, KeyboardHandler, Keysym, Modifiers},
             pointer::{PointerEvent, PointerHandler},
             Capability, SeatHandler, SeatState,
         },
@@ -481,7 +513,10 @@ mod ui_display_wayland_impls {
             _surface: &wl_surface::WlSurface,
             _time: u32,
         ) {
-            self.draw(conn, qh);
+            self.frame_pending = false;
+            if self.needs_redraw {
+                self.draw(conn, qh);
+            }
         }
 
         fn surface_enter(
@@ -537,8 +572,7 @@ mod ui_display_wayland_impls {
 
     impl WindowHandler for UIDisplay {
         fn request_close(&mut self, _: &Connection, _: &QueueHandle<Self>, _: &Window) {
-            self.is_running
-                .store(false, std::sync::atomic::Ordering::Relaxed);
+            self.push_ui_event(UIEvent::CloseRequested);
         }
 
         /// Called on first spawn and resize
@@ -554,13 +588,9 @@ mod ui_display_wayland_impls {
             self.width = configure.new_size.0.map(|v| v.get()).unwrap_or(256);
             self.height = configure.new_size.1.map(|v| v.get()).unwrap_or(256);
 
-            self.ui_event_queue
-                .lock()
-                .unwrap()
-                .push(super::ui_event::UIEvent::WindowResized([
-                    self.width,
-                    self.height,
-                ]));
+            self.push_ui_event(UIEvent::WindowResized([self.width, self.height]));
+            // the buffer is gone, so this can't wait for the manager
+            self.needs_redraw = true;
 
             // Initiate the first draw.
             if self.first_configure {
@@ -673,7 +703,7 @@ mod ui_display_wayland_impls {
             _qh: &QueueHandle<Self>,
             _: &wl_keyboard::WlKeyboard,
             _: u32,
-            event: Key,
+            event: KeyEvent,
         ) {
             if self.key_modifiers.caps_lock {
                 // dbg when caps lock is on
@@ -682,13 +712,7 @@ mod ui_display_wayland_impls {
                 dbg!(&event.keysym.key_char());
                 dbg!(&self.key_modifiers);
             }
-            self.ui_event_queue
-                .lock()
-                .unwrap()
-                .push(super::ui_event::UIEvent::KeyPress(
-                    event,
-                    self.key_modifiers,
-                ));
+            self.push_ui_event(UIEvent::KeyPress(event.into(), self.key_modifiers));
         }
 
         fn release_key(
@@ -697,7 +721,7 @@ mod ui_display_wayland_impls {
             _: &QueueHandle<Self>,
             _: &wl_keyboard::WlKeyboard,
             _: u32,
-            _event: Key,
+            _event: KeyEvent,
         ) {
         }
 
@@ -734,24 +758,43 @@ mod ui_display_wayland_impls {
                         // println!("Pointer entered @{:?}", event.position);
                     }
                     PointerEventKind::Leave { .. } => {
+                        // releases outside of the window aren't sent
+                        self.pressed_buttons = 0;
                         // println!("Pointer left");
                     }
-                    PointerEventKind::Motion { .. } => {}
-                    PointerEventKind::Press { .. } => {
-                        self.ui_event_queue.lock().unwrap().push(
-                            super::ui_event::UIEvent::MousePress(
+                    PointerEventKind::Motion { .. } => {
+                        if self.pressed_buttons > 0 {
+                            self.push_ui_event(UIEvent::MouseMove(
                                 [
                                     [event.position.0 as u32, event.position.1 as u32],
                                     [self.width, self.height],
                                 ],
                                 DisplayArea::FULL,
-                            ),
-                        );
+                            ));
+                        }
+                    }
+                    PointerEventKind::Press { .. } => {
+                        self.pressed_buttons += 1;
+                        self.push_ui_event(UIEvent::MousePress(
+                            [
+                                [event.position.0 as u32, event.position.1 as u32],
+                                [self.width, self.height],
+                            ],
+                            DisplayArea::FULL,
+                        ));
 
                         // println!("Press {:x} @ {:?}", button, event.position);
                         // self.shift = self.shift.xor(Some(0));
                     }
                     PointerEventKind::Release { .. } => {
+                        self.pressed_buttons = self.pressed_buttons.saturating_sub(1);
+                        self.push_ui_event(UIEvent::MouseRelease(
+                            [
+                                [event.position.0 as u32, event.position.1 as u32],
+                                [self.width, self.height],
+                            ],
+                            DisplayArea::FULL,
+                        ));
                         // println!("Release {:x} @ {:?}", button, event.position);
                     }
                     PointerEventKind::Axis { .. } => {
@@ -790,113 +833,15 @@ mod ui_display_wayland_impls {
     }
 }
 
-pub mod ui_event {
+/// conversions from smithay's types into our backend agnostic types
+mod ui_event_conversion_impls {
+    use crate::ui_event::{Key, KeyModifiers};
     use smithay_client_toolkit::seat::keyboard;
 
-    use crate::display_units::DisplayArea;
-
-    /// TODO: not great that I am reexporting smithay's event, given that the goal is to be backend agnostic.
-    /// I am doing it right now because I'd rather get something working sooner, even if I have to compromise a bit
-    ///
-    /// TODO: also, figure out a way to easily match keypresses and shortcuts
-    ///
-    /// TODO: figure out a standard way of "forwarding" events to child
-    #[derive(Debug, Clone)]
-    pub enum UIEvent {
-        KeyPress(Key, KeyModifiers),
-        WindowResized([u32; 2]),
-        /// ([mouse location [x, y], window size [w h]], container)
-        ///
-        /// REVIEW: definitely redundant, but might be helpful?
-        ///
-        /// NOTE: container should always be FULL for the outermost, but is helpful when trying to forward it to children:
-        /// the forwarded area should be: `child_area.map_onto(parent_area)`
-        MousePress([[u32; 2]; 2], DisplayArea),
-    }
-    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
-    pub struct KeyModifiers {
-        pub ctrl: bool,
-        pub alt: bool,
-        pub shift: bool,
-        pub caps_lock: bool,
-        pub logo: bool,
-        pub num_lock: bool,
-    }
-    pub type Key = keyboard::KeyEvent;
-
-    pub trait KeyTrait {
-        fn to_alphabet(&self) -> Option<char>;
-        fn to_digit(&self) -> Option<u8>;
-        fn to_char(&self) -> Option<char>;
-    }
-    impl KeyTrait for Key {
-        fn to_alphabet(&self) -> Option<char> {
-            let c = self.to_char()?;
-            if c.is_ascii() {
-                Some(c)
-            } else {
-                None
-            }
-        }
-
-        fn to_digit(&self) -> Option<u8> {
-            let c = self.to_char()?;
-            c.to_digit(10).map(|c| c as u8)
+    impl From<keyboard::KeyEvent> for Key {
+        fn from(key_event: keyboard::KeyEvent) -> Self {
+            Key::new(key_event.raw_code, key_event.keysym.key_char())
         }
-
-        fn to_char(&self) -> Option<char> {
-            if self.raw_code == 28 {
-                return Some('\n');
-            }
-            self.keysym.key_char()
-        }
-    }
-
-    impl KeyModifiers {
-        pub const NONE: Self = KeyModifiers {
-            ctrl: false,
-            alt: false,
-            shift: false,
-            caps_lock: false,
-            logo: false,
-            num_lock: false,
-        };
-
-        pub const CTRL: Self = KeyModifiers {
-            ctrl: true,
-            alt: false,
-            shift: false,
-            caps_lock: false,
-            logo: false,
-            num_lock: false,
-        };
-
-        pub const ALT: Self = KeyModifiers {
-            ctrl: false,
-            alt: true,
-            shift: false,
-            caps_lock: false,
-            logo: false,
-            num_lock: false,
-        };
-
-        pub const SHIFT: Self = KeyModifiers {
-            ctrl: false,
-            alt: false,
-            shift: true,
-            caps_lock: false,
-            logo: false,
-            num_lock: false,
-        };
-
-        pub const LOGO: Self = KeyModifiers {
-            ctrl: false,
-            alt: false,
-            shift: false,
-            caps_lock: false,
-            logo: true,
-            num_lock: false,
-        };
     }
     impl From<keyboard::Modifiers> for KeyModifiers {
         fn from(
@@ -919,32 +864,4 @@ pub mod ui_event {
             }
         }
     }
-    impl std::ops::BitOr for KeyModifiers {
-        type Output = Self;
-
-        fn bitor(self, rhs: Self) -> Self::Output {
-            Self {
-                ctrl: self.ctrl | rhs.ctrl,
-                alt: self.alt | rhs.alt,
-                shift: self.shift | rhs.shift,
-                caps_lock: self.caps_lock | rhs.caps_lock,
-                logo: self.logo | rhs.logo,
-                num_lock: self.num_lock | rhs.num_lock,
-            }
-        }
-    }
-    impl std::ops::BitAnd for KeyModifiers {
-        type Output = Self;
-
-        fn bitand(self, rhs: Self) -> Self::Output {
-            Self {
-                ctrl: self.ctrl & rhs.ctrl,
-                alt: self.alt & rhs.alt,
-                shift: self.shift & rhs.shift,
-                caps_lock: self.caps_lock & rhs.caps_lock,
-                logo: self.logo & rhs.logo,
-                num_lock: self.num_lock & rhs.num_lock,
-            }
-        }
-    }
 }